@@ -0,0 +1,1105 @@
+#[allow(clippy::module_inception)]
+pub mod assembler {
+    use crate::memory::memory::{AddressingMode, Bus, Byte, Word};
+    use crate::{CpuVariant, Instruction, Register};
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+
+    // Source position and reason of an assembly failure
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct AssemblerError {
+        pub line: usize,
+        pub message: String,
+    }
+
+    impl fmt::Display for AssemblerError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return write!(f, "line {}: {}", self.line, self.message);
+        }
+    }
+
+    impl std::error::Error for AssemblerError {}
+
+    // Assembled bytes, one segment per contiguous run of output, and the
+    // final value of every label and constant. Local labels are stored as
+    // `global@local`
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Assembly {
+        pub segments: Vec<(Word, Vec<Byte>)>,
+        pub labels: HashMap<String, Word>,
+    }
+
+    impl Assembly {
+        pub fn write_to<B: Bus>(&self, memory: &mut B) {
+            for (start, bytes) in &self.segments {
+                for (i, byte) in bytes.iter().enumerate() {
+                    memory.write_byte(start.wrapping_add(i as Word), *byte);
+                }
+            }
+        }
+
+        pub fn get_label(&self, name: &str) -> Option<Word> {
+            return self.labels.get(name).copied();
+        }
+    }
+
+    // Assembles ca65/DASM flavoured source for the instruction set of
+    // variant. Supported syntax:
+    //   label:  /  label at column 0     global label, opens a new scope
+    //   @label:                          local label, valid until the next
+    //                                    global label
+    //   NAME = expr  /  NAME equ expr    constant
+    //   .org expr  /  * = expr           set the program counter
+    //   .byte / .db  expr or "string", ...
+    //   .word / .dw  expr, ...
+    // Expressions know $hex, %binary, decimal and 'c' literals, `*` for the
+    // current address, unary - ~ < (low byte) > (high byte) and the binary
+    // operators * / % + - << >> & ^ | with C precedence. Zero page
+    // addressing is picked when the operand is known to fit in the first
+    // pass, forward references always assemble as absolute
+    pub fn assemble(source: &str, variant: CpuVariant) -> Result<Assembly, AssemblerError> {
+        let mut assembler = Assembler::new(variant);
+        assembler.first_pass(source)?;
+
+        return assembler.second_pass();
+    }
+
+    // Assembles source and writes the result into memory
+    pub fn assemble_into<B: Bus>(
+        source: &str,
+        memory: &mut B,
+        variant: CpuVariant,
+    ) -> Result<Assembly, AssemblerError> {
+        let assembly = assemble(source, variant)?;
+        assembly.write_to(memory);
+
+        return Ok(assembly);
+    }
+
+    enum Operand {
+        None,
+        Accumulator,
+        Immediate(String),
+        Indirect(String),
+        IndirectX(String),
+        IndirectY(String),
+        Direct(String),
+        Indexed(String, Register),
+        Pair(String, String),
+    }
+
+    enum Kind {
+        Instruction {
+            opcode: Byte,
+            mode: AddressingMode,
+            operands: Vec<String>,
+        },
+        Bytes(Vec<String>),
+        Words(Vec<String>),
+    }
+
+    struct Statement {
+        line: usize,
+        scope: String,
+        address: Word,
+        kind: Kind,
+    }
+
+    struct Constant {
+        line: usize,
+        scope: String,
+        address: Word,
+        name: String,
+        expr: String,
+    }
+
+    enum ExprError {
+        Undefined(String),
+        Syntax(String),
+    }
+
+    struct Assembler {
+        variant: CpuVariant,
+        opcodes: HashMap<(String, AddressingMode, Register), Byte>,
+        mnemonics: HashSet<String>,
+        symbols: HashMap<String, Word>,
+        statements: Vec<Statement>,
+        // Constants whose value was not known during the first pass
+        constants: Vec<Constant>,
+    }
+
+    impl Assembler {
+        fn new(variant: CpuVariant) -> Self {
+            let mut opcodes = HashMap::new();
+            let mut mnemonics = HashSet::new();
+            for opcode in 0..=0xffu8 {
+                let instruction = Instruction::decode(opcode, variant);
+                if instruction == Instruction::INVALID {
+                    continue;
+                }
+                let (mode, register) = instruction.addressing_mode();
+                let key = (instruction.mnemonic(), mode, register);
+                // Prefer the canonical encoding of instructions with aliases
+                if u8::from(instruction) == opcode || !opcodes.contains_key(&key) {
+                    opcodes.insert(key, opcode);
+                }
+                mnemonics.insert(instruction.mnemonic());
+            }
+
+            return Assembler {
+                variant,
+                opcodes,
+                mnemonics,
+                symbols: HashMap::new(),
+                statements: Vec::new(),
+                constants: Vec::new(),
+            };
+        }
+
+        fn first_pass(&mut self, source: &str) -> Result<(), AssemblerError> {
+            let mut pc: u32 = 0;
+            let mut scope = String::new();
+
+            for (i, raw) in source.lines().enumerate() {
+                let line = i + 1;
+                let error = |message: String| AssemblerError { line, message };
+                let mut text = strip_comment(raw);
+
+                // Constants and program counter assignment
+                if let Some((name, expr)) = split_assignment(text) {
+                    if name == "*" {
+                        pc = self.evaluate_known(expr, &scope, pc, line)?;
+                    } else {
+                        self.define_constant(name, expr, &scope, pc, line)?;
+                    }
+                    continue;
+                }
+
+                // Labels, with a colon anywhere or without one at column 0
+                let mut at_column_0 = !raw.starts_with(char::is_whitespace);
+                loop {
+                    let trimmed = text.trim_start();
+                    let name_len = trimmed
+                        .find(|c: char| !is_identifier_char(c))
+                        .unwrap_or(trimmed.len());
+                    let name = &trimmed[..name_len];
+                    if name.is_empty() || name.starts_with('.') {
+                        break;
+                    }
+                    let rest = &trimmed[name_len..];
+                    if let Some(rest) = rest.strip_prefix(':') {
+                        text = rest;
+                    } else if at_column_0 && !self.mnemonics.contains(&name.to_uppercase()) {
+                        text = rest;
+                    } else {
+                        break;
+                    }
+                    at_column_0 = false;
+
+                    if !name.starts_with('@') {
+                        scope = name.to_string();
+                    }
+                    let name = qualify(name, &scope);
+                    if self.symbols.insert(name.clone(), pc as Word).is_some() {
+                        return Err(error(format!("duplicate label {}", name)));
+                    }
+                }
+
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                let (word, rest) = match text.find(char::is_whitespace) {
+                    Some(index) => (&text[..index], text[index..].trim()),
+                    None => (text, ""),
+                };
+
+                let (kind, size) = match word.to_lowercase().as_str() {
+                    ".org" => {
+                        pc = self.evaluate_known(rest, &scope, pc, line)?;
+                        continue;
+                    }
+                    ".byte" | ".db" => {
+                        let items = split_list(rest);
+                        let mut size = 0;
+                        for item in &items {
+                            size += match parse_string(item) {
+                                Some(string) => string.len() as u32,
+                                None => 1,
+                            };
+                        }
+                        (Kind::Bytes(items), size)
+                    }
+                    ".word" | ".dw" => {
+                        let items = split_list(rest);
+                        let size = 2 * items.len() as u32;
+                        (Kind::Words(items), size)
+                    }
+                    _ => {
+                        let mnemonic = word.to_uppercase();
+                        if !self.mnemonics.contains(&mnemonic) {
+                            return Err(error(format!("unknown instruction {}", word)));
+                        }
+                        let operand = parse_operand(rest);
+                        let (opcode, mode, operands) =
+                            self.resolve(&mnemonic, operand, &scope, pc, line)?;
+                        let size = Instruction::decode(opcode, self.variant).length() as u32;
+                        let kind = Kind::Instruction {
+                            opcode,
+                            mode,
+                            operands,
+                        };
+                        (kind, size)
+                    }
+                };
+
+                if pc + size > 0x10000 {
+                    return Err(error("program counter overflow".to_string()));
+                }
+                self.statements.push(Statement {
+                    line,
+                    scope: scope.clone(),
+                    address: pc as Word,
+                    kind,
+                });
+                pc += size;
+            }
+
+            return Ok(());
+        }
+
+        fn second_pass(mut self) -> Result<Assembly, AssemblerError> {
+            // Constants may refer to each other in any order, keep
+            // evaluating until a round makes no progress
+            while !self.constants.is_empty() {
+                let pending = self.constants.len();
+                let mut unresolved = Vec::new();
+                for constant in std::mem::take(&mut self.constants) {
+                    match self.evaluate(&constant.expr, &constant.scope, constant.address) {
+                        Ok(value) => {
+                            self.symbols.insert(constant.name, value as Word);
+                        }
+                        Err(_) => unresolved.push(constant),
+                    }
+                }
+                if unresolved.len() == pending {
+                    let constant = &unresolved[0];
+                    let line = constant.line;
+                    let value = self.evaluate(&constant.expr, &constant.scope, constant.address);
+                    return Err(self.expression_error(value.unwrap_err(), line));
+                }
+                self.constants = unresolved;
+            }
+
+            let mut assembly = Assembly {
+                segments: Vec::new(),
+                labels: HashMap::new(),
+            };
+            for statement in &self.statements {
+                let bytes = self.encode(statement)?;
+                match assembly.segments.last_mut() {
+                    Some((start, data))
+                        if *start as usize + data.len() == statement.address as usize =>
+                    {
+                        data.extend(bytes);
+                    }
+                    _ => assembly.segments.push((statement.address, bytes)),
+                }
+            }
+            assembly.segments.retain(|(_, data)| !data.is_empty());
+            assembly.labels = self.symbols;
+
+            return Ok(assembly);
+        }
+
+        fn define_constant(
+            &mut self,
+            name: &str,
+            expr: &str,
+            scope: &str,
+            pc: u32,
+            line: usize,
+        ) -> Result<(), AssemblerError> {
+            let name = qualify(name, scope);
+            if self.symbols.contains_key(&name) {
+                return Err(AssemblerError {
+                    line,
+                    message: format!("duplicate label {}", name),
+                });
+            }
+
+            match self.evaluate(expr, scope, pc as Word) {
+                Ok(value) => {
+                    self.symbols.insert(name, value as Word);
+                }
+                Err(ExprError::Undefined(_)) => self.constants.push(Constant {
+                    line,
+                    scope: scope.to_string(),
+                    address: pc as Word,
+                    name,
+                    expr: expr.to_string(),
+                }),
+                Err(error) => return Err(self.expression_error(error, line)),
+            }
+
+            return Ok(());
+        }
+
+        // Picks opcode and addressing mode for an instruction. Operands
+        // that are already known select zero page when they fit
+        fn resolve(
+            &self,
+            mnemonic: &str,
+            operand: Operand,
+            scope: &str,
+            pc: u32,
+            line: usize,
+        ) -> Result<(Byte, AddressingMode, Vec<String>), AssemblerError> {
+            let fits_zero_page = |expr: &str| match self.evaluate(expr, scope, pc as Word) {
+                Ok(value) => (0..=0xff).contains(&value),
+                Err(_) => false,
+            };
+            let direct = |expr: &str, register: Register| {
+                let (zero_page, absolute) = match register {
+                    Register::None => (AddressingMode::ZeroPage, AddressingMode::Absolute),
+                    _ => (AddressingMode::ZeroPageReg, AddressingMode::AbsoluteReg),
+                };
+                if fits_zero_page(expr) {
+                    return vec![(zero_page, register), (absolute, register)];
+                }
+                return vec![(absolute, register), (zero_page, register)];
+            };
+
+            let (candidates, operands) = match operand {
+                Operand::None => (
+                    vec![
+                        (AddressingMode::Implied, Register::None),
+                        (AddressingMode::Accumulator, Register::None),
+                    ],
+                    vec![],
+                ),
+                Operand::Accumulator => {
+                    (vec![(AddressingMode::Accumulator, Register::None)], vec![])
+                }
+                Operand::Immediate(expr) => (
+                    vec![(AddressingMode::Immediate, Register::None)],
+                    vec![expr],
+                ),
+                Operand::Indirect(expr) => (
+                    vec![
+                        (AddressingMode::Indirect, Register::None),
+                        (AddressingMode::ZeroPageIndirect, Register::None),
+                    ],
+                    vec![expr],
+                ),
+                Operand::IndirectX(expr) => (
+                    vec![
+                        (AddressingMode::IndirectX, Register::X),
+                        (AddressingMode::AbsoluteIndirectX, Register::X),
+                    ],
+                    vec![expr],
+                ),
+                Operand::IndirectY(expr) => {
+                    (vec![(AddressingMode::IndirectY, Register::Y)], vec![expr])
+                }
+                Operand::Direct(expr) => {
+                    let mut candidates = vec![(AddressingMode::Relative, Register::None)];
+                    candidates.extend(direct(&expr, Register::None));
+                    (candidates, vec![expr])
+                }
+                Operand::Indexed(expr, register) => (direct(&expr, register), vec![expr]),
+                Operand::Pair(first, second) => (
+                    vec![(AddressingMode::ZeroPageRelative, Register::None)],
+                    vec![first, second],
+                ),
+            };
+
+            for (mode, register) in candidates {
+                if let Some(opcode) = self.opcodes.get(&(mnemonic.to_string(), mode, register)) {
+                    return Ok((*opcode, mode, operands));
+                }
+            }
+
+            return Err(AssemblerError {
+                line,
+                message: format!("invalid addressing mode for {}", mnemonic),
+            });
+        }
+
+        fn encode(&self, statement: &Statement) -> Result<Vec<Byte>, AssemblerError> {
+            let line = statement.line;
+            let value = |expr: &str| {
+                return self
+                    .evaluate(expr, &statement.scope, statement.address)
+                    .map_err(|error| self.expression_error(error, line));
+            };
+            let out_of_range = |what: &str, expr: &str| AssemblerError {
+                line,
+                message: format!("{} out of range: {}", what, expr),
+            };
+            let byte = |expr: &str, min: i64| {
+                let value = value(expr)?;
+                if !(min..=0xff).contains(&value) {
+                    return Err(out_of_range("byte", expr));
+                }
+                return Ok(value as Byte);
+            };
+            let word = |expr: &str, min: i64| {
+                let value = value(expr)?;
+                if !(min..=0xffff).contains(&value) {
+                    return Err(out_of_range("word", expr));
+                }
+                return Ok((value as Word).to_le_bytes());
+            };
+            let offset = |expr: &str, next: i64| {
+                let offset = value(expr)? - next;
+                if !(-128..=127).contains(&offset) {
+                    return Err(out_of_range("branch", expr));
+                }
+                return Ok(offset as Byte);
+            };
+
+            let mut bytes = Vec::new();
+            match &statement.kind {
+                Kind::Instruction {
+                    opcode,
+                    mode,
+                    operands,
+                } => {
+                    bytes.push(*opcode);
+                    let next = statement.address as i64 + 2;
+                    match mode {
+                        AddressingMode::Implied | AddressingMode::Accumulator => {}
+                        AddressingMode::Immediate => bytes.push(byte(&operands[0], -0x80)?),
+                        AddressingMode::ZeroPage
+                        | AddressingMode::ZeroPageReg
+                        | AddressingMode::IndirectX
+                        | AddressingMode::IndirectY
+                        | AddressingMode::ZeroPageIndirect => bytes.push(byte(&operands[0], 0)?),
+                        AddressingMode::Absolute
+                        | AddressingMode::AbsoluteReg
+                        | AddressingMode::Indirect
+                        | AddressingMode::AbsoluteIndirectX => bytes.extend(word(&operands[0], 0)?),
+                        AddressingMode::Relative => bytes.push(offset(&operands[0], next)?),
+                        AddressingMode::ZeroPageRelative => {
+                            bytes.push(byte(&operands[0], 0)?);
+                            bytes.push(offset(&operands[1], next + 1)?);
+                        }
+                    }
+                }
+                Kind::Bytes(items) => {
+                    for item in items {
+                        match parse_string(item) {
+                            Some(string) => bytes.extend(string.bytes()),
+                            None => bytes.push(byte(item, -0x80)?),
+                        }
+                    }
+                }
+                Kind::Words(items) => {
+                    for item in items {
+                        bytes.extend(word(item, -0x8000)?);
+                    }
+                }
+            }
+
+            return Ok(bytes);
+        }
+
+        // Evaluates an expression that has to be known in the first pass
+        fn evaluate_known(
+            &self,
+            expr: &str,
+            scope: &str,
+            pc: u32,
+            line: usize,
+        ) -> Result<u32, AssemblerError> {
+            let value = self
+                .evaluate(expr, scope, pc as Word)
+                .map_err(|error| self.expression_error(error, line))?;
+            if !(0..=0xffff).contains(&value) {
+                return Err(AssemblerError {
+                    line,
+                    message: format!("address out of range: {}", expr),
+                });
+            }
+
+            return Ok(value as u32);
+        }
+
+        fn evaluate(&self, expr: &str, scope: &str, pc: Word) -> Result<i64, ExprError> {
+            let mut parser = ExpressionParser {
+                chars: expr.chars().collect(),
+                pos: 0,
+                symbols: &self.symbols,
+                scope,
+                pc,
+            };
+
+            return parser.parse();
+        }
+
+        fn expression_error(&self, error: ExprError, line: usize) -> AssemblerError {
+            let message = match error {
+                ExprError::Undefined(name) => format!("undefined label {}", name),
+                ExprError::Syntax(message) => message,
+            };
+
+            return AssemblerError { line, message };
+        }
+    }
+
+    // Operator precedence levels from loosest to tightest binding
+    const BINARY_OPERATORS: [&[&str]; 6] = [
+        &["|"],
+        &["^"],
+        &["&"],
+        &["<<", ">>"],
+        &["+", "-"],
+        &["*", "/", "%"],
+    ];
+
+    struct ExpressionParser<'a> {
+        chars: Vec<char>,
+        pos: usize,
+        symbols: &'a HashMap<String, Word>,
+        scope: &'a str,
+        pc: Word,
+    }
+
+    impl ExpressionParser<'_> {
+        fn parse(&mut self) -> Result<i64, ExprError> {
+            let value = self.binary(0)?;
+            self.skip_whitespace();
+            if self.pos < self.chars.len() {
+                let rest: String = self.chars[self.pos..].iter().collect();
+                return Err(ExprError::Syntax(format!("unexpected {}", rest)));
+            }
+
+            return Ok(value);
+        }
+
+        fn binary(&mut self, level: usize) -> Result<i64, ExprError> {
+            if level == BINARY_OPERATORS.len() {
+                return self.unary();
+            }
+
+            let mut value = self.binary(level + 1)?;
+            loop {
+                self.skip_whitespace();
+                let operator = BINARY_OPERATORS[level]
+                    .iter()
+                    .find(|operator| self.next_is(operator));
+                let Some(operator) = operator else {
+                    break;
+                };
+                self.pos += operator.len();
+                let rhs = self.binary(level + 1)?;
+                let overflow = || ExprError::Syntax("arithmetic overflow".to_string());
+                value = match *operator {
+                    "|" => value | rhs,
+                    "^" => value ^ rhs,
+                    "&" => value & rhs,
+                    "<<" => value.checked_shl(rhs as u32).unwrap_or(0),
+                    ">>" => value.checked_shr(rhs as u32).unwrap_or(0),
+                    "+" => value.checked_add(rhs).ok_or_else(overflow)?,
+                    "-" => value.checked_sub(rhs).ok_or_else(overflow)?,
+                    "*" => value.checked_mul(rhs).ok_or_else(overflow)?,
+                    _ if rhs == 0 => {
+                        return Err(ExprError::Syntax("division by zero".to_string()));
+                    }
+                    "/" => value.checked_div(rhs).ok_or_else(overflow)?,
+                    _ => value.checked_rem(rhs).ok_or_else(overflow)?,
+                };
+            }
+
+            return Ok(value);
+        }
+
+        fn unary(&mut self) -> Result<i64, ExprError> {
+            self.skip_whitespace();
+            return match self.chars.get(self.pos) {
+                Some('-') => {
+                    self.pos += 1;
+                    self.unary()?
+                        .checked_neg()
+                        .ok_or_else(|| ExprError::Syntax("arithmetic overflow".to_string()))
+                }
+                Some('~') => {
+                    self.pos += 1;
+                    Ok(!self.unary()?)
+                }
+                Some('<') => {
+                    self.pos += 1;
+                    Ok(self.unary()? & 0xff)
+                }
+                Some('>') => {
+                    self.pos += 1;
+                    Ok((self.unary()? >> 8) & 0xff)
+                }
+                _ => self.primary(),
+            };
+        }
+
+        fn primary(&mut self) -> Result<i64, ExprError> {
+            let Some(&c) = self.chars.get(self.pos) else {
+                return Err(ExprError::Syntax("missing operand".to_string()));
+            };
+
+            match c {
+                '(' => {
+                    self.pos += 1;
+                    let value = self.binary(0)?;
+                    self.skip_whitespace();
+                    if self.chars.get(self.pos) != Some(&')') {
+                        return Err(ExprError::Syntax("missing )".to_string()));
+                    }
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                '$' => {
+                    self.pos += 1;
+                    return self.number(16);
+                }
+                '%' => {
+                    self.pos += 1;
+                    return self.number(2);
+                }
+                '0'..='9' => return self.number(10),
+                '\'' => {
+                    let value = self.chars.get(self.pos + 1).copied();
+                    if self.chars.get(self.pos + 2) != Some(&'\'') {
+                        return Err(ExprError::Syntax("bad character literal".to_string()));
+                    }
+                    self.pos += 3;
+                    return Ok(value.unwrap_or_default() as i64);
+                }
+                '*' => {
+                    self.pos += 1;
+                    return Ok(self.pc as i64);
+                }
+                _ if is_identifier_char(c) => {
+                    let start = self.pos;
+                    while self.pos < self.chars.len() && is_identifier_char(self.chars[self.pos]) {
+                        self.pos += 1;
+                    }
+                    let name: String = self.chars[start..self.pos].iter().collect();
+                    let name = qualify(&name, self.scope);
+                    return match self.symbols.get(&name) {
+                        Some(value) => Ok(*value as i64),
+                        None => Err(ExprError::Undefined(name)),
+                    };
+                }
+                _ => return Err(ExprError::Syntax(format!("unexpected {}", c))),
+            }
+        }
+
+        fn number(&mut self, radix: u32) -> Result<i64, ExprError> {
+            let start = self.pos;
+            while self.pos < self.chars.len() && self.chars[self.pos].is_digit(radix) {
+                self.pos += 1;
+            }
+            let digits: String = self.chars[start..self.pos].iter().collect();
+
+            return i64::from_str_radix(&digits, radix)
+                .map_err(|_| ExprError::Syntax("bad number".to_string()));
+        }
+
+        fn next_is(&self, operator: &str) -> bool {
+            return operator
+                .chars()
+                .enumerate()
+                .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn is_identifier_char(c: char) -> bool {
+        return c.is_ascii_alphanumeric() || c == '_' || c == '@' || c == '.';
+    }
+
+    // Local labels live inside the scope of the last global label
+    fn qualify(name: &str, scope: &str) -> String {
+        if name.starts_with('@') {
+            return format!("{}{}", scope, name);
+        }
+        return name.to_string();
+    }
+
+    fn strip_comment(line: &str) -> &str {
+        let mut quote = None;
+        for (i, c) in line.char_indices() {
+            match (quote, c) {
+                (None, ';') => return &line[..i],
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (Some(q), _) if q == c => quote = None,
+                _ => {}
+            }
+        }
+
+        return line;
+    }
+
+    // Splits `NAME = expr` and `NAME equ expr`
+    fn split_assignment(line: &str) -> Option<(&str, &str)> {
+        let line = line.trim();
+        let name_len = line
+            .find(|c: char| !is_identifier_char(c) && c != '*')
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_len);
+        if name.is_empty() {
+            return None;
+        }
+
+        let rest = rest.trim_start();
+        if let Some(expr) = rest.strip_prefix('=') {
+            return Some((name, expr.trim()));
+        }
+        let keyword = rest.get(..3)?;
+        if keyword.eq_ignore_ascii_case("equ") && rest[3..].starts_with(char::is_whitespace) {
+            return Some((name, rest[3..].trim()));
+        }
+
+        return None;
+    }
+
+    // Splits at commas outside of quotes and parentheses
+    fn split_list(text: &str) -> Vec<String> {
+        let mut items = Vec::new();
+        let mut depth = 0;
+        let mut quote = None;
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if q == c => quote = None,
+                (Some(_), _) => {}
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (None, '(') => depth += 1,
+                (None, ')') => depth -= 1,
+                (None, ',') if depth == 0 => {
+                    items.push(text[start..i].trim().to_string());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if !text[start..].trim().is_empty() || !items.is_empty() {
+            items.push(text[start..].trim().to_string());
+        }
+
+        return items;
+    }
+
+    fn parse_string(item: &str) -> Option<&str> {
+        if item.len() >= 2 && item.starts_with('"') && item.ends_with('"') {
+            return Some(&item[1..item.len() - 1]);
+        }
+        return None;
+    }
+
+    fn parse_register(text: &str) -> Option<Register> {
+        return match text.to_uppercase().as_str() {
+            "X" => Some(Register::X),
+            "Y" => Some(Register::Y),
+            _ => None,
+        };
+    }
+
+    fn parse_operand(text: &str) -> Operand {
+        if text.is_empty() {
+            return Operand::None;
+        }
+        if text.eq_ignore_ascii_case("a") {
+            return Operand::Accumulator;
+        }
+        if let Some(expr) = text.strip_prefix('#') {
+            return Operand::Immediate(expr.trim().to_string());
+        }
+
+        let items = split_list(text);
+        if text.starts_with('(') {
+            let close = matching_paren(text);
+            let inner = split_list(&text[1..close]);
+            let after = text[close + 1..].trim();
+            match (inner.len(), after) {
+                (1, "") => return Operand::Indirect(inner[0].clone()),
+                (2, "") if parse_register(&inner[1]) == Some(Register::X) => {
+                    return Operand::IndirectX(inner[0].clone());
+                }
+                (1, _) if items.len() == 2 && parse_register(&items[1]) == Some(Register::Y) => {
+                    return Operand::IndirectY(inner[0].clone());
+                }
+                // Parenthesised expression like (1+2)*3
+                _ => {}
+            }
+        }
+
+        if items.len() == 2 {
+            return match parse_register(&items[1]) {
+                Some(register) => Operand::Indexed(items[0].clone(), register),
+                None => Operand::Pair(items[0].clone(), items[1].clone()),
+            };
+        }
+
+        return Operand::Direct(text.to_string());
+    }
+
+    // Index of the parenthesis closing the one at the start of text, or
+    // the end of text when unbalanced
+    fn matching_paren(text: &str) -> usize {
+        let mut depth = 0;
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return text.len() - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::assembler::{assemble, assemble_into, AssemblerError};
+    use crate::memory::memory::{Memory, MemoryLike};
+    use crate::{CpuVariant, CPU};
+
+    fn assemble_bytes(source: &str) -> Vec<u8> {
+        let assembly = assemble(source, CpuVariant::Nmos6502).unwrap();
+        assert_eq!(assembly.segments.len(), 1);
+
+        return assembly.segments[0].1.clone();
+    }
+
+    #[test]
+    fn test_addressing_modes() {
+        let source = "
+            .org $0600
+            nop
+            asl a
+            lsr
+            lda #$42
+            lda $20
+            lda $20,x
+            ldx $20,y
+            lda $1234
+            lda $1234,X
+            lda $1234,Y
+            lda ($20,X)
+            lda ($20),Y
+            jmp ($FFFC)
+        ";
+
+        assert_eq!(
+            assemble_bytes(source),
+            vec![
+                0xEA, 0x0A, 0x4A, 0xA9, 0x42, 0xA5, 0x20, 0xB5, 0x20, 0xB6, 0x20, 0xAD, 0x34, 0x12,
+                0xBD, 0x34, 0x12, 0xB9, 0x34, 0x12, 0xA1, 0x20, 0xB1, 0x20, 0x6C, 0xFC, 0xFF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_labels_and_branches() {
+        let source = "
+            .org $C000
+    start:  ldx #0
+    loop:   inx
+            bne loop
+            beq done
+            jmp start
+    done:   rts
+        ";
+        let assembly = assemble(source, CpuVariant::Nmos6502).unwrap();
+
+        assert_eq!(assembly.segments[0].0, 0xC000);
+        assert_eq!(
+            assembly.segments[0].1,
+            vec![0xA2, 0x00, 0xE8, 0xD0, 0xFD, 0xF0, 0x03, 0x4C, 0x00, 0xC0, 0x60]
+        );
+        assert_eq!(assembly.get_label("loop"), Some(0xC002));
+        assert_eq!(assembly.get_label("done"), Some(0xC00A));
+    }
+
+    #[test]
+    fn test_forward_references_use_absolute() {
+        let source = "
+            lda data
+            lda $10
+    data:   .byte 1
+        ";
+
+        assert_eq!(
+            assemble_bytes(source),
+            vec![0xAD, 0x05, 0x00, 0xA5, 0x10, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_column_0_labels() {
+        let source = "
+loop    dex ; DASM style label
+        bne loop
+nop
+        ";
+        let assembly = assemble(source, CpuVariant::Nmos6502).unwrap();
+
+        assert_eq!(assembly.get_label("loop"), Some(0x0000));
+        assert_eq!(assembly.get_label("nop"), None);
+        assert_eq!(assembly.segments[0].1, vec![0xCA, 0xD0, 0xFD, 0xEA]);
+    }
+
+    #[test]
+    fn test_local_labels() {
+        let source = "
+    first:  ldy #2
+    @loop:  dey
+            bne @loop
+    second: ldy #2
+    @loop:  dey
+            bne @loop
+        ";
+        let assembly = assemble(source, CpuVariant::Nmos6502).unwrap();
+
+        assert_eq!(assembly.get_label("first@loop"), Some(0x0002));
+        assert_eq!(assembly.get_label("second@loop"), Some(0x0007));
+        assert_eq!(
+            assembly.segments[0].1,
+            vec![0xA0, 0x02, 0x88, 0xD0, 0xFD, 0xA0, 0x02, 0x88, 0xD0, 0xFD]
+        );
+    }
+
+    #[test]
+    fn test_directives_and_expressions() {
+        let source = "
+SCREEN = $0400
+COLS equ 40
+            * = $0800
+table:      .byte 1, -1, 'A', \"hi\", %1010, COLS * 2 + 1
+            .word table, SCREEN + COLS, end - table
+            lda #<(SCREEN + 1)
+            ldx #>SCREEN
+            sta SCREEN + COLS * 2, x
+            .db (2 + 3) * 4, 7 % 4, 1 << 4 | 1, $F0 & $3C ^ $01
+end:
+        ";
+
+        assert_eq!(
+            assemble_bytes(source),
+            vec![
+                0x01, 0xFF, 0x41, 0x68, 0x69, 0x0A, 0x51, 0x00, 0x08, 0x28, 0x04, 0x18, 0x00, 0xA9,
+                0x01, 0xA2, 0x04, 0x9D, 0x50, 0x04, 0x14, 0x03, 0x11, 0x31,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_segments() {
+        let source = "
+            .org $FFFC
+            .word reset
+            .org $8000
+    reset:  jmp reset
+        ";
+        let assembly = assemble(source, CpuVariant::Nmos6502).unwrap();
+
+        assert_eq!(
+            assembly.segments,
+            vec![(0xFFFC, vec![0x00, 0x80]), (0x8000, vec![0x4C, 0x00, 0x80])]
+        );
+    }
+
+    #[test]
+    fn test_65c02_syntax() {
+        let source = "
+            .org $0200
+            bra skip
+            stz $10
+            lda ($20)
+            jmp ($3000,x)
+    skip:   bbr3 $12, skip
+            inc a
+        ";
+        let assembly = assemble(source, CpuVariant::Cmos65C02).unwrap();
+
+        assert_eq!(
+            assembly.segments[0].1,
+            vec![0x80, 0x07, 0x64, 0x10, 0xB2, 0x20, 0x7C, 0x00, 0x30, 0x3F, 0x12, 0xFD, 0x1A]
+        );
+        assert!(assemble("stz $10", CpuVariant::Nmos6502).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("  lda #1\n  foo #2", 2, "unknown instruction foo"),
+            ("  jmp nowhere", 1, "undefined label nowhere"),
+            ("a:\na:", 2, "duplicate label a"),
+            ("  inx ($10),y", 1, "invalid addressing mode for INX"),
+            ("  lda #256", 1, "byte out of range: 256"),
+            ("  lda ($1234),y", 1, "byte out of range: $1234"),
+            ("  .word $7FFFFFFFFFFFFFFF * 2", 1, "arithmetic overflow"),
+            ("  .word $7FFFFFFFFFFFFFFF + 1", 1, "arithmetic overflow"),
+            ("  .word -$7FFFFFFFFFFFFFFF - 2", 1, "arithmetic overflow"),
+            ("  .word (1 << 63) / -1", 1, "arithmetic overflow"),
+            ("  .word (1 << 63) % -1", 1, "arithmetic overflow"),
+            ("  .word -(1 << 63)", 1, "arithmetic overflow"),
+            ("  .word 1 / 0", 1, "division by zero"),
+        ];
+
+        for (source, line, message) in cases {
+            assert_eq!(
+                assemble(source, CpuVariant::Nmos6502),
+                Err(AssemblerError {
+                    line,
+                    message: message.to_string()
+                })
+            );
+        }
+
+        let far = format!("  bne far\n  .byte {}\nfar: nop", vec!["0"; 200].join(","));
+        assert_eq!(
+            assemble(&far, CpuVariant::Nmos6502).unwrap_err().message,
+            "branch out of range: far"
+        );
+    }
+
+    #[test]
+    fn test_assemble_into_and_run() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let source = "
+            .org $FFFC
+            .word main
+            .org $0600
+    main:   lda #$10
+            jsr double
+            sta result
+            jmp *
+    double: asl a
+            rts
+    result: .byte 0
+        ";
+
+        let assembly = assemble_into(source, &mut memory, CpuVariant::Nmos6502).unwrap();
+        cpu.reset(&mut memory);
+        let done = assembly.get_label("main").unwrap() + 8;
+        cpu.run_until(&mut memory, |cpu| cpu.pc == done).unwrap();
+
+        let result: u8 = memory.read(assembly.get_label("result").unwrap());
+        assert_eq!(result, 0x20);
+    }
+}
@@ -0,0 +1,475 @@
+#[allow(clippy::module_inception)]
+pub mod banking {
+    use crate::memory::memory::{Byte, Word};
+    use crate::memory_map::memory_map::Device;
+    use crate::save_state::save_state::{SaveStateError, Snapshot, DEVICE};
+
+    // Decides which part of a banked backing store is visible in the CPU
+    // address space. Offsets are relative to the start of the region the
+    // BankedMemory is mapped at
+    pub trait Mapper {
+        // Index into the backing store for offset, None when nothing is
+        // visible there (e.g. a write-only control register)
+        fn map(&self, offset: Word) -> Option<usize>;
+        // Called for every write before it reaches the backing store.
+        // Returns true when the write hit a control register and must not
+        // be stored
+        fn write(&mut self, offset: Word, value: Byte) -> bool;
+        // Register contents for save states, see Snapshot. Mappers without
+        // registers keep the defaults
+        fn save_state(&self) -> Vec<Byte> {
+            return Vec::new();
+        }
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            if !data.is_empty() {
+                return Err(SaveStateError::InvalidSection { tag: DEVICE });
+            }
+            return Ok(());
+        }
+        fn load_state(&mut self, _data: &[Byte]) {}
+    }
+
+    // Backing store of any size seen through a mapper. Map it with
+    // MemoryMap::device over the window (and control registers) it serves
+    pub struct BankedMemory {
+        data: Vec<Byte>,
+        writable: bool,
+        mapper: Box<dyn Mapper>,
+    }
+
+    impl BankedMemory {
+        pub fn rom(data: Vec<Byte>, mapper: Box<dyn Mapper>) -> Self {
+            assert!(!data.is_empty(), "Banked ROM must not be empty");
+            return BankedMemory {
+                data,
+                writable: false,
+                mapper,
+            };
+        }
+
+        pub fn ram(size: usize, mapper: Box<dyn Mapper>) -> Self {
+            assert!(size > 0, "Banked RAM must not be empty");
+            return BankedMemory {
+                data: vec![0; size],
+                writable: true,
+                mapper,
+            };
+        }
+
+        pub fn get_data(&self) -> &[Byte] {
+            return &self.data;
+        }
+
+        pub fn get_data_mut(&mut self) -> &mut [Byte] {
+            return &mut self.data;
+        }
+
+        // Bytes of data in a save state, ROM contents are not saved
+        fn saved_len(&self) -> usize {
+            return if self.writable { self.data.len() } else { 0 };
+        }
+    }
+
+    impl Device for BankedMemory {
+        fn read(&mut self, offset: Word) -> Byte {
+            return match self.mapper.map(offset) {
+                Some(index) => self.data[index % self.data.len()],
+                // Nothing drives the bus, pull-ups read as all ones
+                None => 0xff,
+            };
+        }
+
+        fn write(&mut self, offset: Word, value: Byte) {
+            if self.mapper.write(offset, value) || !self.writable {
+                return;
+            }
+            if let Some(index) = self.mapper.map(offset) {
+                let len = self.data.len();
+                self.data[index % len] = value;
+            }
+        }
+
+        // Reads have no side effects, only writes reach the mapper
+        fn peek(&mut self, offset: Word) -> Option<Byte> {
+            return Some(self.read(offset));
+        }
+    }
+
+    // Contents are only stored for RAM, ROM can't change. The mapper
+    // registers follow
+    impl Snapshot for BankedMemory {
+        fn save_state(&self) -> Vec<Byte> {
+            let mut out = Vec::new();
+            if self.writable {
+                out.extend_from_slice(&self.data);
+            }
+            out.extend_from_slice(&self.mapper.save_state());
+            return out;
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            let len = self.saved_len();
+            if data.len() < len {
+                return Err(SaveStateError::InvalidSection { tag: DEVICE });
+            }
+            return self.mapper.check_state(&data[len..]);
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            let len = self.saved_len();
+            self.data[..len].copy_from_slice(&data[..len]);
+            self.mapper.load_state(&data[len..]);
+        }
+    }
+
+    const PRG_BANK_SIZE: usize = 0x4000;
+
+    // NES UxROM: 16 KiB bank selected by any write to $8000-$FFFF visible
+    // at $8000, last bank fixed at $C000
+    pub struct UxRom {
+        banks: usize,
+        bank: usize,
+    }
+
+    impl UxRom {
+        pub fn new(banks: usize) -> Self {
+            assert!(banks > 0, "UxROM needs at least one bank");
+            return UxRom { banks, bank: 0 };
+        }
+    }
+
+    impl Mapper for UxRom {
+        fn map(&self, offset: Word) -> Option<usize> {
+            let offset = offset as usize;
+            let bank = if offset < PRG_BANK_SIZE {
+                self.bank
+            } else {
+                self.banks - 1
+            };
+
+            return Some(bank * PRG_BANK_SIZE + offset % PRG_BANK_SIZE);
+        }
+
+        fn write(&mut self, _offset: Word, value: Byte) -> bool {
+            self.bank = value as usize % self.banks;
+            return true;
+        }
+
+        fn save_state(&self) -> Vec<Byte> {
+            return (self.bank as u32).to_le_bytes().to_vec();
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            return match data.try_into() {
+                Ok(bytes) if (u32::from_le_bytes(bytes) as usize) < self.banks => Ok(()),
+                _ => Err(SaveStateError::InvalidSection { tag: DEVICE }),
+            };
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            self.bank = u32::from_le_bytes(data.try_into().unwrap()) as usize;
+        }
+    }
+
+    // NES MMC1 PRG banking. Registers are loaded serially: five writes of
+    // bit 0, a write with bit 7 set resets the shift register. Address
+    // bits 13-14 of the fifth write select control, CHR 0, CHR 1 or PRG.
+    // CHR banking lives on the PPU bus and is only latched here
+    pub struct Mmc1 {
+        banks: usize,
+        shift: Byte,
+        count: u8,
+        control: Byte,
+        chr_bank_0: Byte,
+        chr_bank_1: Byte,
+        prg_bank: Byte,
+    }
+
+    impl Mmc1 {
+        pub fn new(banks: usize) -> Self {
+            assert!(banks > 0, "MMC1 needs at least one bank");
+            return Mmc1 {
+                banks,
+                shift: 0,
+                count: 0,
+                // Powers up with the last bank fixed at $C000
+                control: 0x0c,
+                chr_bank_0: 0,
+                chr_bank_1: 0,
+                prg_bank: 0,
+            };
+        }
+
+        pub fn get_control(&self) -> Byte {
+            return self.control;
+        }
+
+        pub fn get_chr_banks(&self) -> (Byte, Byte) {
+            return (self.chr_bank_0, self.chr_bank_1);
+        }
+    }
+
+    impl Mapper for Mmc1 {
+        fn map(&self, offset: Word) -> Option<usize> {
+            let offset = offset as usize;
+            let slot = offset / PRG_BANK_SIZE;
+            let prg = (self.prg_bank & 0x0f) as usize;
+            let bank = match (self.control >> 2) & 0b11 {
+                // 32 KiB mode ignores the low bit of the bank number
+                0 | 1 => (prg & !1) + slot,
+                2 => {
+                    if slot == 0 {
+                        0
+                    } else {
+                        prg
+                    }
+                }
+                _ => {
+                    if slot == 0 {
+                        prg
+                    } else {
+                        self.banks - 1
+                    }
+                }
+            };
+
+            return Some((bank % self.banks) * PRG_BANK_SIZE + offset % PRG_BANK_SIZE);
+        }
+
+        fn write(&mut self, offset: Word, value: Byte) -> bool {
+            if value & 0x80 != 0 {
+                self.shift = 0;
+                self.count = 0;
+                self.control |= 0x0c;
+                return true;
+            }
+
+            self.shift |= (value & 1) << self.count;
+            self.count += 1;
+            if self.count == 5 {
+                match (offset >> 13) & 0b11 {
+                    0 => self.control = self.shift,
+                    1 => self.chr_bank_0 = self.shift,
+                    2 => self.chr_bank_1 = self.shift,
+                    _ => self.prg_bank = self.shift,
+                }
+                self.shift = 0;
+                self.count = 0;
+            }
+
+            return true;
+        }
+
+        fn save_state(&self) -> Vec<Byte> {
+            return vec![
+                self.shift,
+                self.count,
+                self.control,
+                self.chr_bank_0,
+                self.chr_bank_1,
+                self.prg_bank,
+            ];
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            return match *data {
+                [_, count, _, _, _, _] if count < 5 => Ok(()),
+                _ => Err(SaveStateError::InvalidSection { tag: DEVICE }),
+            };
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            self.shift = data[0];
+            self.count = data[1];
+            self.control = data[2];
+            self.chr_bank_0 = data[3];
+            self.chr_bank_1 = data[4];
+            self.prg_bank = data[5];
+        }
+    }
+
+    // Commodore GeoRAM style expansion: a 256 byte window at offset
+    // $000-$0FF into up to 4 MiB of RAM, paged by write-only registers at
+    // offset $1FE (page inside the 16 KiB block) and $1FF (block). Map it
+    // at $DE00-$DFFF on a C64
+    pub struct GeoRam {
+        page: Byte,
+        block: Byte,
+    }
+
+    impl GeoRam {
+        pub fn new() -> Self {
+            return GeoRam { page: 0, block: 0 };
+        }
+    }
+
+    impl Default for GeoRam {
+        fn default() -> Self {
+            return GeoRam::new();
+        }
+    }
+
+    impl Mapper for GeoRam {
+        fn map(&self, offset: Word) -> Option<usize> {
+            if offset > 0xff {
+                return None;
+            }
+            let page = (self.page & 0x3f) as usize;
+            let block = self.block as usize;
+
+            return Some(block * 0x4000 + page * 0x100 + offset as usize);
+        }
+
+        fn write(&mut self, offset: Word, value: Byte) -> bool {
+            match offset {
+                0x1fe => self.page = value,
+                0x1ff => self.block = value,
+                _ => return false,
+            }
+
+            return true;
+        }
+
+        fn save_state(&self) -> Vec<Byte> {
+            return vec![self.page, self.block];
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            if data.len() != 2 {
+                return Err(SaveStateError::InvalidSection { tag: DEVICE });
+            }
+            return Ok(());
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            self.page = data[0];
+            self.block = data[1];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::banking::banking::{BankedMemory, GeoRam, Mapper, Mmc1, UxRom};
+    use crate::memory::memory::{Bus, Byte};
+    use crate::memory_map::memory_map::{Device, MemoryMap};
+    use crate::{CpuVariant, Instruction, CPU};
+
+    // Every 16 KiB bank is filled with its own number
+    fn numbered_banks(banks: usize) -> Vec<Byte> {
+        let mut data = vec![0u8; banks * 0x4000];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = (i / 0x4000) as Byte;
+        }
+        return data;
+    }
+
+    fn mmc1_write(memory: &mut BankedMemory, offset: u16, value: Byte) {
+        for i in 0..5 {
+            memory.write(offset, (value >> i) & 1);
+        }
+    }
+
+    #[test]
+    fn test_uxrom() {
+        let mut memory = BankedMemory::rom(numbered_banks(8), Box::new(UxRom::new(8)));
+
+        assert_eq!(memory.read(0x0000), 0);
+        assert_eq!(memory.read(0x7fff), 7);
+
+        memory.write(0x1234, 5);
+        assert_eq!(memory.read(0x0000), 5);
+        assert_eq!(memory.read(0x3fff), 5);
+        assert_eq!(memory.read(0x4000), 7);
+
+        // Writes select banks instead of reaching the ROM
+        assert_eq!(memory.get_data()[0x1234], 0);
+    }
+
+    #[test]
+    fn test_mmc1_modes() {
+        let mut memory = BankedMemory::rom(numbered_banks(8), Box::new(Mmc1::new(8)));
+
+        // Power-on: switchable at $8000, last bank fixed at $C000
+        mmc1_write(&mut memory, 0x6000, 3);
+        assert_eq!(memory.read(0x0000), 3);
+        assert_eq!(memory.read(0x4000), 7);
+
+        // First bank fixed at $8000, switchable at $C000
+        mmc1_write(&mut memory, 0x0000, 0b01000);
+        assert_eq!(memory.read(0x0000), 0);
+        assert_eq!(memory.read(0x4000), 3);
+
+        // 32 KiB mode drops the low bit
+        mmc1_write(&mut memory, 0x0000, 0b00000);
+        assert_eq!(memory.read(0x0000), 2);
+        assert_eq!(memory.read(0x4000), 3);
+    }
+
+    #[test]
+    fn test_mmc1_shift_register_reset() {
+        let mut mapper = Mmc1::new(8);
+
+        mapper.write(0x6000, 1);
+        mapper.write(0x6000, 1);
+        mapper.write(0x6000, 0x80);
+        for _ in 0..5 {
+            mapper.write(0x2000, 1);
+        }
+
+        assert_eq!(mapper.get_chr_banks(), (0x1f, 0));
+        assert_eq!(mapper.get_control(), 0x0c);
+        assert_eq!(mapper.map(0x0000), Some(0));
+    }
+
+    #[test]
+    fn test_georam() {
+        let mut memory = MemoryMap::new().ram(0x0000, 0xffff).device(
+            0xde00,
+            0xdfff,
+            Box::new(BankedMemory::ram(0x80000, Box::new(GeoRam::new()))),
+        );
+
+        memory.write_byte(0xde10, 0x11);
+        memory.write_byte(0xdffe, 0x05);
+        memory.write_byte(0xdfff, 0x1f);
+        memory.write_byte(0xde10, 0x22);
+
+        assert_eq!(memory.read_byte(0xde10), 0x22);
+        memory.write_byte(0xdffe, 0x00);
+        memory.write_byte(0xdfff, 0x00);
+        assert_eq!(memory.read_byte(0xde10), 0x11);
+        assert_eq!(memory.read_byte(0xdf00), 0xff);
+    }
+
+    #[test]
+    fn test_uxrom_program() {
+        let mut cpu = CPU::new(CpuVariant::Ricoh2A03);
+        let mut prg = numbered_banks(4);
+        let program = [
+            u8::from(Instruction::LDA_IM),
+            0x02,
+            u8::from(Instruction::STA_ABS),
+            0x00,
+            0x80,
+            u8::from(Instruction::LDX_ABS),
+            0x00,
+            0x80,
+        ];
+        prg[0xc000..0xc000 + program.len()].copy_from_slice(&program);
+        prg[0xfffc] = 0x00;
+        prg[0xfffd] = 0xc0;
+        let mut memory = MemoryMap::new()
+            .mirrored_ram(0x0000, 0x1fff, 0x0800)
+            .device(
+                0x8000,
+                0xffff,
+                Box::new(BankedMemory::rom(prg, Box::new(UxRom::new(4)))),
+            );
+
+        cpu.reset(&mut memory);
+        cpu.run_for_cycles(&mut memory, 10).unwrap();
+
+        assert_eq!(cpu.x, 0x02);
+    }
+}
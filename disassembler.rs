@@ -0,0 +1,261 @@
+#[allow(clippy::module_inception)]
+pub mod disassembler {
+    use crate::memory::memory::{AddressingMode, Bus, Byte, Word};
+    use crate::{CpuVariant, Instruction, Register};
+
+    // Disassembles the instruction at addr using the instruction set of
+    // variant. Returns the text in standard syntax (`LDA ($20),Y`,
+    // `BNE $C012`) and the instruction length in bytes. Bytes without an
+    // instruction come out as `.byte $xx`. Only the bytes of the
+    // instruction itself are read, never the ones after it
+    pub fn disassemble<B: Bus>(memory: &mut B, addr: Word, variant: CpuVariant) -> (String, Word) {
+        let opcode = memory.read_byte(addr);
+        let length = instruction_length(opcode, variant);
+        let mut bytes = vec![opcode];
+        for i in 1..length {
+            bytes.push(memory.read_byte(addr.wrapping_add(i)));
+        }
+
+        return (format_instruction(&bytes, addr, variant), length);
+    }
+
+    // Length in bytes of the instruction starting with opcode, one for
+    // bytes without an instruction
+    pub(crate) fn instruction_length(opcode: Byte, variant: CpuVariant) -> Word {
+        return match Instruction::decode(opcode, variant) {
+            Instruction::INVALID => 1,
+            instruction => instruction.length(),
+        };
+    }
+
+    // Text of the instruction at addr whose bytes, opcode first, were
+    // already read. Missing operand bytes count as zero
+    pub(crate) fn format_instruction(bytes: &[Byte], addr: Word, variant: CpuVariant) -> String {
+        let opcode = bytes.first().copied().unwrap_or(0);
+        let instruction = Instruction::decode(opcode, variant);
+        if instruction == Instruction::INVALID {
+            return format!(".byte ${:02X}", opcode);
+        }
+
+        let low = bytes.get(1).copied().unwrap_or(0);
+        let high = bytes.get(2).copied().unwrap_or(0);
+        let word = ((high as Word) << 8) | low as Word;
+        let index = |register: Register| match register {
+            Register::Y => "Y",
+            _ => "X",
+        };
+
+        let mnemonic = instruction.mnemonic();
+        let (mode, register) = instruction.addressing_mode();
+        return match mode {
+            AddressingMode::Implied => mnemonic,
+            AddressingMode::Accumulator => format!("{} A", mnemonic),
+            AddressingMode::Immediate => format!("{} #${:02X}", mnemonic, low),
+            AddressingMode::ZeroPage => format!("{} ${:02X}", mnemonic, low),
+            AddressingMode::ZeroPageReg => {
+                format!("{} ${:02X},{}", mnemonic, low, index(register))
+            }
+            AddressingMode::Absolute => format!("{} ${:04X}", mnemonic, word),
+            AddressingMode::AbsoluteReg => {
+                format!("{} ${:04X},{}", mnemonic, word, index(register))
+            }
+            AddressingMode::Indirect => format!("{} (${:04X})", mnemonic, word),
+            AddressingMode::IndirectX => format!("{} (${:02X},X)", mnemonic, low),
+            AddressingMode::IndirectY => format!("{} (${:02X}),Y", mnemonic, low),
+            AddressingMode::ZeroPageIndirect => format!("{} (${:02X})", mnemonic, low),
+            AddressingMode::AbsoluteIndirectX => format!("{} (${:04X},X)", mnemonic, word),
+            AddressingMode::Relative => {
+                let target = branch_target(addr.wrapping_add(2), low);
+                format!("{} ${:04X}", mnemonic, target)
+            }
+            AddressingMode::ZeroPageRelative => {
+                let target = branch_target(addr.wrapping_add(3), high);
+                format!("{} ${:02X},${:04X}", mnemonic, low, target)
+            }
+        };
+    }
+
+    // Disassembles every instruction starting in start..=end. Returns the
+    // address, text and length of each one
+    pub fn disassemble_range<B: Bus>(
+        memory: &mut B,
+        start: Word,
+        end: Word,
+        variant: CpuVariant,
+    ) -> Vec<(Word, String, Word)> {
+        let mut lines = Vec::new();
+        let mut addr = start as u32;
+        while addr <= end as u32 {
+            let (text, length) = disassemble(memory, addr as Word, variant);
+            lines.push((addr as Word, text, length));
+            addr += length as u32;
+        }
+
+        return lines;
+    }
+
+    fn branch_target(next: Word, offset: u8) -> Word {
+        return next.wrapping_add(offset as i8 as Word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disassembler::disassembler::{disassemble, disassemble_range};
+    use crate::memory::memory::{Bus, Byte, Memory, Word};
+    use crate::{CpuVariant, Instruction};
+
+    fn disassemble_bytes(bytes: &[u8], addr: u16, variant: CpuVariant) -> (String, u16) {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        for (i, byte) in bytes.iter().enumerate() {
+            memory.write_byte(addr + i as u16, *byte);
+        }
+
+        return disassemble(&mut memory, addr, variant);
+    }
+
+    #[test]
+    fn test_disassemble_nmos() {
+        let cases: [(&[u8], &str, u16); 16] = [
+            (&[0xEA], "NOP", 1),
+            (&[0x0A], "ASL A", 1),
+            (&[0xA9, 0x42], "LDA #$42", 2),
+            (&[0xA5, 0x20], "LDA $20", 2),
+            (&[0xB5, 0x20], "LDA $20,X", 2),
+            (&[0xB6, 0x20], "LDX $20,Y", 2),
+            (&[0xAD, 0x34, 0x12], "LDA $1234", 3),
+            (&[0xBD, 0x34, 0x12], "LDA $1234,X", 3),
+            (&[0xB9, 0x34, 0x12], "LDA $1234,Y", 3),
+            (&[0x6C, 0xFC, 0xFF], "JMP ($FFFC)", 3),
+            (&[0xA1, 0x20], "LDA ($20,X)", 2),
+            (&[0xB1, 0x20], "LDA ($20),Y", 2),
+            (&[0x20, 0x00, 0xC0], "JSR $C000", 3),
+            (&[0x60], "RTS", 1),
+            (&[0xA7, 0x10], "LAX $10", 2),
+            (&[0x8B], ".byte $8B", 1),
+        ];
+
+        for (bytes, text, length) in cases {
+            assert_eq!(
+                disassemble_bytes(bytes, 0x0400, CpuVariant::Nmos6502),
+                (text.to_string(), length)
+            );
+        }
+    }
+
+    #[test]
+    fn test_disassemble_branches() {
+        assert_eq!(
+            disassemble_bytes(&[0xD0, 0x10], 0xC000, CpuVariant::Nmos6502),
+            ("BNE $C012".to_string(), 2)
+        );
+        assert_eq!(
+            disassemble_bytes(&[0xF0, 0xFE], 0xC000, CpuVariant::Nmos6502),
+            ("BEQ $C000".to_string(), 2)
+        );
+        assert_eq!(
+            disassemble_bytes(&[0x80, 0x80], 0x1000, CpuVariant::Cmos65C02),
+            ("BRA $0F82".to_string(), 2)
+        );
+        assert_eq!(
+            disassemble_bytes(&[0x8F, 0x12, 0x03], 0x2000, CpuVariant::Cmos65C02),
+            ("BBS0 $12,$2006".to_string(), 3)
+        );
+    }
+
+    #[test]
+    fn test_disassemble_65c02() {
+        let cases: [(&[u8], &str, u16); 5] = [
+            (&[0xB2, 0x20], "LDA ($20)", 2),
+            (&[0x7C, 0x00, 0x30], "JMP ($3000,X)", 3),
+            (&[0x1A], "INC A", 1),
+            (&[0x9C, 0x00, 0x02], "STZ $0200", 3),
+            (&[0x77, 0x40], "RMB7 $40", 2),
+        ];
+
+        for (bytes, text, length) in cases {
+            assert_eq!(
+                disassemble_bytes(bytes, 0x0400, CpuVariant::Cmos65C02),
+                (text.to_string(), length)
+            );
+        }
+    }
+
+    // Records the address of every read
+    struct ReadLog {
+        memory: Memory,
+        reads: Vec<Word>,
+    }
+
+    impl Bus for ReadLog {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            self.reads.push(addr);
+            return self.memory.read_byte(addr);
+        }
+
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            self.memory.write_byte(addr, value);
+        }
+    }
+
+    #[test]
+    fn test_disassemble_reads() {
+        let cases: [(&[u8], u16); 4] = [
+            (&[0xEA], 1),
+            (&[0xA9, 0x42], 2),
+            (&[0xAD, 0x34, 0x12], 3),
+            (&[0x8B], 1),
+        ];
+
+        for (bytes, length) in cases {
+            let mut bus = ReadLog {
+                memory: Memory {
+                    ..Default::default()
+                },
+                reads: Vec::new(),
+            };
+            for (i, byte) in bytes.iter().enumerate() {
+                bus.memory.write_byte(0x0400 + i as u16, *byte);
+            }
+
+            disassemble(&mut bus, 0x0400, CpuVariant::Nmos6502);
+
+            let expected: Vec<Word> = (0x0400..0x0400 + length).collect();
+            assert_eq!(bus.reads, expected, "{:02X?}", bytes);
+        }
+    }
+
+    #[test]
+    fn test_disassemble_range() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let program = [
+            u8::from(Instruction::LDX_IM),
+            0x00,
+            u8::from(Instruction::INX),
+            u8::from(Instruction::BNE),
+            0xFD,
+            u8::from(Instruction::STX_ABS),
+            0x00,
+            0x02,
+        ];
+        for (i, byte) in program.iter().enumerate() {
+            memory.write_byte(0x0600 + i as u16, *byte);
+        }
+
+        let lines = disassemble_range(&mut memory, 0x0600, 0x0605, CpuVariant::Nmos6502);
+
+        assert_eq!(
+            lines,
+            vec![
+                (0x0600, "LDX #$00".to_string(), 2),
+                (0x0602, "INX".to_string(), 1),
+                (0x0603, "BNE $0602".to_string(), 2),
+                (0x0605, "STX $0200".to_string(), 3),
+            ]
+        );
+    }
+}
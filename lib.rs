@@ -0,0 +1,8869 @@
+#![allow(clippy::needless_return)]
+
+pub mod assembler;
+pub mod banking;
+pub mod disassembler;
+pub mod memory;
+pub mod memory_map;
+pub mod save_state;
+pub mod status;
+pub mod tracer;
+
+use memory::memory::{
+    add_mod_65536, AddressingMode, Bus, BusObserver, Byte, MaskedBus, MemoryLike, ObservedBus,
+    RamPattern, RdyBus, RdySource, Word,
+};
+use status::status::StatusFlags;
+use std::collections::HashSet;
+use std::fmt;
+use tracer::tracer::{format_line, Tracer};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    SP,
+    I,
+    PS,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CpuVariant {
+    // Original MOS 6502
+    #[default]
+    Nmos6502,
+    // WDC 65C02
+    Cmos65C02,
+    // NES CPU, NMOS core without decimal mode
+    Ricoh2A03,
+    // Atari 2600 CPU, NMOS core with 13 address lines
+    Mos6507,
+}
+
+impl CpuVariant {
+    pub fn has_decimal_mode(&self) -> bool {
+        return *self != CpuVariant::Ricoh2A03;
+    }
+
+    // NMOS chips do not carry into the high byte when the pointer of
+    // JMP ($xxFF) crosses a page
+    pub fn has_jmp_indirect_bug(&self) -> bool {
+        return *self != CpuVariant::Cmos65C02;
+    }
+
+    pub fn address_mask(&self) -> Word {
+        return match self {
+            CpuVariant::Mos6507 => 0x1fff,
+            _ => 0xffff,
+        };
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // LDA
+    LDA_IM,
+    LDA_ZP,
+    LDA_ZP_X,
+    LDA_ABS,
+    LDA_ABS_X,
+    LDA_ABS_Y,
+    LDA_IN_X,
+    LDA_IN_Y,
+
+    // LDX
+    LDX_IM,
+    LDX_ZP,
+    LDX_ZP_Y,
+    LDX_ABS,
+    LDX_ABS_Y,
+
+    // LDY
+    LDY_IM,
+    LDY_ZP,
+    LDY_ZP_X,
+    LDY_ABS,
+    LDY_ABS_X,
+
+    // STA
+    STA_ZP,
+    STA_ZP_X,
+    STA_ABS,
+    STA_ABS_X,
+    STA_ABS_Y,
+    STA_IN_X,
+    STA_IN_Y,
+
+    // STX
+    STX_ZP,
+    STX_ZP_Y,
+    STX_ABS,
+
+    // STY
+    STY_ZP,
+    STY_ZP_X,
+    STY_ABS,
+
+    // TRANSFER
+    TAX,
+    TAY,
+    TXA,
+    TYA,
+    TSX,
+    TXS,
+
+    // STACK
+    PHA,
+    PHP,
+    PLA,
+    PLP,
+
+    // LOGICAL
+    // AND
+    AND_IM,
+    AND_ZP,
+    AND_ZP_X,
+    AND_ABS,
+    AND_ABS_X,
+    AND_ABS_Y,
+    AND_IN_X,
+    AND_IN_Y,
+    // EXCLUSIVE OR
+    EOR_IM,
+    EOR_ZP,
+    EOR_ZP_X,
+    EOR_ABS,
+    EOR_ABS_X,
+    EOR_ABS_Y,
+    EOR_IN_X,
+    EOR_IN_Y,
+    // OR
+    ORA_IM,
+    ORA_ZP,
+    ORA_ZP_X,
+    ORA_ABS,
+    ORA_ABS_X,
+    ORA_ABS_Y,
+    ORA_IN_X,
+    ORA_IN_Y,
+    // BIT TEST
+    BIT_ZP,
+    BIT_ABS,
+    // ARITHMETIC
+    // ADD WITH CARRY
+    ADC_IM,
+    ADC_ZP,
+    ADC_ZP_X,
+    ADC_ABS,
+    ADC_ABS_X,
+    ADC_ABS_Y,
+    ADC_IN_X,
+    ADC_IN_Y,
+
+    // SUBSTRACT WITH CARRY
+    SBC_IM,
+    SBC_ZP,
+    SBC_ZP_X,
+    SBC_ABS,
+    SBC_ABS_X,
+    SBC_ABS_Y,
+    SBC_IN_X,
+    SBC_IN_Y,
+
+    // COMPARE
+    CMP_IM,
+    CMP_ZP,
+    CMP_ZP_X,
+    CMP_ABS,
+    CMP_ABS_X,
+    CMP_ABS_Y,
+    CMP_IN_X,
+    CMP_IN_Y,
+
+    // COMPARE X
+    CPX_IM,
+    CPX_ZP,
+    CPX_ABS,
+
+    // COMPARE Y
+    CPY_IM,
+    CPY_ZP,
+    CPY_ABS,
+
+    // INCREMENTS
+    INC_ZP,
+    INC_ZP_X,
+    INC_ABS,
+    INC_ABS_X,
+
+    // INCREMENT X
+    INX,
+
+    // INCREMENT Y
+    INY,
+
+    // DECREMENTS
+    DEC_ZP,
+    DEC_ZP_X,
+    DEC_ABS,
+    DEC_ABS_X,
+
+    // DECREMENT X
+    DEX,
+
+    // DECREMENT Y
+    DEY,
+
+    // SHIFTS
+    // ARITHMETIC SHIFT LEFT
+    ASL_A,
+    ASL_ZP,
+    ASL_ZP_X,
+    ASL_ABS,
+    ASL_ABS_X,
+
+    // LOGICAL SHIFT RIGHT
+    LSR_A,
+    LSR_ZP,
+    LSR_ZP_X,
+    LSR_ABS,
+    LSR_ABS_X,
+
+    // ROTATE LEFT
+    ROL_A,
+    ROL_ZP,
+    ROL_ZP_X,
+    ROL_ABS,
+    ROL_ABS_X,
+
+    // ROTATE RIGHT
+    ROR_A,
+    ROR_ZP,
+    ROR_ZP_X,
+    ROR_ABS,
+    ROR_ABS_X,
+
+    // JUMPS AND CALLS
+    JMP_ABS,
+    JMP_IN,
+    JSR_ABS,
+    RTS_IM,
+
+    // BRANCHES
+    BCC,
+    BCS,
+    BEQ,
+    BMI,
+    BNE,
+    BPL,
+    BVC,
+    BVS,
+
+    // STATUS FLAG CHANGES
+    CLC,
+    CLD,
+    CLI,
+    CLV,
+    SEC,
+    SED,
+    SEI,
+
+    // SYSTEM FUNCTIONS
+    BRK,
+    NOP,
+    RTI,
+
+    // 65C02
+    // BRANCH ALWAYS
+    BRA,
+
+    // STACK
+    PHX,
+    PHY,
+    PLX,
+    PLY,
+
+    // STORE ZERO
+    STZ_ZP,
+    STZ_ZP_X,
+    STZ_ABS,
+    STZ_ABS_X,
+
+    // TEST AND RESET/SET BITS
+    TRB_ZP,
+    TRB_ABS,
+    TSB_ZP,
+    TSB_ABS,
+
+    // INCREMENT/DECREMENT ACCUMULATOR
+    INC_A,
+    DEC_A,
+
+    // BIT TEST
+    BIT_IM,
+    BIT_ZP_X,
+    BIT_ABS_X,
+
+    // ZERO PAGE INDIRECT
+    ORA_IN_ZP,
+    AND_IN_ZP,
+    EOR_IN_ZP,
+    ADC_IN_ZP,
+    STA_IN_ZP,
+    LDA_IN_ZP,
+    CMP_IN_ZP,
+    SBC_IN_ZP,
+
+    // JUMP ABSOLUTE INDEXED INDIRECT
+    JMP_IN_X,
+
+    // RESET MEMORY BIT
+    RMB0,
+    RMB1,
+    RMB2,
+    RMB3,
+    RMB4,
+    RMB5,
+    RMB6,
+    RMB7,
+
+    // SET MEMORY BIT
+    SMB0,
+    SMB1,
+    SMB2,
+    SMB3,
+    SMB4,
+    SMB5,
+    SMB6,
+    SMB7,
+
+    // BRANCH ON BIT RESET
+    BBR0,
+    BBR1,
+    BBR2,
+    BBR3,
+    BBR4,
+    BBR5,
+    BBR6,
+    BBR7,
+
+    // BRANCH ON BIT SET
+    BBS0,
+    BBS1,
+    BBS2,
+    BBS3,
+    BBS4,
+    BBS5,
+    BBS6,
+    BBS7,
+
+    // WAIT AND STOP
+    WAI,
+    STP,
+
+    // 65C02 RESERVED NO OPERATIONS
+    // Columns 3 and B, one byte in one cycle
+    NOP_FAST,
+    // $5C, three bytes in eight cycles
+    NOP_SLOW,
+
+    // NMOS UNDOCUMENTED
+    // LOAD A AND X
+    LAX_ZP,
+    LAX_ZP_Y,
+    LAX_ABS,
+    LAX_ABS_Y,
+    LAX_IN_X,
+    LAX_IN_Y,
+
+    // STORE A AND X
+    SAX_ZP,
+    SAX_ZP_Y,
+    SAX_ABS,
+    SAX_IN_X,
+
+    // SHIFT LEFT THEN OR
+    SLO_ZP,
+    SLO_ZP_X,
+    SLO_ABS,
+    SLO_ABS_X,
+    SLO_ABS_Y,
+    SLO_IN_X,
+    SLO_IN_Y,
+
+    // ROTATE LEFT THEN AND
+    RLA_ZP,
+    RLA_ZP_X,
+    RLA_ABS,
+    RLA_ABS_X,
+    RLA_ABS_Y,
+    RLA_IN_X,
+    RLA_IN_Y,
+
+    // SHIFT RIGHT THEN EOR
+    SRE_ZP,
+    SRE_ZP_X,
+    SRE_ABS,
+    SRE_ABS_X,
+    SRE_ABS_Y,
+    SRE_IN_X,
+    SRE_IN_Y,
+
+    // ROTATE RIGHT THEN ADD
+    RRA_ZP,
+    RRA_ZP_X,
+    RRA_ABS,
+    RRA_ABS_X,
+    RRA_ABS_Y,
+    RRA_IN_X,
+    RRA_IN_Y,
+
+    // DECREMENT THEN COMPARE
+    DCP_ZP,
+    DCP_ZP_X,
+    DCP_ABS,
+    DCP_ABS_X,
+    DCP_ABS_Y,
+    DCP_IN_X,
+    DCP_IN_Y,
+
+    // INCREMENT THEN SUBTRACT
+    ISC_ZP,
+    ISC_ZP_X,
+    ISC_ABS,
+    ISC_ABS_X,
+    ISC_ABS_Y,
+    ISC_IN_X,
+    ISC_IN_Y,
+
+    // IMMEDIATE
+    ANC_IM,
+    ALR_IM,
+    ARR_IM,
+    SBX_IM,
+
+    // NO OPERATION WITH OPERAND
+    NOP_IM,
+    NOP_ZP,
+    NOP_ZP_X,
+    NOP_ABS,
+    NOP_ABS_X,
+
+    // HALT
+    JAM,
+
+    INVALID,
+}
+
+impl From<u8> for Instruction {
+    fn from(a: u8) -> Self {
+        match a {
+            // LDA
+            0xA9 => Instruction::LDA_IM,
+            0xA5 => Instruction::LDA_ZP,
+            0xB5 => Instruction::LDA_ZP_X,
+            0xAD => Instruction::LDA_ABS,
+            0xBD => Instruction::LDA_ABS_X,
+            0xB9 => Instruction::LDA_ABS_Y,
+            0xA1 => Instruction::LDA_IN_X,
+            0xB1 => Instruction::LDA_IN_Y,
+            // LDX
+            0xA2 => Instruction::LDX_IM,
+            0xA6 => Instruction::LDX_ZP,
+            0xB6 => Instruction::LDX_ZP_Y,
+            0xAE => Instruction::LDX_ABS,
+            0xBE => Instruction::LDX_ABS_Y,
+            // LDY
+            0xA0 => Instruction::LDY_IM,
+            0xA4 => Instruction::LDY_ZP,
+            0xB4 => Instruction::LDY_ZP_X,
+            0xAC => Instruction::LDY_ABS,
+            0xBC => Instruction::LDY_ABS_X,
+            // STA
+            0x85 => Instruction::STA_ZP,
+            0x95 => Instruction::STA_ZP_X,
+            0x8D => Instruction::STA_ABS,
+            0x9D => Instruction::STA_ABS_X,
+            0x99 => Instruction::STA_ABS_Y,
+            0x81 => Instruction::STA_IN_X,
+            0x91 => Instruction::STA_IN_Y,
+            // STX
+            0x86 => Instruction::STX_ZP,
+            0x96 => Instruction::STX_ZP_Y,
+            0x8E => Instruction::STX_ABS,
+            // STY
+            0x84 => Instruction::STY_ZP,
+            0x94 => Instruction::STY_ZP_X,
+            0x8C => Instruction::STY_ABS,
+            // Transfer
+            0xAA => Instruction::TAX,
+            0xA8 => Instruction::TAY,
+            0x8A => Instruction::TXA,
+            0x98 => Instruction::TYA,
+            0xBA => Instruction::TSX,
+            0x9A => Instruction::TXS,
+            // Stack
+            0x48 => Instruction::PHA,
+            0x08 => Instruction::PHP,
+            0x68 => Instruction::PLA,
+            0x28 => Instruction::PLP,
+            // Logical
+            // And
+            0x29 => Instruction::AND_IM,
+            0x25 => Instruction::AND_ZP,
+            0x35 => Instruction::AND_ZP_X,
+            0x2D => Instruction::AND_ABS,
+            0x3D => Instruction::AND_ABS_X,
+            0x39 => Instruction::AND_ABS_Y,
+            0x21 => Instruction::AND_IN_X,
+            0x31 => Instruction::AND_IN_Y,
+            // Exclusive Or
+            0x49 => Instruction::EOR_IM,
+            0x45 => Instruction::EOR_ZP,
+            0x55 => Instruction::EOR_ZP_X,
+            0x4D => Instruction::EOR_ABS,
+            0x5D => Instruction::EOR_ABS_X,
+            0x59 => Instruction::EOR_ABS_Y,
+            0x41 => Instruction::EOR_IN_X,
+            0x51 => Instruction::EOR_IN_Y,
+            // Or
+            0x09 => Instruction::ORA_IM,
+            0x05 => Instruction::ORA_ZP,
+            0x15 => Instruction::ORA_ZP_X,
+            0x0D => Instruction::ORA_ABS,
+            0x1D => Instruction::ORA_ABS_X,
+            0x19 => Instruction::ORA_ABS_Y,
+            0x01 => Instruction::ORA_IN_X,
+            0x11 => Instruction::ORA_IN_Y,
+            // Bit
+            0x24 => Instruction::BIT_ZP,
+            0x2C => Instruction::BIT_ABS,
+            // Arithmetic
+            // ADC
+            0x69 => Instruction::ADC_IM,
+            0x65 => Instruction::ADC_ZP,
+            0x75 => Instruction::ADC_ZP_X,
+            0x6D => Instruction::ADC_ABS,
+            0x7D => Instruction::ADC_ABS_X,
+            0x79 => Instruction::ADC_ABS_Y,
+            0x61 => Instruction::ADC_IN_X,
+            0x71 => Instruction::ADC_IN_Y,
+            // SBC
+            0xE9 => Instruction::SBC_IM,
+            0xE5 => Instruction::SBC_ZP,
+            0xF5 => Instruction::SBC_ZP_X,
+            0xED => Instruction::SBC_ABS,
+            0xFD => Instruction::SBC_ABS_X,
+            0xF9 => Instruction::SBC_ABS_Y,
+            0xE1 => Instruction::SBC_IN_X,
+            0xF1 => Instruction::SBC_IN_Y,
+            // CMP
+            0xC9 => Instruction::CMP_IM,
+            0xC5 => Instruction::CMP_ZP,
+            0xD5 => Instruction::CMP_ZP_X,
+            0xCD => Instruction::CMP_ABS,
+            0xDD => Instruction::CMP_ABS_X,
+            0xD9 => Instruction::CMP_ABS_Y,
+            0xC1 => Instruction::CMP_IN_X,
+            0xD1 => Instruction::CMP_IN_Y,
+            // CPX
+            0xE0 => Instruction::CPX_IM,
+            0xE4 => Instruction::CPX_ZP,
+            0xEC => Instruction::CPX_ABS,
+            // CPY
+            0xC0 => Instruction::CPY_IM,
+            0xC4 => Instruction::CPY_ZP,
+            0xCC => Instruction::CPY_ABS,
+            // Increments
+            // INC
+            0xE6 => Instruction::INC_ZP,
+            0xF6 => Instruction::INC_ZP_X,
+            0xEE => Instruction::INC_ABS,
+            0xFE => Instruction::INC_ABS_X,
+            // INX
+            0xE8 => Instruction::INX,
+            // INY
+            0xC8 => Instruction::INY,
+            // Decrements
+            // DEC
+            0xC6 => Instruction::DEC_ZP,
+            0xD6 => Instruction::DEC_ZP_X,
+            0xCE => Instruction::DEC_ABS,
+            0xDE => Instruction::DEC_ABS_X,
+            // DEX
+            0xCA => Instruction::DEX,
+            // DEY
+            0x88 => Instruction::DEY,
+
+            // Shifts
+            // ASL
+            0x0A => Instruction::ASL_A,
+            0x06 => Instruction::ASL_ZP,
+            0x16 => Instruction::ASL_ZP_X,
+            0x0E => Instruction::ASL_ABS,
+            0x1E => Instruction::ASL_ABS_X,
+
+            // LSR
+            0x4A => Instruction::LSR_A,
+            0x46 => Instruction::LSR_ZP,
+            0x56 => Instruction::LSR_ZP_X,
+            0x4E => Instruction::LSR_ABS,
+            0x5E => Instruction::LSR_ABS_X,
+
+            // ROL
+            0x2A => Instruction::ROL_A,
+            0x26 => Instruction::ROL_ZP,
+            0x36 => Instruction::ROL_ZP_X,
+            0x2E => Instruction::ROL_ABS,
+            0x3E => Instruction::ROL_ABS_X,
+
+            // ROR
+            0x6A => Instruction::ROR_A,
+            0x66 => Instruction::ROR_ZP,
+            0x76 => Instruction::ROR_ZP_X,
+            0x6E => Instruction::ROR_ABS,
+            0x7E => Instruction::ROR_ABS_X,
+
+            // Jumps and Calls
+            0x4C => Instruction::JMP_ABS,
+            0x6C => Instruction::JMP_IN,
+            0x20 => Instruction::JSR_ABS,
+            0x60 => Instruction::RTS_IM,
+            // Branches
+            0x90 => Instruction::BCC,
+            0xB0 => Instruction::BCS,
+            0xF0 => Instruction::BEQ,
+            0x30 => Instruction::BMI,
+            0xD0 => Instruction::BNE,
+            0x10 => Instruction::BPL,
+            0x50 => Instruction::BVC,
+            0x70 => Instruction::BVS,
+            // Status Flag Changes
+            0x18 => Instruction::CLC,
+            0xD8 => Instruction::CLD,
+            0x58 => Instruction::CLI,
+            0xB8 => Instruction::CLV,
+            0x38 => Instruction::SEC,
+            0xF8 => Instruction::SED,
+            0x78 => Instruction::SEI,
+            // System functions
+            0x00 => Instruction::BRK,
+            0xEA => Instruction::NOP,
+            0x40 => Instruction::RTI,
+
+            // Undocumented
+            // LAX
+            0xA7 => Instruction::LAX_ZP,
+            0xB7 => Instruction::LAX_ZP_Y,
+            0xAF => Instruction::LAX_ABS,
+            0xBF => Instruction::LAX_ABS_Y,
+            0xA3 => Instruction::LAX_IN_X,
+            0xB3 => Instruction::LAX_IN_Y,
+            // SAX
+            0x87 => Instruction::SAX_ZP,
+            0x97 => Instruction::SAX_ZP_Y,
+            0x8F => Instruction::SAX_ABS,
+            0x83 => Instruction::SAX_IN_X,
+            // SLO
+            0x07 => Instruction::SLO_ZP,
+            0x17 => Instruction::SLO_ZP_X,
+            0x0F => Instruction::SLO_ABS,
+            0x1F => Instruction::SLO_ABS_X,
+            0x1B => Instruction::SLO_ABS_Y,
+            0x03 => Instruction::SLO_IN_X,
+            0x13 => Instruction::SLO_IN_Y,
+            // RLA
+            0x27 => Instruction::RLA_ZP,
+            0x37 => Instruction::RLA_ZP_X,
+            0x2F => Instruction::RLA_ABS,
+            0x3F => Instruction::RLA_ABS_X,
+            0x3B => Instruction::RLA_ABS_Y,
+            0x23 => Instruction::RLA_IN_X,
+            0x33 => Instruction::RLA_IN_Y,
+            // SRE
+            0x47 => Instruction::SRE_ZP,
+            0x57 => Instruction::SRE_ZP_X,
+            0x4F => Instruction::SRE_ABS,
+            0x5F => Instruction::SRE_ABS_X,
+            0x5B => Instruction::SRE_ABS_Y,
+            0x43 => Instruction::SRE_IN_X,
+            0x53 => Instruction::SRE_IN_Y,
+            // RRA
+            0x67 => Instruction::RRA_ZP,
+            0x77 => Instruction::RRA_ZP_X,
+            0x6F => Instruction::RRA_ABS,
+            0x7F => Instruction::RRA_ABS_X,
+            0x7B => Instruction::RRA_ABS_Y,
+            0x63 => Instruction::RRA_IN_X,
+            0x73 => Instruction::RRA_IN_Y,
+            // DCP
+            0xC7 => Instruction::DCP_ZP,
+            0xD7 => Instruction::DCP_ZP_X,
+            0xCF => Instruction::DCP_ABS,
+            0xDF => Instruction::DCP_ABS_X,
+            0xDB => Instruction::DCP_ABS_Y,
+            0xC3 => Instruction::DCP_IN_X,
+            0xD3 => Instruction::DCP_IN_Y,
+            // ISC
+            0xE7 => Instruction::ISC_ZP,
+            0xF7 => Instruction::ISC_ZP_X,
+            0xEF => Instruction::ISC_ABS,
+            0xFF => Instruction::ISC_ABS_X,
+            0xFB => Instruction::ISC_ABS_Y,
+            0xE3 => Instruction::ISC_IN_X,
+            0xF3 => Instruction::ISC_IN_Y,
+            // Immediate
+            0x0B | 0x2B => Instruction::ANC_IM,
+            0x4B => Instruction::ALR_IM,
+            0x6B => Instruction::ARR_IM,
+            0xCB => Instruction::SBX_IM,
+            0xEB => Instruction::SBC_IM,
+            // NOPs
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => Instruction::NOP,
+            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => Instruction::NOP_IM,
+            0x04 | 0x44 | 0x64 => Instruction::NOP_ZP,
+            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => Instruction::NOP_ZP_X,
+            0x0C => Instruction::NOP_ABS,
+            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => Instruction::NOP_ABS_X,
+            // JAM
+            0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => {
+                Instruction::JAM
+            }
+
+            _ => Instruction::INVALID,
+        }
+    }
+}
+
+impl From<Instruction> for u8 {
+    fn from(a: Instruction) -> Self {
+        match a {
+            // LDA
+            Instruction::LDA_IM => 0xA9,
+            Instruction::LDA_ZP => 0xA5,
+            Instruction::LDA_ZP_X => 0xB5,
+            Instruction::LDA_ABS => 0xAD,
+            Instruction::LDA_ABS_X => 0xBD,
+            Instruction::LDA_ABS_Y => 0xB9,
+            Instruction::LDA_IN_X => 0xA1,
+            Instruction::LDA_IN_Y => 0xB1,
+            // LDX
+            Instruction::LDX_IM => 0xA2,
+            Instruction::LDX_ZP => 0xA6,
+            Instruction::LDX_ZP_Y => 0xB6,
+            Instruction::LDX_ABS => 0xAE,
+            Instruction::LDX_ABS_Y => 0xBE,
+            // LDY
+            Instruction::LDY_IM => 0xA0,
+            Instruction::LDY_ZP => 0xA4,
+            Instruction::LDY_ZP_X => 0xB4,
+            Instruction::LDY_ABS => 0xAC,
+            Instruction::LDY_ABS_X => 0xBC,
+
+            // STA
+            Instruction::STA_ZP => 0x85,
+            Instruction::STA_ZP_X => 0x95,
+            Instruction::STA_ABS => 0x8D,
+            Instruction::STA_ABS_X => 0x9D,
+            Instruction::STA_ABS_Y => 0x99,
+            Instruction::STA_IN_X => 0x81,
+            Instruction::STA_IN_Y => 0x91,
+            // STX
+            Instruction::STX_ZP => 0x86,
+            Instruction::STX_ZP_Y => 0x96,
+            Instruction::STX_ABS => 0x8E,
+            // STY
+            Instruction::STY_ZP => 0x84,
+            Instruction::STY_ZP_X => 0x94,
+            Instruction::STY_ABS => 0x8C,
+            // Transfer
+            Instruction::TAX => 0xAA,
+            Instruction::TAY => 0xA8,
+            Instruction::TXA => 0x8A,
+            Instruction::TYA => 0x98,
+            Instruction::TSX => 0xBA,
+            Instruction::TXS => 0x9A,
+            // Stack
+            Instruction::PHA => 0x48,
+            Instruction::PHP => 0x08,
+            Instruction::PLA => 0x68,
+            Instruction::PLP => 0x28,
+            // Logical
+            // And
+            Instruction::AND_IM => 0x29,
+            Instruction::AND_ZP => 0x25,
+            Instruction::AND_ZP_X => 0x35,
+            Instruction::AND_ABS => 0x2D,
+            Instruction::AND_ABS_X => 0x3D,
+            Instruction::AND_ABS_Y => 0x39,
+            Instruction::AND_IN_X => 0x21,
+            Instruction::AND_IN_Y => 0x31,
+            // Exclusive Or
+            Instruction::EOR_IM => 0x49,
+            Instruction::EOR_ZP => 0x45,
+            Instruction::EOR_ZP_X => 0x55,
+            Instruction::EOR_ABS => 0x4D,
+            Instruction::EOR_ABS_X => 0x5D,
+            Instruction::EOR_ABS_Y => 0x59,
+            Instruction::EOR_IN_X => 0x41,
+            Instruction::EOR_IN_Y => 0x51,
+            // Or
+            Instruction::ORA_IM => 0x09,
+            Instruction::ORA_ZP => 0x05,
+            Instruction::ORA_ZP_X => 0x15,
+            Instruction::ORA_ABS => 0x0D,
+            Instruction::ORA_ABS_X => 0x1D,
+            Instruction::ORA_ABS_Y => 0x19,
+            Instruction::ORA_IN_X => 0x01,
+            Instruction::ORA_IN_Y => 0x11,
+            // Bit
+            Instruction::BIT_ZP => 0x24,
+            Instruction::BIT_ABS => 0x2C,
+            // Arithmetic
+            // ADC
+            Instruction::ADC_IM => 0x69,
+            Instruction::ADC_ZP => 0x65,
+            Instruction::ADC_ZP_X => 0x75,
+            Instruction::ADC_ABS => 0x6D,
+            Instruction::ADC_ABS_X => 0x7D,
+            Instruction::ADC_ABS_Y => 0x79,
+            Instruction::ADC_IN_X => 0x61,
+            Instruction::ADC_IN_Y => 0x71,
+            // SBC
+            Instruction::SBC_IM => 0xE9,
+            Instruction::SBC_ZP => 0xE5,
+            Instruction::SBC_ZP_X => 0xF5,
+            Instruction::SBC_ABS => 0xED,
+            Instruction::SBC_ABS_X => 0xFD,
+            Instruction::SBC_ABS_Y => 0xF9,
+            Instruction::SBC_IN_X => 0xE1,
+            Instruction::SBC_IN_Y => 0xF1,
+            // CMP
+            Instruction::CMP_IM => 0xC9,
+            Instruction::CMP_ZP => 0xC5,
+            Instruction::CMP_ZP_X => 0xD5,
+            Instruction::CMP_ABS => 0xCD,
+            Instruction::CMP_ABS_X => 0xDD,
+            Instruction::CMP_ABS_Y => 0xD9,
+            Instruction::CMP_IN_X => 0xC1,
+            Instruction::CMP_IN_Y => 0xD1,
+            // CPX
+            Instruction::CPX_IM => 0xE0,
+            Instruction::CPX_ZP => 0xE4,
+            Instruction::CPX_ABS => 0xEC,
+            // CPY
+            Instruction::CPY_IM => 0xC0,
+            Instruction::CPY_ZP => 0xC4,
+            Instruction::CPY_ABS => 0xCC,
+            // Increments
+            // INC
+            Instruction::INC_ZP => 0xE6,
+            Instruction::INC_ZP_X => 0xF6,
+            Instruction::INC_ABS => 0xEE,
+            Instruction::INC_ABS_X => 0xFE,
+            // INX
+            Instruction::INX => 0xE8,
+            // INY
+            Instruction::INY => 0xC8,
+            // Decrements
+            // DEC
+            Instruction::DEC_ZP => 0xC6,
+            Instruction::DEC_ZP_X => 0xD6,
+            Instruction::DEC_ABS => 0xCE,
+            Instruction::DEC_ABS_X => 0xDE,
+            // DEX
+            Instruction::DEX => 0xCA,
+            // DEY
+            Instruction::DEY => 0x88,
+            // Shifts
+            // ASL
+            Instruction::ASL_A => 0x0A,
+            Instruction::ASL_ZP => 0x06,
+            Instruction::ASL_ZP_X => 0x16,
+            Instruction::ASL_ABS => 0x0E,
+            Instruction::ASL_ABS_X => 0x1E,
+            // LSR
+            Instruction::LSR_A => 0x4A,
+            Instruction::LSR_ZP => 0x46,
+            Instruction::LSR_ZP_X => 0x56,
+            Instruction::LSR_ABS => 0x4E,
+            Instruction::LSR_ABS_X => 0x5E,
+            // ROL
+            Instruction::ROL_A => 0x2A,
+            Instruction::ROL_ZP => 0x26,
+            Instruction::ROL_ZP_X => 0x36,
+            Instruction::ROL_ABS => 0x2E,
+            Instruction::ROL_ABS_X => 0x3E,
+            // ROR
+            Instruction::ROR_A => 0x6A,
+            Instruction::ROR_ZP => 0x66,
+            Instruction::ROR_ZP_X => 0x76,
+            Instruction::ROR_ABS => 0x6E,
+            Instruction::ROR_ABS_X => 0x7E,
+            // Jumps and Calls
+            Instruction::JMP_ABS => 0x4C,
+            Instruction::JMP_IN => 0x6C,
+            Instruction::JSR_ABS => 0x20,
+            Instruction::RTS_IM => 0x60,
+            // Branches
+            Instruction::BCC => 0x90,
+            Instruction::BCS => 0xB0,
+            Instruction::BEQ => 0xF0,
+            Instruction::BMI => 0x30,
+            Instruction::BNE => 0xD0,
+            Instruction::BPL => 0x10,
+            Instruction::BVC => 0x50,
+            Instruction::BVS => 0x70,
+            // Status Flag Changes
+            Instruction::CLC => 0x18,
+            Instruction::CLD => 0xD8,
+            Instruction::CLI => 0x58,
+            Instruction::CLV => 0xB8,
+            Instruction::SEC => 0x38,
+            Instruction::SED => 0xF8,
+            Instruction::SEI => 0x78,
+            // System functions
+            Instruction::BRK => 0x00,
+            Instruction::NOP => 0xEA,
+            Instruction::RTI => 0x40,
+
+            // 65C02
+            // Branch always
+            Instruction::BRA => 0x80,
+            // Stack
+            Instruction::PHX => 0xDA,
+            Instruction::PHY => 0x5A,
+            Instruction::PLX => 0xFA,
+            Instruction::PLY => 0x7A,
+            // Store zero
+            Instruction::STZ_ZP => 0x64,
+            Instruction::STZ_ZP_X => 0x74,
+            Instruction::STZ_ABS => 0x9C,
+            Instruction::STZ_ABS_X => 0x9E,
+            // Test and reset/set bits
+            Instruction::TRB_ZP => 0x14,
+            Instruction::TRB_ABS => 0x1C,
+            Instruction::TSB_ZP => 0x04,
+            Instruction::TSB_ABS => 0x0C,
+            // Increment/decrement accumulator
+            Instruction::INC_A => 0x1A,
+            Instruction::DEC_A => 0x3A,
+            // Bit
+            Instruction::BIT_IM => 0x89,
+            Instruction::BIT_ZP_X => 0x34,
+            Instruction::BIT_ABS_X => 0x3C,
+            // Zero page indirect
+            Instruction::ORA_IN_ZP => 0x12,
+            Instruction::AND_IN_ZP => 0x32,
+            Instruction::EOR_IN_ZP => 0x52,
+            Instruction::ADC_IN_ZP => 0x72,
+            Instruction::STA_IN_ZP => 0x92,
+            Instruction::LDA_IN_ZP => 0xB2,
+            Instruction::CMP_IN_ZP => 0xD2,
+            Instruction::SBC_IN_ZP => 0xF2,
+            // Jump absolute indexed indirect
+            Instruction::JMP_IN_X => 0x7C,
+            // Reset memory bit
+            Instruction::RMB0 => 0x07,
+            Instruction::RMB1 => 0x17,
+            Instruction::RMB2 => 0x27,
+            Instruction::RMB3 => 0x37,
+            Instruction::RMB4 => 0x47,
+            Instruction::RMB5 => 0x57,
+            Instruction::RMB6 => 0x67,
+            Instruction::RMB7 => 0x77,
+            // Set memory bit
+            Instruction::SMB0 => 0x87,
+            Instruction::SMB1 => 0x97,
+            Instruction::SMB2 => 0xA7,
+            Instruction::SMB3 => 0xB7,
+            Instruction::SMB4 => 0xC7,
+            Instruction::SMB5 => 0xD7,
+            Instruction::SMB6 => 0xE7,
+            Instruction::SMB7 => 0xF7,
+            // Branch on bit reset
+            Instruction::BBR0 => 0x0F,
+            Instruction::BBR1 => 0x1F,
+            Instruction::BBR2 => 0x2F,
+            Instruction::BBR3 => 0x3F,
+            Instruction::BBR4 => 0x4F,
+            Instruction::BBR5 => 0x5F,
+            Instruction::BBR6 => 0x6F,
+            Instruction::BBR7 => 0x7F,
+            // Branch on bit set
+            Instruction::BBS0 => 0x8F,
+            Instruction::BBS1 => 0x9F,
+            Instruction::BBS2 => 0xAF,
+            Instruction::BBS3 => 0xBF,
+            Instruction::BBS4 => 0xCF,
+            Instruction::BBS5 => 0xDF,
+            Instruction::BBS6 => 0xEF,
+            Instruction::BBS7 => 0xFF,
+            // Wait and stop
+            Instruction::WAI => 0xCB,
+            Instruction::STP => 0xDB,
+            // Undocumented
+            Instruction::LAX_ZP => 0xA7,
+            Instruction::LAX_ZP_Y => 0xB7,
+            Instruction::LAX_ABS => 0xAF,
+            Instruction::LAX_ABS_Y => 0xBF,
+            Instruction::LAX_IN_X => 0xA3,
+            Instruction::LAX_IN_Y => 0xB3,
+            Instruction::SAX_ZP => 0x87,
+            Instruction::SAX_ZP_Y => 0x97,
+            Instruction::SAX_ABS => 0x8F,
+            Instruction::SAX_IN_X => 0x83,
+            Instruction::SLO_ZP => 0x07,
+            Instruction::SLO_ZP_X => 0x17,
+            Instruction::SLO_ABS => 0x0F,
+            Instruction::SLO_ABS_X => 0x1F,
+            Instruction::SLO_ABS_Y => 0x1B,
+            Instruction::SLO_IN_X => 0x03,
+            Instruction::SLO_IN_Y => 0x13,
+            Instruction::RLA_ZP => 0x27,
+            Instruction::RLA_ZP_X => 0x37,
+            Instruction::RLA_ABS => 0x2F,
+            Instruction::RLA_ABS_X => 0x3F,
+            Instruction::RLA_ABS_Y => 0x3B,
+            Instruction::RLA_IN_X => 0x23,
+            Instruction::RLA_IN_Y => 0x33,
+            Instruction::SRE_ZP => 0x47,
+            Instruction::SRE_ZP_X => 0x57,
+            Instruction::SRE_ABS => 0x4F,
+            Instruction::SRE_ABS_X => 0x5F,
+            Instruction::SRE_ABS_Y => 0x5B,
+            Instruction::SRE_IN_X => 0x43,
+            Instruction::SRE_IN_Y => 0x53,
+            Instruction::RRA_ZP => 0x67,
+            Instruction::RRA_ZP_X => 0x77,
+            Instruction::RRA_ABS => 0x6F,
+            Instruction::RRA_ABS_X => 0x7F,
+            Instruction::RRA_ABS_Y => 0x7B,
+            Instruction::RRA_IN_X => 0x63,
+            Instruction::RRA_IN_Y => 0x73,
+            Instruction::DCP_ZP => 0xC7,
+            Instruction::DCP_ZP_X => 0xD7,
+            Instruction::DCP_ABS => 0xCF,
+            Instruction::DCP_ABS_X => 0xDF,
+            Instruction::DCP_ABS_Y => 0xDB,
+            Instruction::DCP_IN_X => 0xC3,
+            Instruction::DCP_IN_Y => 0xD3,
+            Instruction::ISC_ZP => 0xE7,
+            Instruction::ISC_ZP_X => 0xF7,
+            Instruction::ISC_ABS => 0xEF,
+            Instruction::ISC_ABS_X => 0xFF,
+            Instruction::ISC_ABS_Y => 0xFB,
+            Instruction::ISC_IN_X => 0xE3,
+            Instruction::ISC_IN_Y => 0xF3,
+            Instruction::ANC_IM => 0x0B,
+            Instruction::ALR_IM => 0x4B,
+            Instruction::ARR_IM => 0x6B,
+            Instruction::SBX_IM => 0xCB,
+            Instruction::NOP_IM => 0x80,
+            Instruction::NOP_ZP => 0x04,
+            Instruction::NOP_ZP_X => 0x14,
+            Instruction::NOP_ABS => 0x0C,
+            Instruction::NOP_ABS_X => 0x1C,
+            Instruction::NOP_FAST => 0x03,
+            Instruction::NOP_SLOW => 0x5C,
+            Instruction::JAM => 0x02,
+
+            Instruction::INVALID => 0xFF,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    Irq,
+    Nmi,
+}
+
+impl Instruction {
+    // Decodes opcode using the instruction set of the given variant
+    pub fn decode(opcode: Byte, variant: CpuVariant) -> Instruction {
+        return match variant {
+            CpuVariant::Nmos6502 | CpuVariant::Ricoh2A03 | CpuVariant::Mos6507 => {
+                Instruction::from(opcode)
+            }
+            CpuVariant::Cmos65C02 => match opcode {
+                // Branch always
+                0x80 => Instruction::BRA,
+                // Stack
+                0xDA => Instruction::PHX,
+                0x5A => Instruction::PHY,
+                0xFA => Instruction::PLX,
+                0x7A => Instruction::PLY,
+                // Store zero
+                0x64 => Instruction::STZ_ZP,
+                0x74 => Instruction::STZ_ZP_X,
+                0x9C => Instruction::STZ_ABS,
+                0x9E => Instruction::STZ_ABS_X,
+                // Test and reset/set bits
+                0x14 => Instruction::TRB_ZP,
+                0x1C => Instruction::TRB_ABS,
+                0x04 => Instruction::TSB_ZP,
+                0x0C => Instruction::TSB_ABS,
+                // Increment/decrement accumulator
+                0x1A => Instruction::INC_A,
+                0x3A => Instruction::DEC_A,
+                // Bit
+                0x89 => Instruction::BIT_IM,
+                0x34 => Instruction::BIT_ZP_X,
+                0x3C => Instruction::BIT_ABS_X,
+                // Zero page indirect
+                0x12 => Instruction::ORA_IN_ZP,
+                0x32 => Instruction::AND_IN_ZP,
+                0x52 => Instruction::EOR_IN_ZP,
+                0x72 => Instruction::ADC_IN_ZP,
+                0x92 => Instruction::STA_IN_ZP,
+                0xB2 => Instruction::LDA_IN_ZP,
+                0xD2 => Instruction::CMP_IN_ZP,
+                0xF2 => Instruction::SBC_IN_ZP,
+                // Jump absolute indexed indirect
+                0x7C => Instruction::JMP_IN_X,
+                // Reset memory bit
+                0x07 => Instruction::RMB0,
+                0x17 => Instruction::RMB1,
+                0x27 => Instruction::RMB2,
+                0x37 => Instruction::RMB3,
+                0x47 => Instruction::RMB4,
+                0x57 => Instruction::RMB5,
+                0x67 => Instruction::RMB6,
+                0x77 => Instruction::RMB7,
+                // Set memory bit
+                0x87 => Instruction::SMB0,
+                0x97 => Instruction::SMB1,
+                0xA7 => Instruction::SMB2,
+                0xB7 => Instruction::SMB3,
+                0xC7 => Instruction::SMB4,
+                0xD7 => Instruction::SMB5,
+                0xE7 => Instruction::SMB6,
+                0xF7 => Instruction::SMB7,
+                // Branch on bit reset
+                0x0F => Instruction::BBR0,
+                0x1F => Instruction::BBR1,
+                0x2F => Instruction::BBR2,
+                0x3F => Instruction::BBR3,
+                0x4F => Instruction::BBR4,
+                0x5F => Instruction::BBR5,
+                0x6F => Instruction::BBR6,
+                0x7F => Instruction::BBR7,
+                // Branch on bit set
+                0x8F => Instruction::BBS0,
+                0x9F => Instruction::BBS1,
+                0xAF => Instruction::BBS2,
+                0xBF => Instruction::BBS3,
+                0xCF => Instruction::BBS4,
+                0xDF => Instruction::BBS5,
+                0xEF => Instruction::BBS6,
+                0xFF => Instruction::BBS7,
+                // Wait and stop
+                0xCB => Instruction::WAI,
+                0xDB => Instruction::STP,
+                // Undocumented NMOS opcodes are no-ops on the 65C02
+                0x5C => Instruction::NOP_SLOW,
+                0xDC | 0xFC => Instruction::NOP_ABS,
+
+                _ => match opcode & 0x0f {
+                    0x02 => Instruction::NOP_IM,
+                    0x03 | 0x0b => Instruction::NOP_FAST,
+                    _ => Instruction::from(opcode),
+                },
+            },
+        };
+    }
+
+    // Addressing mode and index register of the operand
+    pub fn addressing_mode(self) -> (AddressingMode, Register) {
+        return match self {
+            Instruction::TAX
+            | Instruction::TAY
+            | Instruction::TXA
+            | Instruction::TYA
+            | Instruction::TSX
+            | Instruction::TXS
+            | Instruction::PHA
+            | Instruction::PHP
+            | Instruction::PLA
+            | Instruction::PLP
+            | Instruction::INX
+            | Instruction::INY
+            | Instruction::DEX
+            | Instruction::DEY
+            | Instruction::CLC
+            | Instruction::CLD
+            | Instruction::CLI
+            | Instruction::CLV
+            | Instruction::SEC
+            | Instruction::SED
+            | Instruction::SEI
+            | Instruction::BRK
+            | Instruction::NOP
+            | Instruction::NOP_FAST
+            | Instruction::RTI
+            | Instruction::RTS_IM
+            | Instruction::PHX
+            | Instruction::PHY
+            | Instruction::PLX
+            | Instruction::PLY
+            | Instruction::WAI
+            | Instruction::STP
+            | Instruction::JAM
+            | Instruction::INVALID => (AddressingMode::Implied, Register::None),
+            Instruction::ASL_A
+            | Instruction::LSR_A
+            | Instruction::ROL_A
+            | Instruction::ROR_A
+            | Instruction::INC_A
+            | Instruction::DEC_A => (AddressingMode::Accumulator, Register::None),
+            Instruction::LDA_IM
+            | Instruction::LDX_IM
+            | Instruction::LDY_IM
+            | Instruction::AND_IM
+            | Instruction::EOR_IM
+            | Instruction::ORA_IM
+            | Instruction::ADC_IM
+            | Instruction::SBC_IM
+            | Instruction::CMP_IM
+            | Instruction::CPX_IM
+            | Instruction::CPY_IM
+            | Instruction::BIT_IM
+            | Instruction::ANC_IM
+            | Instruction::ALR_IM
+            | Instruction::ARR_IM
+            | Instruction::SBX_IM
+            | Instruction::NOP_IM => (AddressingMode::Immediate, Register::None),
+            Instruction::LDA_ZP
+            | Instruction::LDX_ZP
+            | Instruction::LDY_ZP
+            | Instruction::STA_ZP
+            | Instruction::STX_ZP
+            | Instruction::STY_ZP
+            | Instruction::AND_ZP
+            | Instruction::EOR_ZP
+            | Instruction::ORA_ZP
+            | Instruction::BIT_ZP
+            | Instruction::ADC_ZP
+            | Instruction::SBC_ZP
+            | Instruction::CMP_ZP
+            | Instruction::CPX_ZP
+            | Instruction::CPY_ZP
+            | Instruction::INC_ZP
+            | Instruction::DEC_ZP
+            | Instruction::ASL_ZP
+            | Instruction::LSR_ZP
+            | Instruction::ROL_ZP
+            | Instruction::ROR_ZP
+            | Instruction::STZ_ZP
+            | Instruction::TRB_ZP
+            | Instruction::TSB_ZP
+            | Instruction::RMB0
+            | Instruction::RMB1
+            | Instruction::RMB2
+            | Instruction::RMB3
+            | Instruction::RMB4
+            | Instruction::RMB5
+            | Instruction::RMB6
+            | Instruction::RMB7
+            | Instruction::SMB0
+            | Instruction::SMB1
+            | Instruction::SMB2
+            | Instruction::SMB3
+            | Instruction::SMB4
+            | Instruction::SMB5
+            | Instruction::SMB6
+            | Instruction::SMB7
+            | Instruction::LAX_ZP
+            | Instruction::SAX_ZP
+            | Instruction::SLO_ZP
+            | Instruction::RLA_ZP
+            | Instruction::SRE_ZP
+            | Instruction::RRA_ZP
+            | Instruction::DCP_ZP
+            | Instruction::ISC_ZP
+            | Instruction::NOP_ZP => (AddressingMode::ZeroPage, Register::None),
+            Instruction::LDA_ZP_X
+            | Instruction::LDY_ZP_X
+            | Instruction::STA_ZP_X
+            | Instruction::STY_ZP_X
+            | Instruction::AND_ZP_X
+            | Instruction::EOR_ZP_X
+            | Instruction::ORA_ZP_X
+            | Instruction::ADC_ZP_X
+            | Instruction::SBC_ZP_X
+            | Instruction::CMP_ZP_X
+            | Instruction::INC_ZP_X
+            | Instruction::DEC_ZP_X
+            | Instruction::ASL_ZP_X
+            | Instruction::LSR_ZP_X
+            | Instruction::ROL_ZP_X
+            | Instruction::ROR_ZP_X
+            | Instruction::STZ_ZP_X
+            | Instruction::BIT_ZP_X
+            | Instruction::SLO_ZP_X
+            | Instruction::RLA_ZP_X
+            | Instruction::SRE_ZP_X
+            | Instruction::RRA_ZP_X
+            | Instruction::DCP_ZP_X
+            | Instruction::ISC_ZP_X
+            | Instruction::NOP_ZP_X => (AddressingMode::ZeroPageReg, Register::X),
+            Instruction::LDX_ZP_Y
+            | Instruction::STX_ZP_Y
+            | Instruction::LAX_ZP_Y
+            | Instruction::SAX_ZP_Y => (AddressingMode::ZeroPageReg, Register::Y),
+            Instruction::LDA_ABS
+            | Instruction::LDX_ABS
+            | Instruction::LDY_ABS
+            | Instruction::STA_ABS
+            | Instruction::STX_ABS
+            | Instruction::STY_ABS
+            | Instruction::AND_ABS
+            | Instruction::EOR_ABS
+            | Instruction::ORA_ABS
+            | Instruction::BIT_ABS
+            | Instruction::ADC_ABS
+            | Instruction::SBC_ABS
+            | Instruction::CMP_ABS
+            | Instruction::CPX_ABS
+            | Instruction::CPY_ABS
+            | Instruction::INC_ABS
+            | Instruction::DEC_ABS
+            | Instruction::ASL_ABS
+            | Instruction::LSR_ABS
+            | Instruction::ROL_ABS
+            | Instruction::ROR_ABS
+            | Instruction::JMP_ABS
+            | Instruction::JSR_ABS
+            | Instruction::STZ_ABS
+            | Instruction::TRB_ABS
+            | Instruction::TSB_ABS
+            | Instruction::LAX_ABS
+            | Instruction::SAX_ABS
+            | Instruction::SLO_ABS
+            | Instruction::RLA_ABS
+            | Instruction::SRE_ABS
+            | Instruction::RRA_ABS
+            | Instruction::DCP_ABS
+            | Instruction::ISC_ABS
+            | Instruction::NOP_ABS
+            | Instruction::NOP_SLOW => (AddressingMode::Absolute, Register::None),
+            Instruction::LDA_ABS_X
+            | Instruction::LDY_ABS_X
+            | Instruction::STA_ABS_X
+            | Instruction::AND_ABS_X
+            | Instruction::EOR_ABS_X
+            | Instruction::ORA_ABS_X
+            | Instruction::ADC_ABS_X
+            | Instruction::SBC_ABS_X
+            | Instruction::CMP_ABS_X
+            | Instruction::INC_ABS_X
+            | Instruction::DEC_ABS_X
+            | Instruction::ASL_ABS_X
+            | Instruction::LSR_ABS_X
+            | Instruction::ROL_ABS_X
+            | Instruction::ROR_ABS_X
+            | Instruction::STZ_ABS_X
+            | Instruction::BIT_ABS_X
+            | Instruction::SLO_ABS_X
+            | Instruction::RLA_ABS_X
+            | Instruction::SRE_ABS_X
+            | Instruction::RRA_ABS_X
+            | Instruction::DCP_ABS_X
+            | Instruction::ISC_ABS_X
+            | Instruction::NOP_ABS_X => (AddressingMode::AbsoluteReg, Register::X),
+            Instruction::LDA_ABS_Y
+            | Instruction::LDX_ABS_Y
+            | Instruction::STA_ABS_Y
+            | Instruction::AND_ABS_Y
+            | Instruction::EOR_ABS_Y
+            | Instruction::ORA_ABS_Y
+            | Instruction::ADC_ABS_Y
+            | Instruction::SBC_ABS_Y
+            | Instruction::CMP_ABS_Y
+            | Instruction::LAX_ABS_Y
+            | Instruction::SLO_ABS_Y
+            | Instruction::RLA_ABS_Y
+            | Instruction::SRE_ABS_Y
+            | Instruction::RRA_ABS_Y
+            | Instruction::DCP_ABS_Y
+            | Instruction::ISC_ABS_Y => (AddressingMode::AbsoluteReg, Register::Y),
+            Instruction::JMP_IN => (AddressingMode::Indirect, Register::None),
+            Instruction::LDA_IN_X
+            | Instruction::STA_IN_X
+            | Instruction::AND_IN_X
+            | Instruction::EOR_IN_X
+            | Instruction::ORA_IN_X
+            | Instruction::ADC_IN_X
+            | Instruction::SBC_IN_X
+            | Instruction::CMP_IN_X
+            | Instruction::LAX_IN_X
+            | Instruction::SAX_IN_X
+            | Instruction::SLO_IN_X
+            | Instruction::RLA_IN_X
+            | Instruction::SRE_IN_X
+            | Instruction::RRA_IN_X
+            | Instruction::DCP_IN_X
+            | Instruction::ISC_IN_X => (AddressingMode::IndirectX, Register::X),
+            Instruction::LDA_IN_Y
+            | Instruction::STA_IN_Y
+            | Instruction::AND_IN_Y
+            | Instruction::EOR_IN_Y
+            | Instruction::ORA_IN_Y
+            | Instruction::ADC_IN_Y
+            | Instruction::SBC_IN_Y
+            | Instruction::CMP_IN_Y
+            | Instruction::LAX_IN_Y
+            | Instruction::SLO_IN_Y
+            | Instruction::RLA_IN_Y
+            | Instruction::SRE_IN_Y
+            | Instruction::RRA_IN_Y
+            | Instruction::DCP_IN_Y
+            | Instruction::ISC_IN_Y => (AddressingMode::IndirectY, Register::Y),
+            Instruction::ORA_IN_ZP
+            | Instruction::AND_IN_ZP
+            | Instruction::EOR_IN_ZP
+            | Instruction::ADC_IN_ZP
+            | Instruction::STA_IN_ZP
+            | Instruction::LDA_IN_ZP
+            | Instruction::CMP_IN_ZP
+            | Instruction::SBC_IN_ZP => (AddressingMode::ZeroPageIndirect, Register::None),
+            Instruction::JMP_IN_X => (AddressingMode::AbsoluteIndirectX, Register::X),
+            Instruction::BCC
+            | Instruction::BCS
+            | Instruction::BEQ
+            | Instruction::BMI
+            | Instruction::BNE
+            | Instruction::BPL
+            | Instruction::BVC
+            | Instruction::BVS
+            | Instruction::BRA => (AddressingMode::Relative, Register::None),
+            Instruction::BBR0
+            | Instruction::BBR1
+            | Instruction::BBR2
+            | Instruction::BBR3
+            | Instruction::BBR4
+            | Instruction::BBR5
+            | Instruction::BBR6
+            | Instruction::BBR7
+            | Instruction::BBS0
+            | Instruction::BBS1
+            | Instruction::BBS2
+            | Instruction::BBS3
+            | Instruction::BBS4
+            | Instruction::BBS5
+            | Instruction::BBS6
+            | Instruction::BBS7 => (AddressingMode::ZeroPageRelative, Register::None),
+        };
+    }
+
+    // Mnemonic as written in assembly, variant names are MNEMONIC_MODE
+    pub fn mnemonic(self) -> String {
+        let name = format!("{:?}", self);
+        return name.split('_').next().unwrap_or_default().to_string();
+    }
+
+    // True for the NMOS opcodes missing from the MOS datasheet
+    pub fn is_undocumented(self) -> bool {
+        return matches!(
+            self,
+            Instruction::LAX_ZP
+                | Instruction::LAX_ZP_Y
+                | Instruction::LAX_ABS
+                | Instruction::LAX_ABS_Y
+                | Instruction::LAX_IN_X
+                | Instruction::LAX_IN_Y
+                | Instruction::SAX_ZP
+                | Instruction::SAX_ZP_Y
+                | Instruction::SAX_ABS
+                | Instruction::SAX_IN_X
+                | Instruction::SLO_ZP
+                | Instruction::SLO_ZP_X
+                | Instruction::SLO_ABS
+                | Instruction::SLO_ABS_X
+                | Instruction::SLO_ABS_Y
+                | Instruction::SLO_IN_X
+                | Instruction::SLO_IN_Y
+                | Instruction::RLA_ZP
+                | Instruction::RLA_ZP_X
+                | Instruction::RLA_ABS
+                | Instruction::RLA_ABS_X
+                | Instruction::RLA_ABS_Y
+                | Instruction::RLA_IN_X
+                | Instruction::RLA_IN_Y
+                | Instruction::SRE_ZP
+                | Instruction::SRE_ZP_X
+                | Instruction::SRE_ABS
+                | Instruction::SRE_ABS_X
+                | Instruction::SRE_ABS_Y
+                | Instruction::SRE_IN_X
+                | Instruction::SRE_IN_Y
+                | Instruction::RRA_ZP
+                | Instruction::RRA_ZP_X
+                | Instruction::RRA_ABS
+                | Instruction::RRA_ABS_X
+                | Instruction::RRA_ABS_Y
+                | Instruction::RRA_IN_X
+                | Instruction::RRA_IN_Y
+                | Instruction::DCP_ZP
+                | Instruction::DCP_ZP_X
+                | Instruction::DCP_ABS
+                | Instruction::DCP_ABS_X
+                | Instruction::DCP_ABS_Y
+                | Instruction::DCP_IN_X
+                | Instruction::DCP_IN_Y
+                | Instruction::ISC_ZP
+                | Instruction::ISC_ZP_X
+                | Instruction::ISC_ABS
+                | Instruction::ISC_ABS_X
+                | Instruction::ISC_ABS_Y
+                | Instruction::ISC_IN_X
+                | Instruction::ISC_IN_Y
+                | Instruction::ANC_IM
+                | Instruction::ALR_IM
+                | Instruction::ARR_IM
+                | Instruction::SBX_IM
+                | Instruction::NOP_IM
+                | Instruction::NOP_ZP
+                | Instruction::NOP_ZP_X
+                | Instruction::NOP_ABS
+                | Instruction::NOP_ABS_X
+                | Instruction::JAM
+        );
+    }
+
+    // Instruction length in bytes including the opcode
+    pub fn length(self) -> Word {
+        return match self.addressing_mode().0 {
+            AddressingMode::Implied | AddressingMode::Accumulator => 1,
+            AddressingMode::Absolute
+            | AddressingMode::AbsoluteReg
+            | AddressingMode::Indirect
+            | AddressingMode::AbsoluteIndirectX
+            | AddressingMode::ZeroPageRelative => 3,
+            _ => 2,
+        };
+    }
+}
+
+// Reasons for execution to stop that the caller has to handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuError {
+    // Opcode has no instruction on the selected variant. Program counter
+    // is left pointing at the opcode
+    IllegalOpcode {
+        address: Word,
+        opcode: Byte,
+    },
+    // JAM was executed, only a reset recovers the CPU
+    Jammed {
+        address: Word,
+    },
+    // Program counter reached a breakpoint, the instruction is not executed.
+    // Carries the interrupt serviced before the check, whose handler the
+    // breakpoint may be at
+    Breakpoint {
+        address: Word,
+        interrupt: Option<Interrupt>,
+    },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            CpuError::IllegalOpcode { address, opcode } => {
+                write!(f, "illegal opcode ${:02X} at ${:04X}", opcode, address)
+            }
+            CpuError::Jammed { address } => write!(f, "CPU jammed at ${:04X}", address),
+            CpuError::Breakpoint { address, .. } => write!(f, "breakpoint at ${:04X}", address),
+        };
+    }
+}
+
+impl std::error::Error for CpuError {}
+
+// Information about a single executed instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepInfo {
+    pub pc: Word,
+    pub opcode: Byte,
+    pub instruction: Instruction,
+    // Interrupt serviced before the instruction was fetched
+    pub interrupt: Option<Interrupt>,
+    pub cycles: u64,
+    // RDY was low or the CPU was halted, nothing was executed and the
+    // instruction is reported as NOP
+    pub stalled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CPU {
+    cycles: u64,
+    pc: Word,
+    sp: Byte,
+    a: Byte,
+    x: Byte,
+    y: Byte,
+    status: StatusFlags,
+    irq_line: bool,
+    nmi_pending: bool,
+    decimal_enabled: bool,
+    variant: CpuVariant,
+    // Set by 65C02 WAI until an interrupt line is asserted
+    waiting: bool,
+    // Set by 65C02 STP until the next reset
+    stopped: bool,
+    // Set by NMOS JAM until the next reset
+    jammed: bool,
+    breakpoints: HashSet<Word>,
+    // Address of the breakpoint last reported, so the next step executes
+    // the instruction there instead of reporting it again
+    breakpoint_hit: Option<Word>,
+    // RDY and SO are active low, true while the line is high
+    rdy_line: bool,
+    so_line: bool,
+    halted: bool,
+    rdy_source: Option<RdySource>,
+    tracer: Option<Tracer>,
+    // Performs the dummy reads and writes of every instruction so the bus
+    // sees one access per cycle
+    cycle_accurate: bool,
+    bus_observer: Option<BusObserver>,
+}
+
+impl Default for CPU {
+    fn default() -> Self {
+        CPU {
+            cycles: 0,
+            pc: 0,
+            sp: 0,
+            a: 0,
+            x: 0,
+            y: 0,
+            status: StatusFlags::empty(),
+            irq_line: false,
+            nmi_pending: false,
+            decimal_enabled: true,
+            variant: CpuVariant::Nmos6502,
+            waiting: false,
+            stopped: false,
+            jammed: false,
+            breakpoints: HashSet::new(),
+            breakpoint_hit: None,
+            rdy_line: true,
+            so_line: true,
+            halted: false,
+            rdy_source: None,
+            tracer: None,
+            cycle_accurate: false,
+            bus_observer: None,
+        }
+    }
+}
+
+impl CPU {
+    pub fn new(variant: CpuVariant) -> Self {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        cpu.set_variant(variant);
+
+        return cpu;
+    }
+
+    pub fn get_variant(&self) -> CpuVariant {
+        return self.variant;
+    }
+
+    // Switches the emulated chip. Decimal mode support follows the variant
+    // and can be overridden afterwards with set_decimal_enabled
+    pub fn set_variant(&mut self, variant: CpuVariant) {
+        self.variant = variant;
+        self.decimal_enabled = variant.has_decimal_mode();
+    }
+
+    // Performs the reset sequence: loads the program counter from the
+    // reset vector, sets the stack pointer to $FD and disables interrupts,
+    // the 65C02 also clears decimal mode. Registers A, X and Y are left
+    // untouched like on real hardware
+    pub fn reset<B: Bus>(&mut self, memory: &mut B) {
+        // The observer sees the address lines the chip actually has
+        let observed = &mut ObservedBus::new(memory, self.bus_observer.clone());
+        let memory = &mut MaskedBus::new(observed, self.variant.address_mask());
+
+        // Same sequence as an interrupt with the stack writes turned into
+        // reads
+        self.dummy_read(memory, self.pc);
+        self.dummy_read(memory, self.pc);
+        for i in 0..3 {
+            self.dummy_read(memory, 0x0100 | self.sp.wrapping_sub(i) as u16);
+        }
+
+        self.pc = memory.read(0xfffc);
+        self.sp = 0xfd;
+        self.status |= StatusFlags::INTERRUPT_DISABLE | StatusFlags::UNUSED;
+        self.clear_cmos_decimal_mode();
+        self.nmi_pending = false;
+        self.waiting = false;
+        self.stopped = false;
+        self.jammed = false;
+        self.cycles += 7;
+    }
+
+    // Clears all registers, optionally fills ram with pseudo random values
+    // generated from `ram_seed` and performs the reset sequence
+    pub fn power_on<B: Bus>(&mut self, memory: &mut B, ram_seed: Option<u64>) {
+        self.cycles = 0;
+        self.pc = 0;
+        self.sp = 0;
+        self.a = 0;
+        self.x = 0;
+        self.y = 0;
+        self.status = StatusFlags::empty();
+        self.irq_line = false;
+        self.nmi_pending = false;
+
+        if let Some(seed) = ram_seed {
+            memory.fill_ram(&mut RamPattern::new(seed));
+        }
+
+        self.reset(memory);
+    }
+
+    // Status register as a byte, bit 5 always reads as 1
+    pub fn get_status(&self) -> Byte {
+        return (self.status | StatusFlags::UNUSED).into();
+    }
+
+    pub fn set_status(&mut self, status: Byte) {
+        self.status = StatusFlags::from(status) | StatusFlags::UNUSED;
+    }
+
+    pub fn get_status_flags(&self) -> StatusFlags {
+        return self.status | StatusFlags::UNUSED;
+    }
+
+    pub fn set_status_flags(&mut self, status: StatusFlags) {
+        self.status = status | StatusFlags::UNUSED;
+    }
+
+    pub fn get_cycles(&self) -> u64 {
+        return self.cycles;
+    }
+
+    pub fn get_pc(&self) -> Word {
+        return self.pc;
+    }
+
+    // Jumps to pc without going through the reset vector, for images
+    // that have to be entered at a fixed address
+    pub fn set_pc(&mut self, pc: Word) {
+        self.pc = pc;
+    }
+
+    pub fn get_sp(&self) -> Byte {
+        return self.sp;
+    }
+
+    pub fn set_sp(&mut self, sp: Byte) {
+        self.sp = sp;
+    }
+
+    pub fn get_a(&self) -> Byte {
+        return self.a;
+    }
+
+    pub fn set_a(&mut self, a: Byte) {
+        self.a = a;
+    }
+
+    pub fn get_x(&self) -> Byte {
+        return self.x;
+    }
+
+    pub fn set_x(&mut self, x: Byte) {
+        self.x = x;
+    }
+
+    pub fn get_y(&self) -> Byte {
+        return self.y;
+    }
+
+    pub fn set_y(&mut self, y: Byte) {
+        self.y = y;
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        return self.waiting;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        return self.stopped;
+    }
+
+    pub fn is_jammed(&self) -> bool {
+        return self.jammed;
+    }
+
+    pub fn get_carry(&self) -> bool {
+        return self.status.contains(StatusFlags::CARRY);
+    }
+
+    pub fn set_carry(&mut self, value: bool) {
+        self.status.set(StatusFlags::CARRY, value);
+    }
+
+    pub fn get_zero(&self) -> bool {
+        return self.status.contains(StatusFlags::ZERO);
+    }
+
+    pub fn set_zero(&mut self, value: bool) {
+        self.status.set(StatusFlags::ZERO, value);
+    }
+
+    pub fn get_interrupt_disable(&self) -> bool {
+        return self.status.contains(StatusFlags::INTERRUPT_DISABLE);
+    }
+
+    pub fn set_interrupt_disable(&mut self, value: bool) {
+        self.status.set(StatusFlags::INTERRUPT_DISABLE, value);
+    }
+
+    pub fn get_decimal_mode(&self) -> bool {
+        return self.status.contains(StatusFlags::DECIMAL_MODE);
+    }
+
+    pub fn set_decimal_mode(&mut self, value: bool) {
+        self.status.set(StatusFlags::DECIMAL_MODE, value);
+    }
+
+    // Whether ADC and SBC honour the decimal mode flag. Variants such as
+    // the Ricoh 2A03 keep the flag but have no BCD circuitry
+    pub fn get_decimal_enabled(&self) -> bool {
+        return self.decimal_enabled;
+    }
+
+    pub fn set_decimal_enabled(&mut self, value: bool) {
+        self.decimal_enabled = value;
+    }
+
+    pub fn get_break_command(&self) -> bool {
+        return self.status.contains(StatusFlags::BREAK);
+    }
+
+    pub fn set_break_command(&mut self, value: bool) {
+        self.status.set(StatusFlags::BREAK, value);
+    }
+
+    pub fn get_overflow(&self) -> bool {
+        return self.status.contains(StatusFlags::OVERFLOW);
+    }
+
+    pub fn set_overflow(&mut self, value: bool) {
+        self.status.set(StatusFlags::OVERFLOW, value);
+    }
+
+    pub fn get_negative(&self) -> bool {
+        return self.status.contains(StatusFlags::NEGATIVE);
+    }
+
+    pub fn set_negative(&mut self, value: bool) {
+        self.status.set(StatusFlags::NEGATIVE, value);
+    }
+
+    // Sets flags based on number passed
+    fn test_number(&mut self, num: u8) {
+        self.set_zero(num == 0);
+        self.set_negative((num & 0b1000_0000) != 0);
+    }
+
+    // Executes an already fetched instruction, pc has to point right after
+    // its opcode. Reported opcode is the canonical encoding of the instruction
+    pub fn execute<B: Bus>(
+        &mut self,
+        memory: &mut B,
+        i: Instruction,
+    ) -> Result<StepInfo, CpuError> {
+        let pc = self.pc.wrapping_sub(1);
+        let cycles = self.cycles;
+
+        // Single byte instructions read the byte after the opcode and
+        // ignore it, BRK skips it afterwards. The 65C02 fetches the next
+        // opcode right away after its one cycle NOPs
+        if i.length() == 1 && !matches!(i, Instruction::INVALID | Instruction::NOP_FAST) {
+            self.dummy_read(memory, self.pc);
+        }
+
+        match i {
+            // LDA
+            Instruction::LDA_IM => self.lda_immediate(memory),
+            Instruction::LDA_ZP => self.lda_zero_page(memory),
+            Instruction::LDA_ZP_X => self.lda_zero_page_x(memory),
+            Instruction::LDA_ABS => self.lda_absolute(memory),
+            Instruction::LDA_ABS_X => self.lda_absolute_x(memory),
+            Instruction::LDA_ABS_Y => self.lda_absolute_y(memory),
+            Instruction::LDA_IN_X => self.lda_indirect_x(memory),
+            Instruction::LDA_IN_Y => self.lda_indirect_y(memory),
+            // LDX
+            Instruction::LDX_IM => self.ldx_immediate(memory),
+            Instruction::LDX_ZP => self.ldx_zero_page(memory),
+            Instruction::LDX_ZP_Y => self.ldx_zero_page_y(memory),
+            Instruction::LDX_ABS => self.ldx_absolute(memory),
+            Instruction::LDX_ABS_Y => self.ldx_absolute_y(memory),
+            // LDY
+            Instruction::LDY_IM => self.ldy_immediate(memory),
+            Instruction::LDY_ZP => self.ldy_zero_page(memory),
+            Instruction::LDY_ZP_X => self.ldy_zero_page_x(memory),
+            Instruction::LDY_ABS => self.ldy_absolute(memory),
+            Instruction::LDY_ABS_X => self.ldy_absolute_x(memory),
+
+            // STA
+            Instruction::STA_ZP => self.sta_zero_page(memory),
+            Instruction::STA_ZP_X => self.sta_zero_page_x(memory),
+            Instruction::STA_ABS => self.sta_absolute(memory),
+            Instruction::STA_ABS_X => self.sta_absolute_x(memory),
+            Instruction::STA_ABS_Y => self.sta_absolute_y(memory),
+            Instruction::STA_IN_X => self.sta_indirect_x(memory),
+            Instruction::STA_IN_Y => self.sta_indirect_y(memory),
+            // STX
+            Instruction::STX_ZP => self.stx_zero_page(memory),
+            Instruction::STX_ZP_Y => self.stx_zero_page_y(memory),
+            Instruction::STX_ABS => self.stx_absolute(memory),
+            // STY
+            Instruction::STY_ZP => self.sty_zero_page(memory),
+            Instruction::STY_ZP_X => self.sty_zero_page_x(memory),
+            Instruction::STY_ABS => self.sty_absolute(memory),
+            // Transfer
+            Instruction::TAX => self.transfer_a_x(),
+            Instruction::TAY => self.transfer_a_y(),
+            Instruction::TXA => self.transfer_x_a(),
+            Instruction::TYA => self.transfer_y_a(),
+            Instruction::TSX => self.transfer_s_x(),
+            Instruction::TXS => self.transfer_x_s(),
+            // Stack
+            Instruction::PHA => self.push_accumulator(memory),
+            Instruction::PHP => self.push_processor_status(memory),
+            Instruction::PLA => self.pull_accumulator(memory),
+            Instruction::PLP => self.pull_processor_status(memory),
+            // Logical
+            // And
+            Instruction::AND_IM => self.and_immediate(memory),
+            Instruction::AND_ZP => self.and_zero_page(memory),
+            Instruction::AND_ZP_X => self.and_zero_page_x(memory),
+            Instruction::AND_ABS => self.and_absolute(memory),
+            Instruction::AND_ABS_X => self.and_absolute_x(memory),
+            Instruction::AND_ABS_Y => self.and_absolute_y(memory),
+            Instruction::AND_IN_X => self.and_indirect_x(memory),
+            Instruction::AND_IN_Y => self.and_indirect_y(memory),
+            // Exclusive Or
+            Instruction::EOR_IM => self.eor_immediate(memory),
+            Instruction::EOR_ZP => self.eor_zero_page(memory),
+            Instruction::EOR_ZP_X => self.eor_zero_page_x(memory),
+            Instruction::EOR_ABS => self.eor_absolute(memory),
+            Instruction::EOR_ABS_X => self.eor_absolute_x(memory),
+            Instruction::EOR_ABS_Y => self.eor_absolute_y(memory),
+            Instruction::EOR_IN_X => self.eor_indirect_x(memory),
+            Instruction::EOR_IN_Y => self.eor_indirect_y(memory),
+            // Or
+            Instruction::ORA_IM => self.ora_immediate(memory),
+            Instruction::ORA_ZP => self.ora_zero_page(memory),
+            Instruction::ORA_ZP_X => self.ora_zero_page_x(memory),
+            Instruction::ORA_ABS => self.ora_absolute(memory),
+            Instruction::ORA_ABS_X => self.ora_absolute_x(memory),
+            Instruction::ORA_ABS_Y => self.ora_absolute_y(memory),
+            Instruction::ORA_IN_X => self.ora_indirect_x(memory),
+            Instruction::ORA_IN_Y => self.ora_indirect_y(memory),
+            // Bit
+            Instruction::BIT_ZP => self.bit_zero_page(memory),
+            Instruction::BIT_ABS => self.bit_absolute(memory),
+
+            // Arithmetic
+            // ADC
+            Instruction::ADC_IM => self.adc_immediate(memory),
+            Instruction::ADC_ZP => self.adc_zero_page(memory),
+            Instruction::ADC_ZP_X => self.adc_zero_page_x(memory),
+            Instruction::ADC_ABS => self.adc_absolute(memory),
+            Instruction::ADC_ABS_X => self.adc_absolute_x(memory),
+            Instruction::ADC_ABS_Y => self.adc_absolute_y(memory),
+            Instruction::ADC_IN_X => self.adc_indirect_x(memory),
+            Instruction::ADC_IN_Y => self.adc_indirect_y(memory),
+            // SBC
+            Instruction::SBC_IM => self.sbc_immediate(memory),
+            Instruction::SBC_ZP => self.sbc_zero_page(memory),
+            Instruction::SBC_ZP_X => self.sbc_zero_page_x(memory),
+            Instruction::SBC_ABS => self.sbc_absolute(memory),
+            Instruction::SBC_ABS_X => self.sbc_absolute_x(memory),
+            Instruction::SBC_ABS_Y => self.sbc_absolute_y(memory),
+            Instruction::SBC_IN_X => self.sbc_indirect_x(memory),
+            Instruction::SBC_IN_Y => self.sbc_indirect_y(memory),
+            // CMP
+            Instruction::CMP_IM => self.cmp_immediate(memory),
+            Instruction::CMP_ZP => self.cmp_zero_page(memory),
+            Instruction::CMP_ZP_X => self.cmp_zero_page_x(memory),
+            Instruction::CMP_ABS => self.cmp_absolute(memory),
+            Instruction::CMP_ABS_X => self.cmp_absolute_x(memory),
+            Instruction::CMP_ABS_Y => self.cmp_absolute_y(memory),
+            Instruction::CMP_IN_X => self.cmp_indirect_x(memory),
+            Instruction::CMP_IN_Y => self.cmp_indirect_y(memory),
+            // CPX
+            Instruction::CPX_IM => self.cpx_immediate(memory),
+            Instruction::CPX_ZP => self.cpx_zero_page(memory),
+            Instruction::CPX_ABS => self.cpx_absolute(memory),
+            // CPY
+            Instruction::CPY_IM => self.cpy_immediate(memory),
+            Instruction::CPY_ZP => self.cpy_zero_page(memory),
+            Instruction::CPY_ABS => self.cpy_absolute(memory),
+            // Increments
+            // INC
+            Instruction::INC_ZP => self.inc_zero_page(memory),
+            Instruction::INC_ZP_X => self.inc_zero_page_x(memory),
+            Instruction::INC_ABS => self.inc_absolute(memory),
+            Instruction::INC_ABS_X => self.inc_absolute_x(memory),
+            // INX
+            Instruction::INX => self.inx(memory),
+            // INY
+            Instruction::INY => self.iny(memory),
+            // Decrements
+            // DEC
+            Instruction::DEC_ZP => self.dec_zero_page(memory),
+            Instruction::DEC_ZP_X => self.dec_zero_page_x(memory),
+            Instruction::DEC_ABS => self.dec_absolute(memory),
+            Instruction::DEC_ABS_X => self.dec_absolute_x(memory),
+            // DEX
+            Instruction::DEX => self.dex(memory),
+            // DEY
+            Instruction::DEY => self.dey(memory),
+            // Shifts
+            // ASL
+            Instruction::ASL_A => self.asl_accumulator(memory),
+            Instruction::ASL_ZP => self.asl_zero_page(memory),
+            Instruction::ASL_ZP_X => self.asl_zero_page_x(memory),
+            Instruction::ASL_ABS => self.asl_absolute(memory),
+            Instruction::ASL_ABS_X => self.asl_absolute_x(memory),
+            // LSR
+            Instruction::LSR_A => self.lsr_accumulator(memory),
+            Instruction::LSR_ZP => self.lsr_zero_page(memory),
+            Instruction::LSR_ZP_X => self.lsr_zero_page_x(memory),
+            Instruction::LSR_ABS => self.lsr_absolute(memory),
+            Instruction::LSR_ABS_X => self.lsr_absolute_x(memory),
+            // ROL
+            Instruction::ROL_A => self.rol_accumulator(memory),
+            Instruction::ROL_ZP => self.rol_zero_page(memory),
+            Instruction::ROL_ZP_X => self.rol_zero_page_x(memory),
+            Instruction::ROL_ABS => self.rol_absolute(memory),
+            Instruction::ROL_ABS_X => self.rol_absolute_x(memory),
+            // ROR
+            Instruction::ROR_A => self.ror_accumulator(memory),
+            Instruction::ROR_ZP => self.ror_zero_page(memory),
+            Instruction::ROR_ZP_X => self.ror_zero_page_x(memory),
+            Instruction::ROR_ABS => self.ror_absolute(memory),
+            Instruction::ROR_ABS_X => self.ror_absolute_x(memory),
+            // Jumps and Calls
+            Instruction::JMP_ABS => self.jmp_absolute(memory),
+            Instruction::JMP_IN => self.jmp_indirect(memory),
+            Instruction::JSR_ABS => self.jsr_absolute(memory),
+            Instruction::RTS_IM => self.rts_implied(memory),
+            // Branches
+            Instruction::BCC => self.bcc(memory),
+            Instruction::BCS => self.bcs(memory),
+            Instruction::BEQ => self.beq(memory),
+            Instruction::BMI => self.bmi(memory),
+            Instruction::BNE => self.bne(memory),
+            Instruction::BPL => self.bpl(memory),
+            Instruction::BVC => self.bvc(memory),
+            Instruction::BVS => self.bvs(memory),
+            // Status Flag Changes
+            Instruction::CLC => self.clc(),
+            Instruction::CLD => self.cld(),
+            Instruction::CLI => self.cli(),
+            Instruction::CLV => self.clv(),
+            Instruction::SEC => self.sec(),
+            Instruction::SED => self.sed(),
+            Instruction::SEI => self.sei(),
+            // System functions
+            Instruction::BRK => self.brk(memory),
+            Instruction::NOP => self.nop(),
+            Instruction::RTI => self.rti(memory),
+
+            // 65C02
+            Instruction::BRA => self.bra(memory),
+            Instruction::PHX => self.push_x(memory),
+            Instruction::PHY => self.push_y(memory),
+            Instruction::PLX => self.pull_x(memory),
+            Instruction::PLY => self.pull_y(memory),
+            Instruction::STZ_ZP => self.stz_zero_page(memory),
+            Instruction::STZ_ZP_X => self.stz_zero_page_x(memory),
+            Instruction::STZ_ABS => self.stz_absolute(memory),
+            Instruction::STZ_ABS_X => self.stz_absolute_x(memory),
+            Instruction::TRB_ZP => self.trb_zero_page(memory),
+            Instruction::TRB_ABS => self.trb_absolute(memory),
+            Instruction::TSB_ZP => self.tsb_zero_page(memory),
+            Instruction::TSB_ABS => self.tsb_absolute(memory),
+            Instruction::INC_A => self.inc_accumulator(memory),
+            Instruction::DEC_A => self.dec_accumulator(memory),
+            Instruction::BIT_IM => self.bit_immediate(memory),
+            Instruction::BIT_ZP_X => self.bit_zero_page_x(memory),
+            Instruction::BIT_ABS_X => self.bit_absolute_x(memory),
+            Instruction::ORA_IN_ZP => self.ora_zero_page_indirect(memory),
+            Instruction::AND_IN_ZP => self.and_zero_page_indirect(memory),
+            Instruction::EOR_IN_ZP => self.eor_zero_page_indirect(memory),
+            Instruction::ADC_IN_ZP => self.adc_zero_page_indirect(memory),
+            Instruction::STA_IN_ZP => self.sta_zero_page_indirect(memory),
+            Instruction::LDA_IN_ZP => self.lda_zero_page_indirect(memory),
+            Instruction::CMP_IN_ZP => self.cmp_zero_page_indirect(memory),
+            Instruction::SBC_IN_ZP => self.sbc_zero_page_indirect(memory),
+            Instruction::JMP_IN_X => self.jmp_absolute_x_indirect(memory),
+            Instruction::RMB0 => self.rmb0(memory),
+            Instruction::RMB1 => self.rmb1(memory),
+            Instruction::RMB2 => self.rmb2(memory),
+            Instruction::RMB3 => self.rmb3(memory),
+            Instruction::RMB4 => self.rmb4(memory),
+            Instruction::RMB5 => self.rmb5(memory),
+            Instruction::RMB6 => self.rmb6(memory),
+            Instruction::RMB7 => self.rmb7(memory),
+            Instruction::SMB0 => self.smb0(memory),
+            Instruction::SMB1 => self.smb1(memory),
+            Instruction::SMB2 => self.smb2(memory),
+            Instruction::SMB3 => self.smb3(memory),
+            Instruction::SMB4 => self.smb4(memory),
+            Instruction::SMB5 => self.smb5(memory),
+            Instruction::SMB6 => self.smb6(memory),
+            Instruction::SMB7 => self.smb7(memory),
+            Instruction::BBR0 => self.bbr0(memory),
+            Instruction::BBR1 => self.bbr1(memory),
+            Instruction::BBR2 => self.bbr2(memory),
+            Instruction::BBR3 => self.bbr3(memory),
+            Instruction::BBR4 => self.bbr4(memory),
+            Instruction::BBR5 => self.bbr5(memory),
+            Instruction::BBR6 => self.bbr6(memory),
+            Instruction::BBR7 => self.bbr7(memory),
+            Instruction::BBS0 => self.bbs0(memory),
+            Instruction::BBS1 => self.bbs1(memory),
+            Instruction::BBS2 => self.bbs2(memory),
+            Instruction::BBS3 => self.bbs3(memory),
+            Instruction::BBS4 => self.bbs4(memory),
+            Instruction::BBS5 => self.bbs5(memory),
+            Instruction::BBS6 => self.bbs6(memory),
+            Instruction::BBS7 => self.bbs7(memory),
+            Instruction::WAI => self.wai(memory),
+            Instruction::STP => self.stp(memory),
+
+            // Undocumented
+            Instruction::LAX_ZP => self.lax_zero_page(memory),
+            Instruction::LAX_ZP_Y => self.lax_zero_page_y(memory),
+            Instruction::LAX_ABS => self.lax_absolute(memory),
+            Instruction::LAX_ABS_Y => self.lax_absolute_y(memory),
+            Instruction::LAX_IN_X => self.lax_indirect_x(memory),
+            Instruction::LAX_IN_Y => self.lax_indirect_y(memory),
+            Instruction::SAX_ZP => self.sax_zero_page(memory),
+            Instruction::SAX_ZP_Y => self.sax_zero_page_y(memory),
+            Instruction::SAX_ABS => self.sax_absolute(memory),
+            Instruction::SAX_IN_X => self.sax_indirect_x(memory),
+            Instruction::SLO_ZP => self.slo_zero_page(memory),
+            Instruction::SLO_ZP_X => self.slo_zero_page_x(memory),
+            Instruction::SLO_ABS => self.slo_absolute(memory),
+            Instruction::SLO_ABS_X => self.slo_absolute_x(memory),
+            Instruction::SLO_ABS_Y => self.slo_absolute_y(memory),
+            Instruction::SLO_IN_X => self.slo_indirect_x(memory),
+            Instruction::SLO_IN_Y => self.slo_indirect_y(memory),
+            Instruction::RLA_ZP => self.rla_zero_page(memory),
+            Instruction::RLA_ZP_X => self.rla_zero_page_x(memory),
+            Instruction::RLA_ABS => self.rla_absolute(memory),
+            Instruction::RLA_ABS_X => self.rla_absolute_x(memory),
+            Instruction::RLA_ABS_Y => self.rla_absolute_y(memory),
+            Instruction::RLA_IN_X => self.rla_indirect_x(memory),
+            Instruction::RLA_IN_Y => self.rla_indirect_y(memory),
+            Instruction::SRE_ZP => self.sre_zero_page(memory),
+            Instruction::SRE_ZP_X => self.sre_zero_page_x(memory),
+            Instruction::SRE_ABS => self.sre_absolute(memory),
+            Instruction::SRE_ABS_X => self.sre_absolute_x(memory),
+            Instruction::SRE_ABS_Y => self.sre_absolute_y(memory),
+            Instruction::SRE_IN_X => self.sre_indirect_x(memory),
+            Instruction::SRE_IN_Y => self.sre_indirect_y(memory),
+            Instruction::RRA_ZP => self.rra_zero_page(memory),
+            Instruction::RRA_ZP_X => self.rra_zero_page_x(memory),
+            Instruction::RRA_ABS => self.rra_absolute(memory),
+            Instruction::RRA_ABS_X => self.rra_absolute_x(memory),
+            Instruction::RRA_ABS_Y => self.rra_absolute_y(memory),
+            Instruction::RRA_IN_X => self.rra_indirect_x(memory),
+            Instruction::RRA_IN_Y => self.rra_indirect_y(memory),
+            Instruction::DCP_ZP => self.dcp_zero_page(memory),
+            Instruction::DCP_ZP_X => self.dcp_zero_page_x(memory),
+            Instruction::DCP_ABS => self.dcp_absolute(memory),
+            Instruction::DCP_ABS_X => self.dcp_absolute_x(memory),
+            Instruction::DCP_ABS_Y => self.dcp_absolute_y(memory),
+            Instruction::DCP_IN_X => self.dcp_indirect_x(memory),
+            Instruction::DCP_IN_Y => self.dcp_indirect_y(memory),
+            Instruction::ISC_ZP => self.isc_zero_page(memory),
+            Instruction::ISC_ZP_X => self.isc_zero_page_x(memory),
+            Instruction::ISC_ABS => self.isc_absolute(memory),
+            Instruction::ISC_ABS_X => self.isc_absolute_x(memory),
+            Instruction::ISC_ABS_Y => self.isc_absolute_y(memory),
+            Instruction::ISC_IN_X => self.isc_indirect_x(memory),
+            Instruction::ISC_IN_Y => self.isc_indirect_y(memory),
+            Instruction::ANC_IM => self.anc_immediate(memory),
+            Instruction::ALR_IM => self.alr_immediate(memory),
+            Instruction::ARR_IM => self.arr_immediate(memory),
+            Instruction::SBX_IM => self.sbx_immediate(memory),
+            Instruction::NOP_IM => self.nop_immediate(memory),
+            Instruction::NOP_ZP => self.nop_zero_page(memory),
+            Instruction::NOP_ZP_X => self.nop_zero_page_x(memory),
+            Instruction::NOP_ABS => self.nop_absolute(memory),
+            Instruction::NOP_ABS_X => self.nop_absolute_x(memory),
+            Instruction::NOP_FAST => self.nop_fast(),
+            Instruction::NOP_SLOW => self.nop_slow(memory),
+            Instruction::JAM => {
+                self.jam();
+                return Err(CpuError::Jammed { address: pc });
+            }
+
+            Instruction::INVALID => {
+                let opcode: u8 = memory.read(pc);
+                self.pc = pc;
+                return Err(CpuError::IllegalOpcode {
+                    address: pc,
+                    opcode,
+                });
+            }
+        }
+
+        return Ok(StepInfo {
+            pc,
+            opcode: u8::from(i),
+            instruction: i,
+            interrupt: None,
+            cycles: self.cycles - cycles,
+            stalled: false,
+        });
+    }
+
+    pub fn fetch_instruction<B: Bus>(&mut self, memory: &mut B) -> Instruction {
+        let opcode = self.fetch_opcode(memory);
+
+        return Instruction::decode(opcode, self.variant);
+    }
+
+    fn fetch_opcode<B: Bus>(&mut self, memory: &mut B) -> Byte {
+        let opcode = memory.read_byte(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+
+        return opcode;
+    }
+
+    // IRQ is level triggered: it is serviced before every instruction
+    // while the line is asserted and interrupts are not disabled
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    pub fn get_irq_line(&self) -> bool {
+        return self.irq_line;
+    }
+
+    // NMI is edge triggered: every call is serviced exactly once
+    pub fn trigger_nmi(&mut self) {
+        self.nmi_pending = true;
+    }
+
+    // Pulling RDY low stalls the CPU before its next read cycle, this is
+    // how DMA controllers take over the bus. The level set here holds for
+    // a whole step, so the stall starts at the next opcode fetch and every
+    // step burns one cycle repeating that read until RDY is high again.
+    // Interrupts stay pending. Use set_rdy_source to stall in the middle
+    // of an instruction
+    pub fn set_rdy_line(&mut self, high: bool) {
+        self.rdy_line = high;
+    }
+
+    pub fn get_rdy_line(&self) -> bool {
+        return self.rdy_line;
+    }
+
+    // Samples RDY once per cycle while an instruction executes. Every read
+    // cycle, and on the 65C02 every write cycle, is repeated for as long as
+    // the source reports the line low. NMOS chips finish writes and stall
+    // at the next read. Only used in cycle accurate mode, since the other
+    // mode has no individual cycles. Pass None to stop sampling
+    pub fn set_rdy_source(&mut self, source: Option<RdySource>) {
+        self.rdy_source = source;
+    }
+
+    // A falling edge on SO sets the overflow flag, the 1541 disk drive
+    // uses it to signal that a byte was read
+    pub fn set_so_line(&mut self, high: bool) {
+        if self.so_line && !high {
+            self.set_overflow(true);
+        }
+        self.so_line = high;
+    }
+
+    pub fn get_so_line(&self) -> bool {
+        return self.so_line;
+    }
+
+    // Stalls the CPU like a low RDY line until resume is called. Halting
+    // does not touch the RDY line, so a host can pause the CPU while a
+    // DMA controller drives RDY
+    pub fn halt(&mut self) {
+        self.halted = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.halted = false;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        return self.halted;
+    }
+
+    // Makes step return CpuError::Breakpoint before executing the
+    // instruction at address. Stepping again executes the instruction
+    pub fn add_breakpoint(&mut self, address: Word) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: Word) {
+        self.breakpoints.remove(&address);
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Reports every instruction step executes, see format_line for the
+    // layout. Pass None to stop tracing
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    // In cycle accurate mode instructions perform every bus access of the
+    // real chip, including dummy reads on page crossings and the extra
+    // write of read-modify-write instructions, so the number of accesses
+    // matches the number of cycles. Off by default because the additional
+    // accesses slow down emulation
+    pub fn set_cycle_accurate(&mut self, enabled: bool) {
+        self.cycle_accurate = enabled;
+    }
+
+    pub fn get_cycle_accurate(&self) -> bool {
+        return self.cycle_accurate;
+    }
+
+    // Reports every bus access made by step and reset, in order. Combined
+    // with cycle accurate mode it is called once per cycle. Pass None to
+    // stop observing
+    pub fn set_bus_observer(&mut self, observer: Option<BusObserver>) {
+        self.bus_observer = observer;
+    }
+
+    fn poll_interrupts<B: Bus>(&mut self, memory: &mut B) -> Option<Interrupt> {
+        if self.nmi_pending {
+            self.nmi_pending = false;
+            self.service_interrupt(memory, Interrupt::Nmi);
+            return Some(Interrupt::Nmi);
+        }
+
+        if self.irq_line && !self.get_interrupt_disable() {
+            self.service_interrupt(memory, Interrupt::Irq);
+            return Some(Interrupt::Irq);
+        }
+
+        return None;
+    }
+
+    fn service_interrupt<B: Bus>(&mut self, memory: &mut B, interrupt: Interrupt) {
+        let vector = match interrupt {
+            Interrupt::Irq => 0xfffe,
+            Interrupt::Nmi => 0xfffa,
+        };
+        // Hardware interrupts push status with the break flag cleared
+        let status = self.status.to_pushed(false);
+
+        // The opcode fetch is replaced by two reads that are thrown away
+        self.dummy_read(memory, self.pc);
+        self.dummy_read(memory, self.pc);
+        self.push_interrupt_frame(memory, status);
+        self.set_interrupt_disable(true);
+        self.clear_cmos_decimal_mode();
+
+        self.pc = memory.read(vector);
+        self.cycles += 7;
+    }
+
+    // Fetches and executes a single instruction, servicing pending
+    // interrupts first
+    pub fn step<B: Bus>(&mut self, memory: &mut B) -> Result<StepInfo, CpuError> {
+        let cycles = self.cycles;
+
+        if self.jammed {
+            return Err(CpuError::Jammed {
+                address: self.pc.wrapping_sub(1),
+            });
+        }
+        if !self.rdy_line || self.halted {
+            return Ok(self.stall(memory));
+        }
+        if self.stopped {
+            return Ok(self.idle(Instruction::STP));
+        }
+        if self.waiting {
+            if !self.nmi_pending && !self.irq_line {
+                return Ok(self.idle(Instruction::WAI));
+            }
+            // With interrupts disabled WAI resumes without servicing the IRQ
+            self.waiting = false;
+        }
+
+        // Chips with fewer address lines see the address space mirrored,
+        // the observer sees the masked addresses. Stalled cycles only exist
+        // in cycle accurate mode
+        let mask = self.variant.address_mask();
+        let rdy = self.rdy_source.clone().filter(|_| self.cycle_accurate);
+        let stall_writes = self.variant == CpuVariant::Cmos65C02;
+        let observed = &mut ObservedBus::new(&mut *memory, self.bus_observer.clone());
+        let masked = &mut MaskedBus::new(observed, mask);
+        let bus = &mut RdyBus::new(masked, rdy.clone(), stall_writes);
+        let interrupt = self.poll_interrupts(bus);
+        self.cycles += bus.get_stalls();
+
+        // Checked after the interrupt so breakpoints in handlers are caught
+        let pc = self.pc;
+        if self.breakpoints.contains(&pc) && self.breakpoint_hit != Some(pc) {
+            self.breakpoint_hit = Some(pc);
+            return Err(CpuError::Breakpoint {
+                address: pc,
+                interrupt,
+            });
+        }
+        self.breakpoint_hit = None;
+
+        // The tracer only peeks, its reads are not part of the program
+        if let Some(tracer) = &self.tracer {
+            tracer.trace(&format_line(self, &mut MaskedBus::new(&mut *memory, mask)));
+        }
+
+        let observed = &mut ObservedBus::new(memory, self.bus_observer.clone());
+        let masked = &mut MaskedBus::new(observed, mask);
+        let memory = &mut RdyBus::new(masked, rdy, stall_writes);
+        let opcode = self.fetch_opcode(memory);
+        let instruction = Instruction::decode(opcode, self.variant);
+
+        let result = self.execute(memory, instruction);
+        self.cycles += memory.get_stalls();
+        result?;
+
+        return Ok(StepInfo {
+            pc,
+            opcode,
+            instruction,
+            interrupt,
+            cycles: self.cycles - cycles,
+            stalled: false,
+        });
+    }
+
+    // Burns a single cycle while the CPU is stopped or waiting for an
+    // interrupt. Reported pc is the one after the instruction
+    fn idle(&mut self, instruction: Instruction) -> StepInfo {
+        self.cycles += 1;
+
+        return StepInfo {
+            pc: self.pc,
+            opcode: u8::from(instruction),
+            instruction,
+            interrupt: None,
+            cycles: 1,
+            stalled: false,
+        };
+    }
+
+    // Burns a single cycle while RDY is low or the CPU is halted. The next
+    // cycle would fetch the opcode at pc, so that read is repeated
+    fn stall<B: Bus>(&mut self, memory: &mut B) -> StepInfo {
+        let observed = &mut ObservedBus::new(memory, self.bus_observer.clone());
+        self.dummy_read(
+            &mut MaskedBus::new(observed, self.variant.address_mask()),
+            self.pc,
+        );
+        self.cycles += 1;
+
+        return StepInfo {
+            pc: self.pc,
+            opcode: u8::from(Instruction::NOP),
+            instruction: Instruction::NOP,
+            interrupt: None,
+            cycles: 1,
+            stalled: true,
+        };
+    }
+
+    // Executes instructions until at least `cycles` cycles have passed.
+    // Returns the amount of cycles actually used
+    pub fn run_for_cycles<B: Bus>(&mut self, memory: &mut B, cycles: u64) -> Result<u64, CpuError> {
+        let start = self.cycles;
+        while self.cycles - start < cycles {
+            self.step(memory)?;
+        }
+
+        return Ok(self.cycles - start);
+    }
+
+    // Executes instructions until predicate returns true. Predicate is checked
+    // after every instruction. Returns the amount of cycles used
+    pub fn run_until<B: Bus, F>(
+        &mut self,
+        memory: &mut B,
+        mut predicate: F,
+    ) -> Result<u64, CpuError>
+    where
+        F: FnMut(&CPU) -> bool,
+    {
+        let start = self.cycles;
+        loop {
+            self.step(memory)?;
+            if predicate(self) {
+                break;
+            }
+        }
+
+        return Ok(self.cycles - start);
+    }
+}
+
+macro_rules! ld {
+    ($func_name: ident, $reg_name: ident, $addr_reg: ident, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Immediate, 2);
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 5);
+            cycles.insert(AddressingMode::ZeroPageIndirect, 5);
+
+            let (addr, page_crossed) =
+                self.operand_address(memory, *$addr_mode, self.$addr_reg, false);
+            let value: u8 = memory.read_byte(addr);
+
+            self.$reg_name = value;
+            self.test_number(value);
+
+            self.cycles += cycles[$addr_mode];
+            self.cycles += if page_crossed { 1 } else { 0 }
+        }
+    };
+}
+
+impl CPU {
+    ld! {lda_immediate, a, x, &AddressingMode::Immediate}
+    ld! {lda_zero_page, a, x, &AddressingMode::ZeroPage}
+    ld! {lda_zero_page_x, a, x, &AddressingMode::ZeroPageReg}
+    ld! {lda_absolute, a, x, &AddressingMode::Absolute}
+    ld! {lda_absolute_x, a, x, &AddressingMode::AbsoluteReg}
+    ld! {lda_absolute_y, a, y, &AddressingMode::AbsoluteReg}
+    ld! {lda_indirect_x, a, x, &AddressingMode::IndirectX}
+    ld! {lda_indirect_y, a, y, &AddressingMode::IndirectY}
+    ld! {lda_zero_page_indirect, a, x, &AddressingMode::ZeroPageIndirect}
+}
+
+impl CPU {
+    ld! {ldx_immediate, x, y, &AddressingMode::Immediate}
+    ld! {ldx_zero_page, x, y, &AddressingMode::ZeroPage}
+    ld! {ldx_zero_page_y, x, y, &AddressingMode::ZeroPageReg}
+    ld! {ldx_absolute, x, y, &AddressingMode::Absolute}
+    ld! {ldx_absolute_y, x, y, &AddressingMode::AbsoluteReg}
+}
+
+impl CPU {
+    ld! {ldy_immediate, y, x, &AddressingMode::Immediate}
+    ld! {ldy_zero_page, y, x, &AddressingMode::ZeroPage}
+    ld! {ldy_zero_page_x, y, x, &AddressingMode::ZeroPageReg}
+    ld! {ldy_absolute, y, x, &AddressingMode::Absolute}
+    ld! {ldy_absolute_x, y, x, &AddressingMode::AbsoluteReg}
+}
+
+macro_rules! st {
+    ($func_name: ident, $reg_name: ident, $addr_reg: ident, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 5);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 6);
+            cycles.insert(AddressingMode::ZeroPageIndirect, 5);
+
+            let (addr, _) = self.operand_address(memory, *$addr_mode, self.$addr_reg, true);
+            memory.write_byte(addr, self.$reg_name);
+
+            self.cycles += cycles[$addr_mode];
+        }
+    };
+}
+
+impl CPU {
+    st! {sta_zero_page, a, x, &AddressingMode::ZeroPage}
+    st! {sta_zero_page_x, a, x, &AddressingMode::ZeroPageReg}
+    st! {sta_absolute, a, x, &AddressingMode::Absolute}
+    st! {sta_absolute_x, a, x, &AddressingMode::AbsoluteReg}
+    st! {sta_absolute_y, a, y, &AddressingMode::AbsoluteReg}
+    st! {sta_indirect_x, a, x, &AddressingMode::IndirectX}
+    st! {sta_indirect_y, a, y ,&AddressingMode::IndirectY}
+    st! {sta_zero_page_indirect, a, x, &AddressingMode::ZeroPageIndirect}
+}
+
+impl CPU {
+    st! {stx_zero_page, x, y, &AddressingMode::ZeroPage}
+    st! {stx_zero_page_y, x, y, &AddressingMode::ZeroPageReg}
+    st! {stx_absolute, x, y, &AddressingMode::Absolute}
+}
+
+impl CPU {
+    st! {sty_zero_page, y, x, &AddressingMode::ZeroPage}
+    st! {sty_zero_page_x, y, x, &AddressingMode::ZeroPageReg}
+    st! {sty_absolute, y, x, &AddressingMode::Absolute}
+}
+
+macro_rules! stz {
+    ($func_name: ident, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 5);
+
+            let (addr, _) = self.operand_address(memory, *$addr_mode, self.x, true);
+            memory.write_byte(addr, 0);
+
+            self.cycles += cycles[$addr_mode];
+        }
+    };
+}
+
+impl CPU {
+    stz! {stz_zero_page, &AddressingMode::ZeroPage}
+    stz! {stz_zero_page_x, &AddressingMode::ZeroPageReg}
+    stz! {stz_absolute, &AddressingMode::Absolute}
+    stz! {stz_absolute_x, &AddressingMode::AbsoluteReg}
+}
+
+macro_rules! transfer_reg_reg {
+    ($func_name: ident, $reg_src: ident, $reg_dest: ident, $test_en: expr) => {
+        fn $func_name(&mut self) {
+            let value = self.$reg_src;
+            self.$reg_dest = value;
+
+            if $test_en {
+                self.test_number(value);
+            }
+            self.cycles += 2;
+        }
+    };
+}
+
+impl CPU {
+    transfer_reg_reg! {transfer_a_x, a, x, true}
+    transfer_reg_reg! {transfer_a_y, a, y, true}
+    transfer_reg_reg! {transfer_x_a, x, a, true}
+    transfer_reg_reg! {transfer_y_a, y, a, true}
+    transfer_reg_reg! {transfer_s_x, sp, x, true}
+    transfer_reg_reg! {transfer_x_s, x, sp, false}
+}
+
+impl CPU {
+    // The stack lives in page one and grows down, sp points at the next
+    // free slot and wraps around within the page
+    fn push<B: Bus>(&mut self, memory: &mut B, value: Byte) {
+        memory.write_byte(0x0100 | self.sp as u16, value);
+        self.sp = self.sp.wrapping_sub(1);
+    }
+
+    fn pull<B: Bus>(&mut self, memory: &mut B) -> Byte {
+        self.sp = self.sp.wrapping_add(1);
+        return memory.read_byte(0x0100 | self.sp as u16);
+    }
+
+    // Words are pushed high byte first so they end up little endian
+    fn push_word<B: Bus>(&mut self, memory: &mut B, value: Word) {
+        self.push(memory, (value >> 8) as u8);
+        self.push(memory, value as u8);
+    }
+
+    fn pull_word<B: Bus>(&mut self, memory: &mut B) -> Word {
+        let low = self.pull(memory);
+        let high = self.pull(memory);
+        return ((high as u16) << 8) | low as u16;
+    }
+
+    // Pulling reads the current stack slot before incrementing sp
+    fn stack_dummy_read<B: Bus>(&self, memory: &mut B) {
+        self.dummy_read(memory, 0x0100 | self.sp as u16);
+    }
+}
+
+impl CPU {
+    // Bus access whose value the chip ignores. Only performed in cycle
+    // accurate mode
+    fn dummy_read<B: Bus>(&self, memory: &mut B, addr: Word) {
+        if self.cycle_accurate {
+            memory.read_byte(addr);
+        }
+    }
+
+    // Resolves the operand like Bus::effective_address, adding the dummy
+    // reads made while indexing. Indexed absolute and (zp),Y addresses are
+    // read before the high byte is fixed up: reads only spend that cycle
+    // when a page was crossed, stores and read-modify-write instructions
+    // (`write`) always do. The 65C02 reads the last operand byte again
+    // instead of the unfixed address
+    fn operand_address<B: Bus>(
+        &mut self,
+        memory: &mut B,
+        mode: AddressingMode,
+        index: Byte,
+        write: bool,
+    ) -> (Word, bool) {
+        if !self.cycle_accurate {
+            return memory.effective_address(&mut self.pc, mode, index);
+        }
+
+        // The zero page pointer is read before X is added to it
+        if mode == AddressingMode::IndirectX {
+            let (base, _) = memory.effective_address(&mut self.pc, AddressingMode::ZeroPage, 0);
+            memory.read_byte(base);
+            let addr = memory.read_zero_page_word((base as u8).wrapping_add(index));
+            return (addr, false);
+        }
+
+        let (addr, page_crossed) = memory.effective_address(&mut self.pc, mode, index);
+        match mode {
+            AddressingMode::ZeroPageReg => {
+                memory.read_byte((addr as u8).wrapping_sub(index) as u16);
+            }
+            AddressingMode::AbsoluteReg | AddressingMode::IndirectY if page_crossed || write => {
+                let unfixed = if page_crossed && self.variant == CpuVariant::Cmos65C02 {
+                    self.pc.wrapping_sub(1)
+                } else {
+                    (addr.wrapping_sub(index as u16) & 0xff00) | (addr & 0x00ff)
+                };
+                memory.read_byte(unfixed);
+            }
+            _ => {}
+        }
+
+        return (addr, page_crossed);
+    }
+
+    // Read-modify-write instructions write the unmodified value back
+    // before the result, the 65C02 reads it a second time instead
+    fn modify<B: Bus>(&mut self, memory: &mut B, addr: Word, value: Byte, res: Byte) {
+        if self.cycle_accurate {
+            if self.variant == CpuVariant::Cmos65C02 {
+                memory.read_byte(addr);
+            } else {
+                memory.write_byte(addr, value);
+            }
+        }
+        memory.write_byte(addr, res);
+    }
+}
+
+macro_rules! push_reg {
+    ($func_name: ident, $reg_name: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            let value = self.$reg_name;
+            self.push(memory, value);
+
+            self.cycles += 3;
+        }
+    };
+}
+
+macro_rules! pull_reg {
+    ($func_name: ident, $reg_name: ident, $test_en: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            self.stack_dummy_read(memory);
+            let value = self.pull(memory);
+
+            self.$reg_name = value;
+
+            if $test_en {
+                self.test_number(value);
+            }
+
+            self.cycles += 4;
+        }
+    };
+}
+
+impl CPU {
+    push_reg! {push_accumulator, a}
+    pull_reg! {pull_accumulator, a, true}
+    push_reg! {push_x, x}
+    push_reg! {push_y, y}
+    pull_reg! {pull_x, x, true}
+    pull_reg! {pull_y, y, true}
+}
+
+impl CPU {
+    // PHP always pushes the break flag set
+    fn push_processor_status<B: Bus>(&mut self, memory: &mut B) {
+        let value = self.status.to_pushed(true);
+        self.push(memory, value);
+
+        self.cycles += 3;
+    }
+
+    fn pull_processor_status<B: Bus>(&mut self, memory: &mut B) {
+        self.stack_dummy_read(memory);
+        let value = self.pull(memory);
+
+        self.status = StatusFlags::from_pulled(value);
+
+        self.cycles += 4;
+    }
+}
+
+macro_rules! logic {
+    ($func_name: ident, $op_func: expr, $reg_name: ident, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Immediate, 2);
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 5);
+            cycles.insert(AddressingMode::ZeroPageIndirect, 5);
+
+            let value1 = self.a;
+            let (addr, page_crossed) =
+                self.operand_address(memory, *$addr_mode, self.$reg_name, false);
+            let value2: u8 = memory.read_byte(addr);
+
+            self.a = $op_func(value1, value2);
+            self.test_number(self.a);
+
+            self.cycles += cycles[$addr_mode] + if page_crossed { 1 } else { 0 };
+        }
+    };
+}
+
+impl CPU {
+    logic! {and_immediate, |n1, n2| n1 & n2, x, &AddressingMode::Immediate}
+    logic! {and_zero_page, |n1, n2| n1 & n2, x, &AddressingMode::ZeroPage}
+    logic! {and_zero_page_x, |n1, n2| n1 & n2, x, &AddressingMode::ZeroPageReg}
+    logic! {and_absolute, |n1, n2| n1 & n2, x, &AddressingMode::Absolute}
+    logic! {and_absolute_x, |n1, n2| n1 & n2, x, &AddressingMode::AbsoluteReg}
+    logic! {and_absolute_y, |n1, n2| n1 & n2, y, &AddressingMode::AbsoluteReg}
+    logic! {and_indirect_x, |n1, n2| n1 & n2, x, &AddressingMode::IndirectX}
+    logic! {and_indirect_y, |n1, n2| n1 & n2, y, &AddressingMode::IndirectY}
+    logic! {and_zero_page_indirect, |n1, n2| n1 & n2, x, &AddressingMode::ZeroPageIndirect}
+
+    logic! {eor_immediate, |n1, n2| n1 ^ n2, x, &AddressingMode::Immediate}
+    logic! {eor_zero_page, |n1, n2| n1 ^ n2, x, &AddressingMode::ZeroPage}
+    logic! {eor_zero_page_x, |n1, n2| n1 ^ n2, x, &AddressingMode::ZeroPageReg}
+    logic! {eor_absolute, |n1, n2| n1 ^ n2, x, &AddressingMode::Absolute}
+    logic! {eor_absolute_x, |n1, n2| n1 ^ n2, x, &AddressingMode::AbsoluteReg}
+    logic! {eor_absolute_y, |n1, n2| n1 ^ n2, y, &AddressingMode::AbsoluteReg}
+    logic! {eor_indirect_x, |n1, n2| n1 ^ n2, x, &AddressingMode::IndirectX}
+    logic! {eor_indirect_y, |n1, n2| n1 ^ n2, y, &AddressingMode::IndirectY}
+    logic! {eor_zero_page_indirect, |n1, n2| n1 ^ n2, x, &AddressingMode::ZeroPageIndirect}
+
+    logic! {ora_immediate, |n1, n2| n1 | n2, x, &AddressingMode::Immediate}
+    logic! {ora_zero_page, |n1, n2| n1 | n2, x, &AddressingMode::ZeroPage}
+    logic! {ora_zero_page_x, |n1, n2| n1 | n2, x, &AddressingMode::ZeroPageReg}
+    logic! {ora_absolute, |n1, n2| n1 | n2, x, &AddressingMode::Absolute}
+    logic! {ora_absolute_x, |n1, n2| n1 | n2, x, &AddressingMode::AbsoluteReg}
+    logic! {ora_absolute_y, |n1, n2| n1 | n2, y, &AddressingMode::AbsoluteReg}
+    logic! {ora_indirect_x, |n1, n2| n1 | n2, x, &AddressingMode::IndirectX}
+    logic! {ora_indirect_y, |n1, n2| n1 | n2, y, &AddressingMode::IndirectY}
+    logic! {ora_zero_page_indirect, |n1, n2| n1 | n2, x, &AddressingMode::ZeroPageIndirect}
+
+    fn bit_zero_page<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_zero_page(&mut self.pc);
+
+        self.set_zero((self.a & value) == 0);
+        self.set_overflow((value & 0b0100_0000) == 0b0100_0000);
+        self.set_negative((value & 0b1000_0000) == 0b1000_0000);
+
+        self.cycles += 3;
+    }
+
+    fn bit_absolute<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_absolute(&mut self.pc);
+
+        self.set_zero((self.a & value) == 0);
+        self.set_overflow((value & 0b0100_0000) == 0b0100_0000);
+        self.set_negative((value & 0b1000_0000) == 0b1000_0000);
+
+        self.cycles += 4;
+    }
+
+    // 65C02 BIT immediate only affects the zero flag
+    fn bit_immediate<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_immediate(&mut self.pc);
+
+        self.set_zero((self.a & value) == 0);
+
+        self.cycles += 2;
+    }
+
+    fn bit_zero_page_x<B: Bus>(&mut self, memory: &mut B) {
+        let (addr, _) = self.operand_address(memory, AddressingMode::ZeroPageReg, self.x, false);
+        let value = memory.read_byte(addr);
+
+        self.set_zero((self.a & value) == 0);
+        self.set_overflow((value & 0b0100_0000) == 0b0100_0000);
+        self.set_negative((value & 0b1000_0000) == 0b1000_0000);
+
+        self.cycles += 4;
+    }
+
+    fn bit_absolute_x<B: Bus>(&mut self, memory: &mut B) {
+        let (addr, page_crossed) =
+            self.operand_address(memory, AddressingMode::AbsoluteReg, self.x, false);
+        let value = memory.read_byte(addr);
+
+        self.set_zero((self.a & value) == 0);
+        self.set_overflow((value & 0b0100_0000) == 0b0100_0000);
+        self.set_negative((value & 0b1000_0000) == 0b1000_0000);
+
+        self.cycles += 4 + if page_crossed { 1 } else { 0 };
+    }
+}
+
+macro_rules! test_modify_bits {
+    ($func_name: ident, $op_func: expr, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 5);
+            cycles.insert(AddressingMode::Absolute, 6);
+
+            let (addr, _) = self.operand_address(memory, *$addr_mode, 0, true);
+            let value = memory.read_byte(addr);
+
+            self.set_zero((self.a & value) == 0);
+            let res: u8 = $op_func(value, self.a);
+            self.modify(memory, addr, value, res);
+
+            self.cycles += cycles[$addr_mode];
+        }
+    };
+}
+
+impl CPU {
+    test_modify_bits! {trb_zero_page, |m: u8, a: u8| m & !a, &AddressingMode::ZeroPage}
+    test_modify_bits! {trb_absolute, |m: u8, a: u8| m & !a, &AddressingMode::Absolute}
+    test_modify_bits! {tsb_zero_page, |m: u8, a: u8| m | a, &AddressingMode::ZeroPage}
+    test_modify_bits! {tsb_absolute, |m: u8, a: u8| m | a, &AddressingMode::Absolute}
+}
+
+macro_rules! zero_page_bit {
+    ($func_name: ident, $bit: expr, $set: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            let (addr, _) = self.operand_address(memory, AddressingMode::ZeroPage, 0, true);
+            let value = memory.read_byte(addr);
+            let res = if $set {
+                value | (1 << $bit)
+            } else {
+                value & !(1 << $bit)
+            };
+            self.modify(memory, addr, value, res);
+
+            self.cycles += 5;
+        }
+    };
+}
+
+impl CPU {
+    zero_page_bit! {rmb0, 0, false}
+    zero_page_bit! {rmb1, 1, false}
+    zero_page_bit! {rmb2, 2, false}
+    zero_page_bit! {rmb3, 3, false}
+    zero_page_bit! {rmb4, 4, false}
+    zero_page_bit! {rmb5, 5, false}
+    zero_page_bit! {rmb6, 6, false}
+    zero_page_bit! {rmb7, 7, false}
+
+    zero_page_bit! {smb0, 0, true}
+    zero_page_bit! {smb1, 1, true}
+    zero_page_bit! {smb2, 2, true}
+    zero_page_bit! {smb3, 3, true}
+    zero_page_bit! {smb4, 4, true}
+    zero_page_bit! {smb5, 5, true}
+    zero_page_bit! {smb6, 6, true}
+    zero_page_bit! {smb7, 7, true}
+}
+
+macro_rules! arithmetic {
+    ($func_name: ident, $arithm_func: expr, $addr_mode: expr, $addr_reg: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Immediate, 2);
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 5);
+            cycles.insert(AddressingMode::ZeroPageIndirect, 5);
+
+            let value_reg = self.a;
+            let (addr, page_crossed) =
+                self.operand_address(memory, *$addr_mode, self.$addr_reg, false);
+            let value_mem: u8 = memory.read_byte(addr);
+            let carry = self.get_carry();
+
+            self.cycles += cycles[$addr_mode] + if page_crossed { 1 } else { 0 };
+            $arithm_func(self, value_reg, value_mem, carry);
+
+            // The 65C02 takes an extra cycle to fix up the flags of a
+            // decimal result
+            if self.variant == CpuVariant::Cmos65C02
+                && self.get_decimal_mode()
+                && self.decimal_enabled
+            {
+                self.dummy_read(memory, self.pc);
+                self.cycles += 1;
+            }
+        }
+    };
+}
+
+macro_rules! cmp {
+    ($func_name: ident, $reg_name: ident, $addr_mode: expr, $addr_reg: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Immediate, 2);
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 5);
+            cycles.insert(AddressingMode::ZeroPageIndirect, 5);
+
+            let value_reg = self.$reg_name;
+            let (addr, page_crossed) =
+                self.operand_address(memory, *$addr_mode, self.$addr_reg, false);
+            let value_mem: u8 = memory.read_byte(addr);
+
+            Self::compare(self, value_reg, value_mem);
+
+            self.cycles += cycles[$addr_mode] + if page_crossed { 1 } else { 0 };
+        }
+    };
+}
+
+impl CPU {
+    // Carry is the unsigned carry out of bit 7, overflow is set when both
+    // operands have the same sign and the result has the other one
+    fn addition(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        if cpu.get_decimal_mode() && cpu.decimal_enabled {
+            if cpu.variant == CpuVariant::Cmos65C02 {
+                Self::cmos_decimal_addition(cpu, n1, n2, c);
+            } else {
+                Self::decimal_addition(cpu, n1, n2, c);
+            }
+            return;
+        }
+
+        let sum = n1 as u16 + n2 as u16 + c as u16;
+        let value = sum as u8;
+        cpu.a = value;
+        cpu.test_number(value);
+        cpu.set_carry(sum > 0xff);
+        cpu.set_overflow(((n1 ^ value) & (n2 ^ value) & 0b1000_0000) != 0);
+    }
+
+    // Carry is cleared when the substraction borrows, overflow is set when
+    // the operands have different signs and the result has the sign of n2
+    fn substraction(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        if cpu.get_decimal_mode() && cpu.decimal_enabled {
+            if cpu.variant == CpuVariant::Cmos65C02 {
+                Self::cmos_decimal_substraction(cpu, n1, n2, c);
+            } else {
+                Self::decimal_substraction(cpu, n1, n2, c);
+            }
+            return;
+        }
+
+        let difference = n1 as i16 - n2 as i16 - (!c) as i16;
+        let value = difference as u8;
+        cpu.a = value;
+        cpu.test_number(value);
+        cpu.set_carry(difference >= 0);
+        cpu.set_overflow(((n1 ^ n2) & (n1 ^ value) & 0b1000_0000) != 0);
+    }
+
+    // Substraction without borrow that only sets flags, used by CMP, CPX,
+    // CPY and DCP
+    fn compare(cpu: &mut CPU, register: u8, value: u8) {
+        cpu.set_carry(register >= value);
+        cpu.test_number(register.wrapping_sub(value));
+    }
+
+    // NMOS decimal addition. A and C hold the BCD result, Z is taken from
+    // the binary sum and N and V from the sum before the high digit is
+    // adjusted, exactly like the NMOS chip does
+    fn decimal_addition(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        let binary = n1.wrapping_add(n2).wrapping_add(c as u8);
+
+        let mut low = (n1 & 0x0f) as u16 + (n2 & 0x0f) as u16 + c as u16;
+        if low >= 0x0a {
+            low = ((low + 0x06) & 0x0f) + 0x10;
+        }
+        let intermediate = (n1 & 0xf0) as u16 + (n2 & 0xf0) as u16 + low;
+        let intermediate_signed = (n1 & 0xf0) as i8 as i16 + (n2 & 0xf0) as i8 as i16 + low as i16;
+
+        let mut value = intermediate;
+        if value >= 0xa0 {
+            value += 0x60;
+        }
+
+        cpu.a = value as u8;
+        cpu.set_zero(binary == 0);
+        cpu.set_negative((intermediate & 0b1000_0000) != 0);
+        cpu.set_overflow(!(-128..=127).contains(&intermediate_signed));
+        cpu.set_carry(value >= 0x100);
+    }
+
+    // NMOS decimal substraction. Only A holds the BCD result, all flags are
+    // the same as for binary substraction
+    fn decimal_substraction(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        let binary = n1 as i16 - n2 as i16 - (1 - c as i16);
+
+        let mut low = (n1 & 0x0f) as i16 - (n2 & 0x0f) as i16 + c as i16 - 1;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0f) - 0x10;
+        }
+        let mut value = (n1 & 0xf0) as i16 - (n2 & 0xf0) as i16 + low;
+        if value < 0 {
+            value -= 0x60;
+        }
+
+        cpu.a = value as u8;
+        cpu.set_zero(binary as u8 == 0);
+        cpu.set_negative((binary as u8 & 0b1000_0000) != 0);
+        cpu.set_overflow(((n1 ^ n2) & (n1 ^ binary as u8) & 0b1000_0000) != 0);
+        cpu.set_carry(binary >= 0);
+    }
+
+    // 65C02 decimal addition. A, C and V match the NMOS chip, N and Z are
+    // taken from the BCD result
+    fn cmos_decimal_addition(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        Self::decimal_addition(cpu, n1, n2, c);
+        cpu.test_number(cpu.a);
+    }
+
+    // 65C02 decimal substraction. The high digit is adjusted on the full
+    // difference before the low one, which only differs from the NMOS chip
+    // for invalid BCD operands. N and Z are taken from the BCD result, C
+    // and V are the same as for binary substraction
+    fn cmos_decimal_substraction(cpu: &mut CPU, n1: u8, n2: u8, c: bool) {
+        let binary = n1 as i16 - n2 as i16 - (1 - c as i16);
+        let low = (n1 & 0x0f) as i16 - (n2 & 0x0f) as i16 - (1 - c as i16);
+
+        let mut value = binary;
+        if binary < 0 {
+            value -= 0x60;
+        }
+        if low < 0 {
+            value -= 0x06;
+        }
+
+        cpu.a = value as u8;
+        cpu.test_number(cpu.a);
+        cpu.set_overflow(((n1 ^ n2) & (n1 ^ binary as u8) & 0b1000_0000) != 0);
+        cpu.set_carry(binary >= 0);
+    }
+
+    arithmetic! {adc_immediate, Self::addition, &AddressingMode::Immediate, x}
+    arithmetic! {adc_zero_page, Self::addition, &AddressingMode::ZeroPage, x}
+    arithmetic! {adc_zero_page_x, Self::addition, &AddressingMode::ZeroPageReg, x}
+    arithmetic! {adc_absolute, Self::addition, &AddressingMode::Absolute, x}
+    arithmetic! {adc_absolute_x, Self::addition, &AddressingMode::AbsoluteReg, x}
+    arithmetic! {adc_absolute_y, Self::addition, &AddressingMode::AbsoluteReg, y}
+    arithmetic! {adc_indirect_x, Self::addition, &AddressingMode::IndirectX, x}
+    arithmetic! {adc_indirect_y, Self::addition, &AddressingMode::IndirectY, y}
+    arithmetic! {adc_zero_page_indirect, Self::addition, &AddressingMode::ZeroPageIndirect, x}
+
+    arithmetic! {sbc_immediate, Self::substraction, &AddressingMode::Immediate, x}
+    arithmetic! {sbc_zero_page, Self::substraction, &AddressingMode::ZeroPage, x}
+    arithmetic! {sbc_zero_page_x, Self::substraction, &AddressingMode::ZeroPageReg, x}
+    arithmetic! {sbc_absolute, Self::substraction, &AddressingMode::Absolute, x}
+    arithmetic! {sbc_absolute_x, Self::substraction, &AddressingMode::AbsoluteReg, x}
+    arithmetic! {sbc_absolute_y, Self::substraction, &AddressingMode::AbsoluteReg, y}
+    arithmetic! {sbc_indirect_x, Self::substraction, &AddressingMode::IndirectX, x}
+    arithmetic! {sbc_indirect_y, Self::substraction, &AddressingMode::IndirectY, y}
+    arithmetic! {sbc_zero_page_indirect, Self::substraction, &AddressingMode::ZeroPageIndirect, x}
+
+    cmp! {cmp_immediate, a, &AddressingMode::Immediate, x}
+    cmp! {cmp_zero_page, a, &AddressingMode::ZeroPage, x}
+    cmp! {cmp_zero_page_x, a, &AddressingMode::ZeroPageReg, x}
+    cmp! {cmp_absolute, a, &AddressingMode::Absolute, x}
+    cmp! {cmp_absolute_x, a, &AddressingMode::AbsoluteReg, x}
+    cmp! {cmp_absolute_y, a, &AddressingMode::AbsoluteReg, y}
+    cmp! {cmp_indirect_x, a, &AddressingMode::IndirectX, x}
+    cmp! {cmp_indirect_y, a, &AddressingMode::IndirectY, y}
+    cmp! {cmp_zero_page_indirect, a, &AddressingMode::ZeroPageIndirect, x}
+
+    cmp! {cpx_immediate, x, &AddressingMode::Immediate, y}
+    cmp! {cpx_zero_page, x, &AddressingMode::ZeroPage, y}
+    cmp! {cpx_absolute, x, &AddressingMode::Absolute, y}
+
+    cmp! {cpy_immediate, y, &AddressingMode::Immediate, x}
+    cmp! {cpy_zero_page, y, &AddressingMode::ZeroPage, x}
+    cmp! {cpy_absolute, y, &AddressingMode::Absolute, x}
+}
+
+macro_rules! inc_dec {
+    ($func_name: ident, $op_func: expr, $addr_mode: expr, $reg_type: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            match $reg_type {
+                Register::A => {
+                    self.a = $op_func(self.a);
+                    self.cycles += 2;
+                    self.test_number(self.a);
+                }
+                Register::X => {
+                    self.x = $op_func(self.x);
+                    self.cycles += 2;
+                    self.test_number(self.x);
+                }
+                Register::Y => {
+                    self.y = $op_func(self.y);
+                    self.cycles += 2;
+                    self.test_number(self.y);
+                }
+                _ => {
+                    use std::collections::HashMap;
+                    let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+                    cycles.insert(AddressingMode::ZeroPage, 5);
+                    cycles.insert(AddressingMode::ZeroPageReg, 6);
+                    cycles.insert(AddressingMode::Absolute, 6);
+                    cycles.insert(AddressingMode::AbsoluteReg, 7);
+
+                    let (addr, _) = self.operand_address(memory, $addr_mode, self.x, true);
+                    let value = memory.read_byte(addr);
+                    let res = $op_func(value);
+                    self.modify(memory, addr, value, res);
+
+                    self.cycles += cycles[&$addr_mode];
+                    self.test_number(res);
+                }
+            }
+        }
+    };
+}
+
+impl CPU {
+    inc_dec! {inc_zero_page, |n: u8| n.wrapping_add(1), AddressingMode::ZeroPage, Register::None}
+    inc_dec! {inc_zero_page_x, |n: u8| n.wrapping_add(1), AddressingMode::ZeroPageReg, Register::None}
+    inc_dec! {inc_absolute, |n: u8| n.wrapping_add(1), AddressingMode::Absolute, Register::None}
+    inc_dec! {inc_absolute_x, |n: u8| n.wrapping_add(1), AddressingMode::AbsoluteReg, Register::None}
+    inc_dec! {inx, |n: u8| n.wrapping_add(1), AddressingMode::Implied, Register::X}
+    inc_dec! {iny, |n: u8| n.wrapping_add(1), AddressingMode::Implied, Register::Y}
+    inc_dec! {inc_accumulator, |n: u8| n.wrapping_add(1), AddressingMode::Implied, Register::A}
+
+    inc_dec! {dec_zero_page, |n: u8| n.wrapping_sub(1), AddressingMode::ZeroPage, Register::None}
+    inc_dec! {dec_zero_page_x, |n: u8| n.wrapping_sub(1), AddressingMode::ZeroPageReg, Register::None}
+    inc_dec! {dec_absolute, |n: u8| n.wrapping_sub(1), AddressingMode::Absolute, Register::None}
+    inc_dec! {dec_absolute_x, |n: u8| n.wrapping_sub(1), AddressingMode::AbsoluteReg, Register::None}
+    inc_dec! {dex, |n: u8| n.wrapping_sub(1), AddressingMode::Implied, Register::X}
+    inc_dec! {dey, |n: u8| n.wrapping_sub(1), AddressingMode::Implied, Register::Y}
+    inc_dec! {dec_accumulator, |n: u8| n.wrapping_sub(1), AddressingMode::Implied, Register::A}
+}
+
+macro_rules! shifts {
+    ($func_name: ident, $op_func: expr, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Implied, 2);
+            cycles.insert(AddressingMode::ZeroPage, 5);
+            cycles.insert(AddressingMode::ZeroPageReg, 6);
+            cycles.insert(AddressingMode::Absolute, 6);
+            cycles.insert(AddressingMode::AbsoluteReg, 7);
+
+            if *$addr_mode == AddressingMode::Implied {
+                let (res, carry) = $op_func(self.a, self.get_carry());
+                self.a = res;
+                self.set_carry(carry);
+                self.test_number(res);
+                self.cycles += cycles[$addr_mode];
+                return;
+            }
+
+            // The 65C02 only spends the fix-up cycle of abs,X when the
+            // index crosses a page
+            let cmos = self.variant == CpuVariant::Cmos65C02;
+            let (addr, page_crossed) = self.operand_address(memory, *$addr_mode, self.x, !cmos);
+            let value = memory.read_byte(addr);
+            let (res, carry) = $op_func(value, self.get_carry());
+            self.modify(memory, addr, value, res);
+
+            self.set_carry(carry);
+            self.test_number(res);
+            self.cycles += match *$addr_mode {
+                AddressingMode::AbsoluteReg if cmos && !page_crossed => 6,
+                _ => cycles[$addr_mode],
+            };
+        }
+    };
+}
+
+fn asl_func(n: u8, _carry: bool) -> (u8, bool) {
+    let bit7 = ((n & 0b1000_0000) >> 7) == 1;
+    (n << 1, bit7)
+}
+
+fn lsr_func(n: u8, _carry: bool) -> (u8, bool) {
+    let bit0 = (n & 0b0000_0001) == 1;
+    ((n >> 1), bit0)
+}
+
+// The carry in is rotated into bit 0 and bit 7 becomes the carry out
+fn rol_func(n: u8, carry: bool) -> (u8, bool) {
+    let bit7 = ((n & 0b1000_0000) >> 7) == 1;
+    ((n << 1) | carry as u8, bit7)
+}
+
+// The carry in is rotated into bit 7 and bit 0 becomes the carry out
+fn ror_func(n: u8, carry: bool) -> (u8, bool) {
+    let bit0 = (n & 0b0000_0001) == 1;
+    ((n >> 1) | (carry as u8) << 7, bit0)
+}
+
+impl CPU {
+    shifts! {asl_accumulator, asl_func, &AddressingMode::Implied}
+    shifts! {asl_zero_page, asl_func, &AddressingMode::ZeroPage}
+    shifts! {asl_zero_page_x, asl_func, &AddressingMode::ZeroPageReg}
+    shifts! {asl_absolute, asl_func, &AddressingMode::Absolute}
+    shifts! {asl_absolute_x, asl_func, &AddressingMode::AbsoluteReg}
+
+    shifts! {lsr_accumulator, lsr_func, &AddressingMode::Implied}
+    shifts! {lsr_zero_page, lsr_func, &AddressingMode::ZeroPage}
+    shifts! {lsr_zero_page_x, lsr_func, &AddressingMode::ZeroPageReg}
+    shifts! {lsr_absolute, lsr_func, &AddressingMode::Absolute}
+    shifts! {lsr_absolute_x, lsr_func, &AddressingMode::AbsoluteReg}
+
+    shifts! {rol_accumulator, rol_func, &AddressingMode::Implied}
+    shifts! {rol_zero_page, rol_func, &AddressingMode::ZeroPage}
+    shifts! {rol_zero_page_x, rol_func, &AddressingMode::ZeroPageReg}
+    shifts! {rol_absolute, rol_func, &AddressingMode::Absolute}
+    shifts! {rol_absolute_x, rol_func, &AddressingMode::AbsoluteReg}
+
+    shifts! {ror_accumulator, ror_func, &AddressingMode::Implied}
+    shifts! {ror_zero_page, ror_func, &AddressingMode::ZeroPage}
+    shifts! {ror_zero_page_x, ror_func, &AddressingMode::ZeroPageReg}
+    shifts! {ror_absolute, ror_func, &AddressingMode::Absolute}
+    shifts! {ror_absolute_x, ror_func, &AddressingMode::AbsoluteReg}
+}
+
+impl CPU {
+    fn jmp_absolute<B: Bus>(&mut self, memory: &mut B) {
+        self.pc = memory.read(self.pc);
+        self.cycles += 3;
+    }
+
+    // NMOS chips don't carry into the high byte of the pointer, JMP ($10FF)
+    // reads its high byte from $1000. The 65C02 fixes this at the cost of
+    // one cycle
+    fn jmp_indirect<B: Bus>(&mut self, memory: &mut B) {
+        let pointer: u16 = memory.read(self.pc);
+        let high_addr = if self.variant.has_jmp_indirect_bug() {
+            (pointer & 0xff00) | (pointer.wrapping_add(1) & 0x00ff)
+        } else {
+            self.dummy_read(memory, self.pc.wrapping_add(1));
+            self.cycles += 1;
+            pointer.wrapping_add(1)
+        };
+        let low: u8 = memory.read(pointer);
+        let high: u8 = memory.read(high_addr);
+
+        self.pc = ((high as u16) << 8) | low as u16;
+        self.cycles += 5;
+    }
+
+    fn jmp_absolute_x_indirect<B: Bus>(&mut self, memory: &mut B) {
+        let pointer: u16 = memory.read(self.pc);
+        self.dummy_read(memory, self.pc.wrapping_add(1));
+
+        self.pc = memory.read(add_mod_65536(pointer, self.x as u16));
+        self.cycles += 6;
+    }
+
+    // Pushes the address of the last byte of the instruction, RTS adds
+    // one when pulling it. The high byte of the destination is only read
+    // after the return address has been pushed
+    fn jsr_absolute<B: Bus>(&mut self, memory: &mut B) {
+        let low = memory.read_byte(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        self.stack_dummy_read(memory);
+
+        let return_addr = self.pc;
+        self.push_word(memory, return_addr);
+        let high = memory.read_byte(self.pc);
+
+        self.pc = ((high as u16) << 8) | low as u16;
+        self.cycles += 6;
+    }
+
+    // Reads the pulled address before incrementing it
+    fn rts_implied<B: Bus>(&mut self, memory: &mut B) {
+        self.stack_dummy_read(memory);
+        let dest = self.pull_word(memory);
+        self.dummy_read(memory, dest);
+        self.pc = dest.wrapping_add(1);
+        self.cycles += 6;
+    }
+}
+
+macro_rules! branches {
+    ($func_name: ident, $cpu_flag_val: ident, $is_set: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            let condition = self.$cpu_flag_val() == $is_set;
+            self.branch(memory, condition);
+        }
+    };
+}
+
+macro_rules! branch_on_bit {
+    ($func_name: ident, $bit: expr, $is_set: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            let (addr, _) = self.operand_address(memory, AddressingMode::ZeroPage, 0, false);
+            let value = memory.read_byte(addr);
+            self.dummy_read(memory, addr);
+            let condition = ((value >> $bit) & 1 == 1) == $is_set;
+            self.branch(memory, condition);
+            self.cycles += 3;
+        }
+    };
+}
+
+impl CPU {
+    // Reads the signed offset operand and jumps relative to the next
+    // instruction if condition holds. A taken branch costs one cycle more,
+    // two if the target is on another page
+    fn branch<B: Bus>(&mut self, memory: &mut B, condition: bool) {
+        let offset: u8 = memory.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        if condition {
+            let target = self.pc.wrapping_add(offset as i8 as u16);
+            let page_crossed = (self.pc & 0xff00) != (target & 0xff00);
+
+            // The next opcode is read while the offset is added, then the
+            // target with the old high byte (the 65C02 reads the next
+            // opcode again)
+            self.dummy_read(memory, self.pc);
+            if page_crossed {
+                let unfixed = if self.variant == CpuVariant::Cmos65C02 {
+                    self.pc
+                } else {
+                    (self.pc & 0xff00) | (target & 0x00ff)
+                };
+                self.dummy_read(memory, unfixed);
+            }
+            self.pc = target;
+            self.cycles += 3 + page_crossed as u64;
+        } else {
+            self.cycles += 2;
+        }
+    }
+
+    fn bra<B: Bus>(&mut self, memory: &mut B) {
+        self.branch(memory, true);
+    }
+
+    branches! {bcc, get_carry, false}
+    branches! {bcs, get_carry, true}
+    branches! {beq, get_zero, true}
+    branches! {bne, get_zero, false}
+    branches! {bmi, get_negative, true}
+    branches! {bpl, get_negative, false}
+    branches! {bvc, get_overflow, false}
+    branches! {bvs, get_overflow, true}
+
+    branch_on_bit! {bbr0, 0, false}
+    branch_on_bit! {bbr1, 1, false}
+    branch_on_bit! {bbr2, 2, false}
+    branch_on_bit! {bbr3, 3, false}
+    branch_on_bit! {bbr4, 4, false}
+    branch_on_bit! {bbr5, 5, false}
+    branch_on_bit! {bbr6, 6, false}
+    branch_on_bit! {bbr7, 7, false}
+
+    branch_on_bit! {bbs0, 0, true}
+    branch_on_bit! {bbs1, 1, true}
+    branch_on_bit! {bbs2, 2, true}
+    branch_on_bit! {bbs3, 3, true}
+    branch_on_bit! {bbs4, 4, true}
+    branch_on_bit! {bbs5, 5, true}
+    branch_on_bit! {bbs6, 6, true}
+    branch_on_bit! {bbs7, 7, true}
+}
+
+impl CPU {
+    fn clc(&mut self) {
+        self.set_carry(false);
+        self.cycles += 2;
+    }
+
+    fn cld(&mut self) {
+        self.set_decimal_mode(false);
+        self.cycles += 2;
+    }
+
+    fn cli(&mut self) {
+        self.set_interrupt_disable(false);
+        self.cycles += 2;
+    }
+
+    fn clv(&mut self) {
+        self.set_overflow(false);
+        self.cycles += 2;
+    }
+
+    fn sec(&mut self) {
+        self.set_carry(true);
+        self.cycles += 2;
+    }
+
+    fn sed(&mut self) {
+        self.set_decimal_mode(true);
+        self.cycles += 2;
+    }
+
+    fn sei(&mut self) {
+        self.set_interrupt_disable(true);
+        self.cycles += 2;
+    }
+}
+
+impl CPU {
+    // Pushes program counter and the given status onto the stack
+    fn push_interrupt_frame<B: Bus>(&mut self, memory: &mut B, status: Byte) {
+        let pc = self.pc;
+        self.push_word(memory, pc);
+        self.push(memory, status);
+    }
+
+    // The 65C02 clears decimal mode when entering an interrupt handler or
+    // coming out of reset, the NMOS chips leave it as it was
+    fn clear_cmos_decimal_mode(&mut self) {
+        if self.variant == CpuVariant::Cmos65C02 {
+            self.status.remove(StatusFlags::DECIMAL_MODE);
+        }
+    }
+
+    // BRK is followed by a padding byte, the pushed return address skips it
+    fn brk<B: Bus>(&mut self, memory: &mut B) {
+        self.pc = self.pc.wrapping_add(1);
+        let status = self.status.to_pushed(true);
+        self.push_interrupt_frame(memory, status);
+        self.clear_cmos_decimal_mode();
+
+        let irq_pc: u16 = memory.read(0xfffe);
+        self.pc = irq_pc;
+        self.cycles += 7;
+    }
+
+    fn nop(&mut self) {
+        self.cycles += 2;
+    }
+
+    // Sleeps until an interrupt line is asserted
+    fn wai<B: Bus>(&mut self, memory: &mut B) {
+        self.dummy_read(memory, self.pc);
+        self.waiting = true;
+        self.cycles += 3;
+    }
+
+    // Stops the clock until the next reset
+    fn stp<B: Bus>(&mut self, memory: &mut B) {
+        self.dummy_read(memory, self.pc);
+        self.stopped = true;
+        self.cycles += 3;
+    }
+
+    fn rti<B: Bus>(&mut self, memory: &mut B) {
+        self.stack_dummy_read(memory);
+        let status = self.pull(memory);
+        let pc = self.pull_word(memory);
+
+        self.status = StatusFlags::from_pulled(status);
+        self.pc = pc;
+        self.cycles += 6;
+    }
+}
+
+macro_rules! lax {
+    ($func_name: ident, $addr_mode: expr, $addr_reg: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+            cycles.insert(AddressingMode::IndirectY, 5);
+
+            let (addr, page_crossed) =
+                self.operand_address(memory, *$addr_mode, self.$addr_reg, false);
+            let value: u8 = memory.read_byte(addr);
+
+            self.a = value;
+            self.x = value;
+            self.test_number(value);
+
+            self.cycles += cycles[$addr_mode] + if page_crossed { 1 } else { 0 };
+        }
+    };
+}
+
+macro_rules! sax {
+    ($func_name: ident, $addr_mode: expr, $addr_reg: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::IndirectX, 6);
+
+            let (addr, _) = self.operand_address(memory, *$addr_mode, self.$addr_reg, true);
+            memory.write_byte(addr, self.a & self.x);
+
+            self.cycles += cycles[$addr_mode];
+        }
+    };
+}
+
+// Read-modify-write instruction combined with an operation on the accumulator.
+// op_func returns the value written back to memory
+macro_rules! combined_rmw {
+    ($func_name: ident, $op_func: expr, $addr_mode: expr, $addr_reg: ident) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::ZeroPage, 5);
+            cycles.insert(AddressingMode::ZeroPageReg, 6);
+            cycles.insert(AddressingMode::Absolute, 6);
+            cycles.insert(AddressingMode::AbsoluteReg, 7);
+            cycles.insert(AddressingMode::IndirectX, 8);
+            cycles.insert(AddressingMode::IndirectY, 8);
+
+            let (addr, _) = self.operand_address(memory, *$addr_mode, self.$addr_reg, true);
+            let value = memory.read_byte(addr);
+            let res: u8 = $op_func(self, value);
+            self.modify(memory, addr, value, res);
+
+            self.cycles += cycles[$addr_mode];
+        }
+    };
+}
+
+// Reads the operand and discards it
+macro_rules! nop_read {
+    ($func_name: ident, $addr_mode: expr) => {
+        fn $func_name<B: Bus>(&mut self, memory: &mut B) {
+            use std::collections::HashMap;
+            let mut cycles: HashMap<AddressingMode, u64> = HashMap::new();
+            cycles.insert(AddressingMode::Immediate, 2);
+            cycles.insert(AddressingMode::ZeroPage, 3);
+            cycles.insert(AddressingMode::ZeroPageReg, 4);
+            cycles.insert(AddressingMode::Absolute, 4);
+            cycles.insert(AddressingMode::AbsoluteReg, 4);
+
+            let (addr, page_crossed) = self.operand_address(memory, *$addr_mode, self.x, false);
+            let _: u8 = memory.read_byte(addr);
+
+            self.cycles += cycles[$addr_mode] + if page_crossed { 1 } else { 0 };
+        }
+    };
+}
+
+impl CPU {
+    lax! {lax_zero_page, &AddressingMode::ZeroPage, y}
+    lax! {lax_zero_page_y, &AddressingMode::ZeroPageReg, y}
+    lax! {lax_absolute, &AddressingMode::Absolute, y}
+    lax! {lax_absolute_y, &AddressingMode::AbsoluteReg, y}
+    lax! {lax_indirect_x, &AddressingMode::IndirectX, x}
+    lax! {lax_indirect_y, &AddressingMode::IndirectY, y}
+
+    sax! {sax_zero_page, &AddressingMode::ZeroPage, y}
+    sax! {sax_zero_page_y, &AddressingMode::ZeroPageReg, y}
+    sax! {sax_absolute, &AddressingMode::Absolute, y}
+    sax! {sax_indirect_x, &AddressingMode::IndirectX, x}
+}
+
+impl CPU {
+    fn slo_func(cpu: &mut CPU, n: u8) -> u8 {
+        let (res, carry) = asl_func(n, cpu.get_carry());
+        cpu.set_carry(carry);
+        cpu.a |= res;
+        cpu.test_number(cpu.a);
+
+        return res;
+    }
+
+    fn rla_func(cpu: &mut CPU, n: u8) -> u8 {
+        let (res, carry) = rol_func(n, cpu.get_carry());
+        cpu.set_carry(carry);
+        cpu.a &= res;
+        cpu.test_number(cpu.a);
+
+        return res;
+    }
+
+    fn sre_func(cpu: &mut CPU, n: u8) -> u8 {
+        let (res, carry) = lsr_func(n, cpu.get_carry());
+        cpu.set_carry(carry);
+        cpu.a ^= res;
+        cpu.test_number(cpu.a);
+
+        return res;
+    }
+
+    fn rra_func(cpu: &mut CPU, n: u8) -> u8 {
+        let (res, carry) = ror_func(n, cpu.get_carry());
+        let a = cpu.a;
+        Self::addition(cpu, a, res, carry);
+
+        return res;
+    }
+
+    fn dcp_func(cpu: &mut CPU, n: u8) -> u8 {
+        let res = n.wrapping_sub(1);
+        let a = cpu.a;
+        Self::compare(cpu, a, res);
+
+        return res;
+    }
+
+    fn isc_func(cpu: &mut CPU, n: u8) -> u8 {
+        let res = n.wrapping_add(1);
+        let a = cpu.a;
+        let carry = cpu.get_carry();
+        Self::substraction(cpu, a, res, carry);
+
+        return res;
+    }
+
+    combined_rmw! {slo_zero_page, Self::slo_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {slo_zero_page_x, Self::slo_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {slo_absolute, Self::slo_func, &AddressingMode::Absolute, x}
+    combined_rmw! {slo_absolute_x, Self::slo_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {slo_absolute_y, Self::slo_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {slo_indirect_x, Self::slo_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {slo_indirect_y, Self::slo_func, &AddressingMode::IndirectY, y}
+
+    combined_rmw! {rla_zero_page, Self::rla_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {rla_zero_page_x, Self::rla_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {rla_absolute, Self::rla_func, &AddressingMode::Absolute, x}
+    combined_rmw! {rla_absolute_x, Self::rla_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {rla_absolute_y, Self::rla_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {rla_indirect_x, Self::rla_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {rla_indirect_y, Self::rla_func, &AddressingMode::IndirectY, y}
+
+    combined_rmw! {sre_zero_page, Self::sre_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {sre_zero_page_x, Self::sre_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {sre_absolute, Self::sre_func, &AddressingMode::Absolute, x}
+    combined_rmw! {sre_absolute_x, Self::sre_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {sre_absolute_y, Self::sre_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {sre_indirect_x, Self::sre_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {sre_indirect_y, Self::sre_func, &AddressingMode::IndirectY, y}
+
+    combined_rmw! {rra_zero_page, Self::rra_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {rra_zero_page_x, Self::rra_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {rra_absolute, Self::rra_func, &AddressingMode::Absolute, x}
+    combined_rmw! {rra_absolute_x, Self::rra_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {rra_absolute_y, Self::rra_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {rra_indirect_x, Self::rra_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {rra_indirect_y, Self::rra_func, &AddressingMode::IndirectY, y}
+
+    combined_rmw! {dcp_zero_page, Self::dcp_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {dcp_zero_page_x, Self::dcp_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {dcp_absolute, Self::dcp_func, &AddressingMode::Absolute, x}
+    combined_rmw! {dcp_absolute_x, Self::dcp_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {dcp_absolute_y, Self::dcp_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {dcp_indirect_x, Self::dcp_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {dcp_indirect_y, Self::dcp_func, &AddressingMode::IndirectY, y}
+
+    combined_rmw! {isc_zero_page, Self::isc_func, &AddressingMode::ZeroPage, x}
+    combined_rmw! {isc_zero_page_x, Self::isc_func, &AddressingMode::ZeroPageReg, x}
+    combined_rmw! {isc_absolute, Self::isc_func, &AddressingMode::Absolute, x}
+    combined_rmw! {isc_absolute_x, Self::isc_func, &AddressingMode::AbsoluteReg, x}
+    combined_rmw! {isc_absolute_y, Self::isc_func, &AddressingMode::AbsoluteReg, y}
+    combined_rmw! {isc_indirect_x, Self::isc_func, &AddressingMode::IndirectX, x}
+    combined_rmw! {isc_indirect_y, Self::isc_func, &AddressingMode::IndirectY, y}
+}
+
+impl CPU {
+    // AND followed by copying the negative flag into carry
+    fn anc_immediate<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_immediate(&mut self.pc);
+
+        self.a &= value;
+        self.test_number(self.a);
+        self.set_carry((self.a & 0b1000_0000) != 0);
+
+        self.cycles += 2;
+    }
+
+    // AND followed by LSR A
+    fn alr_immediate<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_immediate(&mut self.pc);
+
+        let (res, carry) = lsr_func(self.a & value, self.get_carry());
+        self.a = res;
+        self.set_carry(carry);
+        self.test_number(res);
+
+        self.cycles += 2;
+    }
+
+    // AND followed by ROR A. Carry and overflow come from bits 6 and 5 of
+    // the result, in decimal mode the result is additionally BCD adjusted
+    fn arr_immediate<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_immediate(&mut self.pc);
+
+        let and = self.a & value;
+        let carry_in = self.get_carry();
+        let mut res = (and >> 1) | ((carry_in as u8) << 7);
+
+        self.set_zero(res == 0);
+        self.set_negative(carry_in);
+
+        if self.get_decimal_mode() && self.decimal_enabled {
+            self.set_overflow(((and ^ res) & 0b0100_0000) != 0);
+
+            if (and & 0x0f) + (and & 0x01) > 0x05 {
+                res = (res & 0xf0) | (res.wrapping_add(0x06) & 0x0f);
+            }
+
+            let carry = (and & 0xf0) as u16 + (and & 0x10) as u16 > 0x50;
+            if carry {
+                res = res.wrapping_add(0x60);
+            }
+            self.set_carry(carry);
+        } else {
+            let bit6 = (res & 0b0100_0000) != 0;
+            let bit5 = (res & 0b0010_0000) != 0;
+            self.set_carry(bit6);
+            self.set_overflow(bit6 ^ bit5);
+        }
+
+        self.a = res;
+        self.cycles += 2;
+    }
+
+    // X = (A & X) - value, carry is set like in CMP and decimal mode is ignored
+    fn sbx_immediate<B: Bus>(&mut self, memory: &mut B) {
+        let value: u8 = memory.read_immediate(&mut self.pc);
+
+        let and = self.a & self.x;
+        self.x = and.wrapping_sub(value);
+        self.set_carry(and >= value);
+        self.test_number(self.x);
+
+        self.cycles += 2;
+    }
+
+    nop_read! {nop_immediate, &AddressingMode::Immediate}
+    nop_read! {nop_zero_page, &AddressingMode::ZeroPage}
+    nop_read! {nop_zero_page_x, &AddressingMode::ZeroPageReg}
+    nop_read! {nop_absolute, &AddressingMode::Absolute}
+    nop_read! {nop_absolute_x, &AddressingMode::AbsoluteReg}
+
+    fn nop_fast(&mut self) {
+        self.cycles += 1;
+    }
+
+    // Reads $FF00 plus the low operand byte, then $FFFF four times
+    fn nop_slow<B: Bus>(&mut self, memory: &mut B) {
+        let (addr, _) = memory.effective_address(&mut self.pc, AddressingMode::Absolute, 0);
+        self.dummy_read(memory, 0xff00 | (addr & 0x00ff));
+        for _ in 0..4 {
+            self.dummy_read(memory, 0xffff);
+        }
+
+        self.cycles += 8;
+    }
+
+    // Locks up the CPU until the next reset
+    fn jam(&mut self) {
+        self.jammed = true;
+        self.cycles += 2;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::unnecessary_cast)]
+mod tests {
+    use std::iter::zip;
+
+    use super::*;
+    use crate::memory::memory::{BusAccess, BusCycle, Memory};
+    use crate::memory_map::memory_map::MemoryMap;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn assert_cpu(cpu: &CPU, cpu_copy: &CPU) {
+        assert_eq!(
+            cpu.cycles, cpu_copy.cycles,
+            "Amount of cycles used should be {}, but found {}",
+            cpu_copy.cycles, cpu.cycles
+        );
+        assert_eq!(
+            cpu.a, cpu_copy.a,
+            "Register a value should be {}, but found {}",
+            cpu_copy.a, cpu.a
+        );
+        assert_eq!(
+            cpu.x, cpu_copy.x,
+            "Register x value should be {}, but found {}",
+            cpu_copy.x, cpu.x
+        );
+        assert_eq!(
+            cpu.y, cpu_copy.y,
+            "Register y value should be {}, but found {}",
+            cpu_copy.y, cpu.y
+        );
+        assert_eq!(
+            cpu.pc, cpu_copy.pc,
+            "Program counter should be {}, but found {}",
+            cpu_copy.pc, cpu.pc
+        );
+        assert_eq!(
+            cpu.sp, cpu_copy.sp,
+            "Stack pointer value should be {}, but found {}",
+            cpu_copy.sp, cpu.sp
+        );
+        assert_eq!(
+            cpu.get_carry(),
+            cpu_copy.get_carry(),
+            "Carry flag should be {}, but found {}!",
+            cpu.get_carry(),
+            cpu_copy.get_carry()
+        );
+        assert_eq!(
+            cpu.get_zero(),
+            cpu_copy.get_zero(),
+            "Zero flag should be {}, but found {}!",
+            cpu.get_zero(),
+            cpu_copy.get_zero()
+        );
+        assert_eq!(
+            cpu.get_interrupt_disable(),
+            cpu_copy.get_interrupt_disable(),
+            "Interrupt disable flag should be {}, but found {}!",
+            cpu.get_interrupt_disable(),
+            cpu_copy.get_interrupt_disable()
+        );
+        assert_eq!(
+            cpu.get_decimal_mode(),
+            cpu_copy.get_decimal_mode(),
+            "Decimal mode flag should be {}, but found {}!",
+            cpu.get_decimal_mode(),
+            cpu_copy.get_decimal_mode()
+        );
+        assert_eq!(
+            cpu.get_break_command(),
+            cpu_copy.get_break_command(),
+            "Break command flag should be {}, but found {}!",
+            cpu.get_break_command(),
+            cpu_copy.get_break_command()
+        );
+        assert_eq!(
+            cpu.get_overflow(),
+            cpu_copy.get_overflow(),
+            "Overflow flag should be {}, but found {}!",
+            cpu.get_overflow(),
+            cpu_copy.get_overflow()
+        );
+        assert_eq!(
+            cpu.get_negative(),
+            cpu_copy.get_negative(),
+            "Negative flag should be {}, but found {}!",
+            cpu.get_negative(),
+            cpu_copy.get_negative()
+        );
+    }
+
+    macro_rules! test_ld {
+        // reg_type is required for ABS_REG instructions only. For other instructions use Register::None
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr) => {
+            test_ld! {$func_name, $reg_name, $instr_name, $addr_mode, $reg_type, CpuVariant::Nmos6502}
+        };
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr, $variant: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU::new($variant);
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Immediate, 2);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+                pc_increments.insert(AddressingMode::IndirectX, 2);
+                pc_increments.insert(AddressingMode::IndirectY, 2);
+                pc_increments.insert(AddressingMode::ZeroPageIndirect, 2);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Immediate, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 4);
+                cycles_increments.insert(AddressingMode::IndirectX, 6);
+                cycles_increments.insert(AddressingMode::IndirectY, 5);
+                cycles_increments.insert(AddressingMode::ZeroPageIndirect, 5);
+
+                let mut additional_cycles = [0, 0, 0, 0];
+
+                let values = [69u8, 0u8, 0xff, (!10u8 + 1)];
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                // (zp),Y pointers at $FF take their high byte from $00, where the program lives
+                let addresses_zp_pointer = [0x13u8, 0x69, 0xFE, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let y_values = [0x23, 0x00u8, 0x43, 0xBB];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_zp_final_y = [0x36u8, 0x69, 0x42, 0x66];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let addresses_absolute_final_y = [0x0036u16, 0xfff4, 0xAC10, 0x00BA];
+
+                for i in 0..4 {
+                    match $addr_mode {
+                        AddressingMode::Immediate => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, values[i as usize]);
+                        }
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values[i as usize]);
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory
+                                .write(addresses_zp_final_x[i as usize] as u16, values[i as usize]);
+                            memory
+                                .write(addresses_zp_final_y[i as usize] as u16, values[i as usize]);
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values[i as usize]);
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values[i as usize]);
+                            if $reg_type == Register::X {
+                                memory.write(
+                                    addresses_absolute_final_x[i as usize],
+                                    values[i as usize],
+                                );
+                                if addresses_absolute_final_x[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            } else if $reg_type == Register::Y {
+                                memory.write(
+                                    addresses_absolute_final_y[i as usize],
+                                    values[i as usize],
+                                );
+                                if addresses_absolute_final_y[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            }
+                        }
+                        AddressingMode::IndirectX => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory.write(addresses_absolute[i as usize], values[i as usize]);
+                        }
+                        AddressingMode::IndirectY => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values[i as usize]);
+                            if addresses_absolute_final_y[i as usize] & 0xff00
+                                != addresses_absolute[i as usize] & 0xff00
+                            {
+                                additional_cycles[i as usize] += 1;
+                            }
+                        }
+                        AddressingMode::ZeroPageIndirect => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute_final_y[i as usize],
+                            );
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values[i as usize]);
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..4 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.x = x_values[i];
+                    cpu.y = y_values[i];
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.y = y_values[i];
+                    cpu_copy.$reg_name = value;
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode] + additional_cycles[i];
+                    cpu_copy.set_zero(value == 0);
+                    cpu_copy.set_negative((value as i8) < 0);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    // LDA
+    test_ld! {test_lda_immediate, a, Instruction::LDA_IM, &AddressingMode::Immediate, Register::None}
+    test_ld! {test_lda_zero_page, a, Instruction::LDA_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_ld! {test_lda_zero_page_x, a, Instruction::LDA_ZP_X, &AddressingMode::ZeroPageReg, Register::None}
+    test_ld! {test_lda_absolute, a, Instruction::LDA_ABS, &AddressingMode::Absolute, Register::None}
+    test_ld! {test_lda_absolute_x, a, Instruction::LDA_ABS_X, &AddressingMode::AbsoluteReg, Register::X}
+    test_ld! {test_lda_absolute_y, a, Instruction::LDA_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y}
+    test_ld! {test_lda_indirect_x, a, Instruction::LDA_IN_X, &AddressingMode::IndirectX, Register::None}
+    test_ld! {test_lda_indirect_y, a, Instruction::LDA_IN_Y, &AddressingMode::IndirectY, Register::None}
+    test_ld! {test_lda_zero_page_indirect, a, Instruction::LDA_IN_ZP, &AddressingMode::ZeroPageIndirect, Register::None, CpuVariant::Cmos65C02}
+
+    // LDX
+    test_ld! {test_ldx_immediate, x, Instruction::LDX_IM, &AddressingMode::Immediate, Register::None}
+    test_ld! {test_ldx_zero_page, x, Instruction::LDX_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_ld! {test_ldx_zero_page_y, x, Instruction::LDX_ZP_Y, &AddressingMode::ZeroPageReg, Register::None}
+    test_ld! {test_ldx_absolute, x, Instruction::LDX_ABS, &AddressingMode::Absolute, Register::None}
+    test_ld! {test_ldx_absolute_y, x, Instruction::LDX_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y}
+
+    // LDY
+    test_ld! {test_ldy_immediate, y, Instruction::LDY_IM, &AddressingMode::Immediate, Register::None}
+    test_ld! {test_ldy_zero_page, y, Instruction::LDY_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_ld! {test_ldy_zero_page_x, y, Instruction::LDY_ZP_X, &AddressingMode::ZeroPageReg, Register::None}
+    test_ld! {test_ldy_absolute, y, Instruction::LDY_ABS, &AddressingMode::Absolute, Register::None}
+    test_ld! {test_ldy_absolute_x, y, Instruction::LDY_ABS_X, &AddressingMode::AbsoluteReg, Register::X}
+
+    macro_rules! test_st {
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr) => {
+            test_st! {$func_name, $reg_name, $instr_name, $addr_mode, $reg_type, CpuVariant::Nmos6502}
+        };
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr, $variant: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU::new($variant);
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+                pc_increments.insert(AddressingMode::IndirectX, 2);
+                pc_increments.insert(AddressingMode::IndirectY, 2);
+                pc_increments.insert(AddressingMode::ZeroPageIndirect, 2);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 5);
+                cycles_increments.insert(AddressingMode::IndirectX, 6);
+                cycles_increments.insert(AddressingMode::IndirectY, 6);
+                cycles_increments.insert(AddressingMode::ZeroPageIndirect, 5);
+
+                let values = [69u8, 0u8, 0xff, (!10u8 + 1)];
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                // (zp),Y pointers at $FF take their high byte from $00, where the program lives
+                let addresses_zp_pointer = [0x13u8, 0x69, 0xFE, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let y_values = [0x23, 0x00u8, 0x43, 0xBB];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_zp_final_y = [0x36u8, 0x69, 0x42, 0x66];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let addresses_absolute_final_y = [0x0036u16, 0xfff4, 0xAC10, 0x00BA];
+                let mut addresses_final: [u16; 4] = [0, 0, 0, 0];
+
+                for i in 0..4 {
+                    match $addr_mode {
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            addresses_final[i as usize] = addresses_zp[i as usize] as u16;
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            if $reg_type == Register::X {
+                                addresses_final[i as usize] =
+                                    addresses_zp_final_x[i as usize] as u16;
+                            } else if $reg_type == Register::Y {
+                                addresses_final[i as usize] =
+                                    addresses_zp_final_y[i as usize] as u16;
+                            }
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute[i as usize];
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            if $reg_type == Register::X {
+                                addresses_final[i as usize] =
+                                    addresses_absolute_final_x[i as usize];
+                            } else if $reg_type == Register::Y {
+                                addresses_final[i as usize] =
+                                    addresses_absolute_final_y[i as usize];
+                            }
+                        }
+                        AddressingMode::IndirectX => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            addresses_final[i as usize] = addresses_absolute[i as usize];
+                        }
+                        AddressingMode::IndirectY => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            addresses_final[i as usize] = addresses_absolute_final_y[i as usize];
+                        }
+                        AddressingMode::ZeroPageIndirect => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute_final_y[i as usize],
+                            );
+                            addresses_final[i as usize] = addresses_absolute_final_y[i as usize];
+                        }
+                        _ => {
+                            panic!("Unsupported addressing mode {:?}!", $addr_mode);
+                        }
+                    }
+                }
+
+                for i in 0..4 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let address = addresses_final[i];
+                    let value = values[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.x = x_values[i];
+                    cpu.y = y_values[i];
+                    cpu.$reg_name = value;
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let actual_value: u8 = memory.read(address as u16);
+
+                    assert_eq!(actual_value, value);
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode];
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.y = y_values[i];
+                    cpu_copy.$reg_name = value;
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    // STA
+    test_st! {test_sta_zero_page, a, Instruction::STA_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_st! {test_sta_zero_page_x, a, Instruction::STA_ZP_X, &AddressingMode::ZeroPageReg, Register::X}
+    test_st! {test_sta_absolute, a, Instruction::STA_ABS, &AddressingMode::Absolute, Register::None}
+    test_st! {test_sta_absolute_x, a, Instruction::STA_ABS_X, &AddressingMode::AbsoluteReg, Register::X}
+    test_st! {test_sta_absolute_y, a, Instruction::STA_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y}
+    test_st! {test_sta_indirect_x, a, Instruction::STA_IN_X, &AddressingMode::IndirectX, Register::None}
+    test_st! {test_sta_indirect_y, a, Instruction::STA_IN_Y, &AddressingMode::IndirectY, Register::None}
+    test_st! {test_sta_zero_page_indirect, a, Instruction::STA_IN_ZP, &AddressingMode::ZeroPageIndirect, Register::None, CpuVariant::Cmos65C02}
+    // STX
+    test_st! {test_stx_zero_page, x, Instruction::STX_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_st! {test_stx_zero_page_y, x, Instruction::STX_ZP_Y, &AddressingMode::ZeroPageReg, Register::Y}
+    test_st! {test_stx_absolute, x, Instruction::STX_ABS, &AddressingMode::Absolute, Register::None}
+    // STY
+    test_st! {test_sty_zero_page, y, Instruction::STY_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_st! {test_sty_zero_page_x, y, Instruction::STY_ZP_X, &AddressingMode::ZeroPageReg, Register::X}
+    test_st! {test_sty_absolute, y, Instruction::STY_ABS, &AddressingMode::Absolute, Register::None}
+
+    // Transfer
+    macro_rules! test_transfer_reg_reg {
+        ($func_name: ident, $reg_src: ident, $reg_dest: ident, $instr_name: expr, $test_flags_en: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let values = [0u8, 69, (!105u8 + 1)];
+
+                for i in 0..3 {
+                    memory.write_byte(i, $instr_name.into());
+                }
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.$reg_src = value;
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + 2;
+                    cpu_copy.pc = pc + 1;
+                    cpu_copy.$reg_src = value;
+                    cpu_copy.$reg_dest = value;
+                    if $test_flags_en {
+                        cpu_copy.set_zero(value == 0);
+                        cpu_copy.set_negative((value as i8) < 0);
+                    }
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_transfer_reg_reg! {test_transfer_a_x, a, x, Instruction::TAX, true}
+    test_transfer_reg_reg! {test_transfer_a_y, a, y, Instruction::TAY, true}
+    test_transfer_reg_reg! {test_transfer_x_a, x, a, Instruction::TXA, true}
+    test_transfer_reg_reg! {test_transfer_y_a, y, a, Instruction::TYA, true}
+    test_transfer_reg_reg! {test_transfer_s_x, sp, x, Instruction::TSX, true}
+    test_transfer_reg_reg! {test_transfer_x_s, x, sp, Instruction::TXS, false}
+
+    // Stack
+    macro_rules! test_push_stack {
+        ($func_name: ident, $reg_name: ident, $instr_name: expr) => {
+            test_push_stack! {$func_name, $reg_name, $instr_name, CpuVariant::Nmos6502}
+        };
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $variant: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU::new($variant);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                cpu.sp = 0xff;
+
+                let mut cpu_copy = cpu.clone();
+
+                let values = [0u8, 69, (!105u8 + 1)];
+
+                for i in 0..3 {
+                    memory.write_byte(i, $instr_name.into());
+                }
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.$reg_name = value;
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let actual_value: u8 = memory.read(0x0100 + (cpu.sp + 1) as u16);
+
+                    cpu_copy.cycles = cycles + 3;
+                    cpu_copy.pc = pc + 1;
+                    cpu_copy.$reg_name = value;
+                    cpu_copy.sp = 0xff - (i + 1) as u8;
+                    assert_cpu(&cpu, &cpu_copy);
+                    assert_eq!(actual_value, value);
+                }
+            }
+        };
+    }
+
+    macro_rules! test_pull_stack {
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $instr_push_name: expr) => {
+            test_pull_stack! {$func_name, $reg_name, $instr_name, $instr_push_name, CpuVariant::Nmos6502}
+        };
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $instr_push_name: expr, $variant: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU::new($variant);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                cpu.sp = 0xff;
+
+                let values = [0u8, 69, (!105u8 + 1)];
+
+                for i in 0..3 {
+                    memory.write_byte(i, $instr_name.into());
+                    cpu.$reg_name = values[i as usize];
+                    cpu.execute(&mut memory, $instr_push_name.into()).unwrap();
+                }
+                let mut cpu_copy = cpu.clone();
+                let sp_init = cpu_copy.sp;
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values[values.len() - i - 1];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.$reg_name = value;
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + 4;
+                    cpu_copy.pc = pc + 1;
+                    cpu_copy.sp = sp_init + i as u8 + 1;
+                    cpu_copy.$reg_name = value;
+                    if (u8::from(Instruction::PLP) != u8::from($instr_name)) {
+                        cpu_copy.set_zero(value == 0);
+                        cpu_copy.set_negative((value as i8) < 0);
+                    }
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_push_stack! {test_push_accumulator, a, Instruction::PHA}
+    test_pull_stack! {test_pull_accumulator, a, Instruction::PLA, Instruction::PHA}
+    test_push_stack! {test_push_x, x, Instruction::PHX, CpuVariant::Cmos65C02}
+    test_push_stack! {test_push_y, y, Instruction::PHY, CpuVariant::Cmos65C02}
+    test_pull_stack! {test_pull_x, x, Instruction::PLX, Instruction::PHX, CpuVariant::Cmos65C02}
+    test_pull_stack! {test_pull_y, y, Instruction::PLY, Instruction::PHY, CpuVariant::Cmos65C02}
+
+    macro_rules! test_logic {
+        ($func_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr, $op_func: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Immediate, 2);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+                pc_increments.insert(AddressingMode::IndirectX, 2);
+                pc_increments.insert(AddressingMode::IndirectY, 2);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Immediate, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 4);
+                cycles_increments.insert(AddressingMode::IndirectX, 6);
+                cycles_increments.insert(AddressingMode::IndirectY, 5);
+
+                let mut additional_cycles = [0, 0, 0, 0];
+
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                // (zp),Y pointers at $FF take their high byte from $00, where the program lives
+                let addresses_zp_pointer = [0x13u8, 0x69, 0xFE, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let y_values = [0x23, 0x00u8, 0x43, 0xBB];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let addresses_absolute_final_y = [0x0036u16, 0xfff4, 0xAC10, 0x00BA];
+
+                let values1 = [0b0000_0000u8, 0b1111_1111, 0b0000_1111, 0b1010_1010];
+                let values2 = [0b1111_1111u8, 0b0101_0101, 0b0011_0011, 0b0101_1011];
+                let values_res: Vec<u8> = zip(values1, values2)
+                    .map(|pair| $op_func(pair.0, pair.1))
+                    .collect();
+
+                for i in 0..4 {
+                    match $addr_mode {
+                        AddressingMode::Immediate => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                values2[i as usize],
+                            );
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values2[i as usize]);
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            if $reg_type == Register::X {
+                                memory.write(
+                                    addresses_absolute_final_x[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_x[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            } else if $reg_type == Register::Y {
+                                memory.write(
+                                    addresses_absolute_final_y[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_y[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            }
+                        }
+                        AddressingMode::IndirectX => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory.write(addresses_absolute[i as usize], values2[i as usize])
+                        }
+                        AddressingMode::IndirectY => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values2[i as usize]);
+                            if addresses_absolute_final_y[i as usize] & 0xff00
+                                != addresses_absolute[i as usize] & 0xff00
+                            {
+                                additional_cycles[i as usize] += 1;
+                            }
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..4 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values_res[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.a = values1[i];
+                    cpu.x = x_values[i];
+                    cpu.y = y_values[i];
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode] + additional_cycles[i];
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.a = value;
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.y = y_values[i];
+                    cpu_copy.set_zero(value == 0);
+                    cpu_copy.set_negative((value as i8) < 0);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    // AND
+    test_logic! {test_and_immediate, Instruction::AND_IM, &AddressingMode::Immediate, Register::None, |n1, n2| n1 & n2}
+    test_logic! {test_and_zero_page, Instruction::AND_ZP, &AddressingMode::ZeroPage, Register::None, |n1, n2| n1 & n2}
+    test_logic! {test_and_zero_page_x, Instruction::AND_ZP_X, &AddressingMode::ZeroPageReg, Register::X, |n1, n2| n1 & n2}
+    test_logic! {test_and_absolute, Instruction::AND_ABS, &AddressingMode::Absolute, Register::None, |n1, n2| n1 & n2}
+    test_logic! {test_and_absolute_x, Instruction::AND_ABS_X, &AddressingMode::AbsoluteReg, Register::X, |n1, n2| n1 & n2}
+    test_logic! {test_and_absolute_y, Instruction::AND_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y, |n1, n2| n1 & n2}
+    test_logic! {test_and_indirect_x, Instruction::AND_IN_X, &AddressingMode::IndirectX, Register::X, |n1, n2| n1 & n2}
+    test_logic! {test_and_indirect_y, Instruction::AND_IN_Y, &AddressingMode::IndirectY, Register::Y, |n1, n2| n1 & n2}
+    // EOR
+    test_logic! {test_eor_immediate, Instruction::EOR_IM, &AddressingMode::Immediate, Register::None, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_zero_page, Instruction::EOR_ZP, &AddressingMode::ZeroPage, Register::None, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_zero_page_x, Instruction::EOR_ZP_X, &AddressingMode::ZeroPageReg, Register::X, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_absolute, Instruction::EOR_ABS, &AddressingMode::Absolute, Register::None, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_absolute_x, Instruction::EOR_ABS_X, &AddressingMode::AbsoluteReg, Register::X, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_absolute_y, Instruction::EOR_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_indirect_x, Instruction::EOR_IN_X, &AddressingMode::IndirectX, Register::X, |n1, n2| n1 ^ n2}
+    test_logic! {test_eor_indirect_y, Instruction::EOR_IN_Y, &AddressingMode::IndirectY, Register::Y, |n1, n2| n1 ^ n2}
+    // ORA
+    test_logic! {test_ora_immediate, Instruction::ORA_IM, &AddressingMode::Immediate, Register::None, |n1, n2| n1 | n2}
+    test_logic! {test_ora_zero_page, Instruction::ORA_ZP, &AddressingMode::ZeroPage, Register::None, |n1, n2| n1 | n2}
+    test_logic! {test_ora_zero_page_x, Instruction::ORA_ZP_X, &AddressingMode::ZeroPageReg, Register::X, |n1, n2| n1 | n2}
+    test_logic! {test_ora_absolute, Instruction::ORA_ABS, &AddressingMode::Absolute, Register::None, |n1, n2| n1 | n2}
+    test_logic! {test_ora_absolute_x, Instruction::ORA_ABS_X, &AddressingMode::AbsoluteReg, Register::X, |n1, n2| n1 | n2}
+    test_logic! {test_ora_absolute_y, Instruction::ORA_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y, |n1, n2| n1 | n2}
+    test_logic! {test_ora_indirect_x, Instruction::ORA_IN_X, &AddressingMode::IndirectX, Register::X, |n1, n2| n1 | n2}
+    test_logic! {test_ora_indirect_y, Instruction::ORA_IN_Y, &AddressingMode::IndirectY, Register::Y, |n1, n2| n1 | n2}
+
+    macro_rules! test_compare {
+        ($func_name: ident, $reg_name: ident, $instr_name: expr, $addr_mode: expr, $reg_type: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Immediate, 2);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+                pc_increments.insert(AddressingMode::IndirectX, 2);
+                pc_increments.insert(AddressingMode::IndirectY, 2);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Immediate, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 4);
+                cycles_increments.insert(AddressingMode::IndirectX, 6);
+                cycles_increments.insert(AddressingMode::IndirectY, 5);
+
+                let mut additional_cycles = [0, 0, 0, 0];
+
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                // (zp),Y pointers at $FF take their high byte from $00, where the program lives
+                let addresses_zp_pointer = [0x13u8, 0x69, 0xFE, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let y_values = [0x23, 0x00u8, 0x43, 0xBB];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let addresses_absolute_final_y = [0x0036u16, 0xfff4, 0xAC10, 0x00BA];
+
+                let values1 = [123u8, 53, 234, 10];
+                let values2 = [123u8, 69, 13, 255];
+                let values_res = [0u8, 246, 221, 11];
+
+                for i in 0..3 {
+                    match $addr_mode {
+                        AddressingMode::Immediate => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                values2[i as usize],
+                            );
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values2[i as usize]);
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            if $reg_type == Register::X {
+                                memory.write(
+                                    addresses_absolute_final_x[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_x[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            } else if $reg_type == Register::Y {
+                                memory.write(
+                                    addresses_absolute_final_y[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_y[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            }
+                        }
+                        AddressingMode::IndirectX => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory.write(addresses_absolute[i as usize], values2[i as usize])
+                        }
+                        AddressingMode::IndirectY => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values2[i as usize]);
+                            if addresses_absolute_final_y[i as usize] & 0xff00
+                                != addresses_absolute[i as usize] & 0xff00
+                            {
+                                additional_cycles[i as usize] += 1;
+                            }
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = values_res[i];
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.x = x_values[i];
+                    cpu.y = y_values[i];
+                    cpu.$reg_name = values1[i];
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode] + additional_cycles[i];
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.y = y_values[i];
+                    cpu_copy.$reg_name = values1[i];
+                    cpu_copy.set_carry(values1[i] >= values2[i]);
+                    cpu_copy.set_zero(value == 0);
+                    cpu_copy.set_negative((value as i8) < 0);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+    // CMP
+    test_compare! {test_cmp_immediate, a, Instruction::CMP_IM, &AddressingMode::Immediate, Register::None}
+    test_compare! {test_cmp_zero_page, a, Instruction::CMP_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_compare! {test_cmp_zero_page_x, a, Instruction::CMP_ZP_X, &AddressingMode::ZeroPageReg, Register::X}
+    test_compare! {test_cmp_absolute, a, Instruction::CMP_ABS, &AddressingMode::Absolute, Register::None}
+    test_compare! {test_cmp_absolute_x, a, Instruction::CMP_ABS_X, &AddressingMode::AbsoluteReg, Register::X}
+    test_compare! {test_cmp_absolute_y, a, Instruction::CMP_ABS_Y, &AddressingMode::AbsoluteReg, Register::Y}
+    test_compare! {test_cmp_indirect_x, a, Instruction::CMP_IN_X, &AddressingMode::IndirectX, Register::X}
+    test_compare! {test_cmp_indirect_y, a, Instruction::CMP_IN_Y, &AddressingMode::IndirectY, Register::Y}
+    // CPX
+    test_compare! {test_cpx_immediate, x, Instruction::CPX_IM, &AddressingMode::Immediate, Register::None}
+    test_compare! {test_cpx_zero_page, x, Instruction::CPX_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_compare! {test_cpx_absolute, x, Instruction::CPX_ABS, &AddressingMode::Absolute, Register::None}
+    // CPY
+    test_compare! {test_cpy_immediate, y, Instruction::CPY_IM, &AddressingMode::Immediate, Register::None}
+    test_compare! {test_cpy_zero_page, y, Instruction::CPY_ZP, &AddressingMode::ZeroPage, Register::None}
+    test_compare! {test_cpy_absolute, y, Instruction::CPY_ABS, &AddressingMode::Absolute, Register::None}
+
+    enum ArithmeticOperation {
+        Addition,
+        Substraction,
+    }
+
+    macro_rules! test_arithmetic {
+        ($func_name: ident, $instr_name: expr, $op_type: expr, $addr_mode: expr, $reg_type: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Immediate, 2);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+                pc_increments.insert(AddressingMode::IndirectX, 2);
+                pc_increments.insert(AddressingMode::IndirectY, 2);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Immediate, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 4);
+                cycles_increments.insert(AddressingMode::IndirectX, 6);
+                cycles_increments.insert(AddressingMode::IndirectY, 5);
+
+                let mut additional_cycles = [0, 0, 0, 0];
+
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                // (zp),Y pointers at $FF take their high byte from $00, where the program lives
+                let addresses_zp_pointer = [0x13u8, 0x69, 0xFE, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let y_values = [0x23, 0x00u8, 0x43, 0xBB];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let addresses_absolute_final_y = [0x0036u16, 0xfff4, 0xAC10, 0x00BA];
+
+                let overflow_flags = [true, false, true, false, true];
+                let values1 = [123u8, 152, 234, 10, 128];
+                let values2 = [123u8, 167, 13, 255, 127];
+                // (result, carry, overflow)
+                let addition: [(u8, bool, bool); 5] = [
+                    (247u8, false, true),
+                    (63, true, true),
+                    (248, false, false),
+                    (9, true, false),
+                    (0, true, false),
+                ];
+                let substraction: [(u8, bool, bool); 5] = [
+                    (0u8, true, false),
+                    (240, false, false),
+                    (221, true, false),
+                    (10, false, false),
+                    (1, true, true),
+                ];
+
+                let operation = match $op_type {
+                    ArithmeticOperation::Addition => addition,
+                    ArithmeticOperation::Substraction => substraction,
+                };
+
+                for i in 0..3 {
+                    match $addr_mode {
+                        AddressingMode::Immediate => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values2[i as usize]);
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                values2[i as usize],
+                            );
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values2[i as usize]);
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            if $reg_type == Register::X {
+                                memory.write(
+                                    addresses_absolute_final_x[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_x[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            } else if $reg_type == Register::Y {
+                                memory.write(
+                                    addresses_absolute_final_y[i as usize],
+                                    values2[i as usize],
+                                );
+                                if addresses_absolute_final_y[i as usize] & 0xff00
+                                    != addresses_absolute[i as usize] & 0xff00
+                                {
+                                    additional_cycles[i as usize] += 1;
+                                }
+                            }
+                        }
+                        AddressingMode::IndirectX => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(
+                                addresses_zp_final_x[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory.write(addresses_absolute[i as usize], values2[i as usize])
+                        }
+                        AddressingMode::IndirectY => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp_pointer[i as usize]);
+                            memory.write(
+                                addresses_zp_pointer[i as usize] as u16,
+                                addresses_absolute[i as usize],
+                            );
+                            memory
+                                .write(addresses_absolute_final_y[i as usize], values2[i as usize]);
+                            if addresses_absolute_final_y[i as usize] & 0xff00
+                                != addresses_absolute[i as usize] & 0xff00
+                            {
+                                additional_cycles[i as usize] += 1;
+                            }
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let value = operation[i].0;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.x = x_values[i];
+                    cpu.y = y_values[i];
+                    cpu.a = values1[i];
+
+                    cpu.set_carry(overflow_flags[i]);
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode] + additional_cycles[i];
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.y = y_values[i];
+                    cpu_copy.a = value;
+                    cpu_copy.set_carry(operation[i].1);
+                    cpu_copy.set_zero(value == 0);
+                    cpu_copy.set_overflow(operation[i].2);
+                    cpu_copy.set_negative((value as i8) < 0);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+    // ADC
+    test_arithmetic! {test_adc_immediate, Instruction::ADC_IM, ArithmeticOperation::Addition, &AddressingMode::Immediate, Register::None}
+    test_arithmetic! {test_adc_zero_page, Instruction::ADC_ZP, ArithmeticOperation::Addition, &AddressingMode::ZeroPage, Register::None}
+    test_arithmetic! {test_adc_zero_page_x, Instruction::ADC_ZP_X, ArithmeticOperation::Addition, &AddressingMode::ZeroPageReg, Register::X}
+    test_arithmetic! {test_adc_absolute, Instruction::ADC_ABS, ArithmeticOperation::Addition, &AddressingMode::Absolute, Register::None}
+    test_arithmetic! {test_adc_absolute_x, Instruction::ADC_ABS_X, ArithmeticOperation::Addition, &AddressingMode::AbsoluteReg, Register::X}
+    test_arithmetic! {test_adc_absolute_y, Instruction::ADC_ABS_Y, ArithmeticOperation::Addition, &AddressingMode::AbsoluteReg, Register::Y}
+    test_arithmetic! {test_adc_indirect_x, Instruction::ADC_IN_X, ArithmeticOperation::Addition, &AddressingMode::IndirectX, Register::X}
+    test_arithmetic! {test_adc_indirect_y, Instruction::ADC_IN_Y, ArithmeticOperation::Addition, &AddressingMode::IndirectY, Register::Y}
+
+    // SBC
+    test_arithmetic! {test_sbc_immediate, Instruction::SBC_IM, ArithmeticOperation::Substraction, &AddressingMode::Immediate, Register::None}
+    test_arithmetic! {test_sbc_zero_page, Instruction::SBC_ZP, ArithmeticOperation::Substraction, &AddressingMode::ZeroPage, Register::None}
+    test_arithmetic! {test_sbc_zero_page_x, Instruction::SBC_ZP_X, ArithmeticOperation::Substraction, &AddressingMode::ZeroPageReg, Register::X}
+    test_arithmetic! {test_sbc_absolute, Instruction::SBC_ABS, ArithmeticOperation::Substraction, &AddressingMode::Absolute, Register::None}
+    test_arithmetic! {test_sbc_absolute_x, Instruction::SBC_ABS_X, ArithmeticOperation::Substraction, &AddressingMode::AbsoluteReg, Register::X}
+    test_arithmetic! {test_sbc_absolute_y, Instruction::SBC_ABS_Y, ArithmeticOperation::Substraction, &AddressingMode::AbsoluteReg, Register::Y}
+    test_arithmetic! {test_sbc_indirect_x, Instruction::SBC_IN_X, ArithmeticOperation::Substraction, &AddressingMode::IndirectX, Register::X}
+    test_arithmetic! {test_sbc_indirect_y, Instruction::SBC_IN_Y, ArithmeticOperation::Substraction, &AddressingMode::IndirectY, Register::Y}
+
+    #[test]
+    fn test_arithmetic_exhaustive() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        for instruction in [
+            Instruction::ADC_IM,
+            Instruction::SBC_IM,
+            Instruction::CMP_IM,
+        ] {
+            memory.write(0x0000, u8::from(instruction));
+            let operands = (0..=255u8).flat_map(|a| (0..=255u8).map(move |value| (a, value)));
+            for ((a, value), carry_in) in operands.flat_map(|pair| [(pair, false), (pair, true)]) {
+                memory.write(0x0001, value);
+                cpu.pc = 0;
+                cpu.a = a;
+                cpu.set_carry(carry_in);
+                cpu.set_overflow(false);
+
+                cpu.step(&mut memory).unwrap();
+
+                // Reference: unsigned result for C, signed result for V
+                let (unsigned, signed) = match instruction {
+                    Instruction::ADC_IM => (
+                        a as i16 + value as i16 + carry_in as i16,
+                        a as i8 as i16 + value as i8 as i16 + carry_in as i16,
+                    ),
+                    Instruction::SBC_IM => (
+                        a as i16 - value as i16 - !carry_in as i16,
+                        a as i8 as i16 - value as i8 as i16 - !carry_in as i16,
+                    ),
+                    _ => (a as i16 - value as i16, 0),
+                };
+                let result = unsigned as u8;
+                let carry = match instruction {
+                    Instruction::ADC_IM => unsigned > 0xff,
+                    _ => unsigned >= 0,
+                };
+                let case = format!(
+                    "{:?} a={:#04x} value={:#04x} carry={}",
+                    instruction, a, value, carry_in
+                );
+
+                if instruction == Instruction::CMP_IM {
+                    assert_eq!(cpu.a, a, "{}", case);
+                } else {
+                    assert_eq!(cpu.a, result, "{}", case);
+                }
+                assert_eq!(cpu.get_carry(), carry, "{}", case);
+                assert_eq!(
+                    cpu.get_overflow(),
+                    !(-128..=127).contains(&signed),
+                    "{}",
+                    case
+                );
+                assert_eq!(cpu.get_negative(), result & 0b1000_0000 != 0, "{}", case);
+                assert_eq!(cpu.get_zero(), result == 0, "{}", case);
+            }
+        }
+    }
+
+    macro_rules! test_arithmetic_decimal {
+        ($func_name: ident, $instr_name: expr, $cases: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                cpu.set_decimal_mode(true);
+
+                // (a, operand, carry in) => (a, carry out, zero, negative, overflow)
+                let cases: [((u8, u8, bool), (u8, bool, bool, bool, bool)); 6] = $cases;
+
+                for i in 0..cases.len() {
+                    memory.write(2 * i as u16, u8::from($instr_name));
+                    memory.write(2 * i as u16 + 1, cases[i].0 .1);
+                }
+
+                let mut cpu_copy = cpu.clone();
+
+                for i in 0..cases.len() {
+                    let ((a, _, carry), (res, carry_res, zero, negative, overflow)) = cases[i];
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.a = a;
+                    cpu.set_carry(carry);
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.cycles = cycles + 2;
+                    cpu_copy.pc = pc + 2;
+                    cpu_copy.a = res;
+                    cpu_copy.set_carry(carry_res);
+                    cpu_copy.set_zero(zero);
+                    cpu_copy.set_negative(negative);
+                    cpu_copy.set_overflow(overflow);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_arithmetic_decimal! {test_adc_decimal, Instruction::ADC_IM, [
+        ((0x09, 0x01, false), (0x10, false, false, false, false)),
+        ((0x12, 0x34, true), (0x47, false, false, false, false)),
+        ((0x58, 0x46, true), (0x05, true, false, true, true)),
+        ((0x99, 0x01, false), (0x00, true, false, true, false)),
+        ((0x50, 0x50, false), (0x00, true, false, true, true)),
+        ((0x00, 0x00, false), (0x00, false, true, false, false)),
+    ]}
+
+    test_arithmetic_decimal! {test_sbc_decimal, Instruction::SBC_IM, [
+        ((0x46, 0x12, true), (0x34, true, false, false, false)),
+        ((0x40, 0x13, true), (0x27, true, false, false, false)),
+        ((0x32, 0x02, false), (0x29, true, false, false, false)),
+        ((0x00, 0x01, true), (0x99, false, false, true, false)),
+        ((0x21, 0x34, true), (0x87, false, false, true, false)),
+        ((0x42, 0x42, true), (0x00, true, true, false, false)),
+    ]}
+
+    #[test]
+    fn test_adc_decimal_disabled() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.set_decimal_mode(true);
+        cpu.set_decimal_enabled(false);
+        cpu.set_carry(false);
+        cpu.a = 0x09;
+
+        memory.write(0, u8::from(Instruction::ADC_IM));
+        memory.write(1, 0x01u8);
+        let instruction = cpu.fetch_instruction(&mut memory);
+
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        assert_eq!(cpu.a, 0x0a);
+        assert_eq!(cpu.get_decimal_mode(), true);
+    }
+
+    macro_rules! test_increments_decrements {
+        ($func_name: ident, $instr_name: expr, $op_func: expr, $reg_type: expr, $addr_mode: expr) => {
+            test_increments_decrements! {$func_name, $instr_name, $op_func, $reg_type, $addr_mode, CpuVariant::Nmos6502}
+        };
+        ($func_name: ident, $instr_name: expr, $op_func: expr, $reg_type: expr, $addr_mode: expr, $variant: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU::new($variant);
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Implied, 1);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Implied, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 5);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 6);
+                cycles_increments.insert(AddressingMode::Absolute, 6);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 7);
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let values = [69, 0, 234, 1];
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let mut addresses_final = [0, 0, 0, 0];
+
+                let values_res = values.map(|value| $op_func(value));
+
+                for i in 0..4 {
+                    match $addr_mode {
+                        AddressingMode::Implied => memory.write(i, u8::from($instr_name)),
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values[i as usize]);
+                            addresses_final[i as usize] = addresses_zp[i as usize] as u16;
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory
+                                .write(addresses_zp_final_x[i as usize] as u16, values[i as usize]);
+                            addresses_final[i as usize] = addresses_zp_final_x[i as usize] as u16;
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute[i as usize];
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory
+                                .write(addresses_absolute_final_x[i as usize], values[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute_final_x[i as usize];
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..4 {
+                    let value: u8 = values_res[i] as u8;
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    if $reg_type == Register::A {
+                        cpu.a = values[i];
+                    } else if $reg_type == Register::X {
+                        cpu.x = values[i];
+                    } else if $reg_type == Register::Y {
+                        cpu.y = values[i];
+                    } else {
+                        cpu.x = x_values[i];
+                    }
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let res: u8 = memory.read(addresses_final[i as usize]);
+
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode];
+
+                    if $reg_type == Register::None {
+                        cpu_copy.x = x_values[i];
+                    }
+
+                    if $reg_type == Register::A {
+                        cpu_copy.a = value;
+                    } else if $reg_type == Register::X {
+                        cpu_copy.x = value;
+                    } else if $reg_type == Register::Y {
+                        cpu_copy.y = value;
+                    }
+                    cpu_copy.set_zero(value == 0);
+                    cpu_copy.set_negative((value as i8) < 0);
+                    assert_cpu(&cpu, &cpu_copy);
+                    if $reg_type == Register::None {
+                        assert_eq!(res, values_res[i] as u8);
+                    }
+                }
+            }
+        };
+    }
+
+    test_increments_decrements! {test_inc_zero_page, Instruction::INC_ZP, |n| n + 1, Register::None, &AddressingMode::ZeroPage}
+    test_increments_decrements! {test_inc_zero_page_x, Instruction::INC_ZP_X, |n| n + 1, Register::None, &AddressingMode::ZeroPageReg}
+    test_increments_decrements! {test_inc_absolute, Instruction::INC_ABS, |n| n + 1, Register::None, &AddressingMode::Absolute}
+    test_increments_decrements! {test_inc_absolute_x, Instruction::INC_ABS_X, |n| n + 1, Register::None, &AddressingMode::AbsoluteReg}
+    test_increments_decrements! {test_inx, Instruction::INX, |n| n + 1, Register::X, &AddressingMode::Implied}
+    test_increments_decrements! {test_iny, Instruction::INY, |n| n + 1, Register::Y, &AddressingMode::Implied}
+    test_increments_decrements! {test_inc_accumulator, Instruction::INC_A, |n| n + 1, Register::A, &AddressingMode::Implied, CpuVariant::Cmos65C02}
+
+    test_increments_decrements! {test_dec_zero_page, Instruction::DEC_ZP, |n| n as i8 - 1, Register::None, &AddressingMode::ZeroPage}
+    test_increments_decrements! {test_dec_zero_page_x, Instruction::DEC_ZP_X, |n| n as i8 - 1, Register::None, &AddressingMode::ZeroPageReg}
+    test_increments_decrements! {test_dec_absolute, Instruction::DEC_ABS, |n| n as i8 - 1, Register::None, &AddressingMode::Absolute}
+    test_increments_decrements! {test_dec_absolute_x, Instruction::DEC_ABS_X, |n| n as i8 - 1, Register::None, &AddressingMode::AbsoluteReg}
+    test_increments_decrements! {test_dex, Instruction::DEX, |n| n as i8 - 1, Register::X, &AddressingMode::Implied}
+    test_increments_decrements! {test_dey, Instruction::DEY, |n| n as i8 - 1, Register::Y, &AddressingMode::Implied}
+    test_increments_decrements! {test_dec_accumulator, Instruction::DEC_A, |n| n as i8 - 1, Register::A, &AddressingMode::Implied, CpuVariant::Cmos65C02}
+
+    macro_rules! test_shifts {
+        ($func_name: ident, $instr_name: expr, $op_func: expr, $addr_mode: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::Implied, 1);
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Implied, 2);
+                cycles_increments.insert(AddressingMode::ZeroPage, 5);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 6);
+                cycles_increments.insert(AddressingMode::Absolute, 6);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 7);
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let carries = [true, false, true, false];
+                let values = [69, 0, 234, 1];
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let mut addresses_final = [0, 0, 0, 0];
+
+                let values_res: Vec<(u8, bool)> = zip(values, carries).map(|value_and_carry| $op_func(value_and_carry.0, value_and_carry.1)).collect();
+
+                for i in 0..3 {
+                    match $addr_mode {
+                        AddressingMode::Implied => memory.write(i, u8::from($instr_name)),
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory.write(addresses_zp[i as usize] as u16, values[i as usize]);
+                            addresses_final[i as usize] = addresses_zp[i as usize] as u16;
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            memory
+                                .write(addresses_zp_final_x[i as usize] as u16, values[i as usize]);
+                            addresses_final[i as usize] = addresses_zp_final_x[i as usize] as u16;
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory.write(addresses_absolute[i as usize], values[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute[i as usize];
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            memory
+                                .write(addresses_absolute_final_x[i as usize], values[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute_final_x[i as usize];
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..3 {
+                    let value: (u8, bool) = values_res[i];
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    if *$addr_mode == AddressingMode::Implied {
+                        cpu.a = values[i];
+                        cpu_copy.a = value.0;
+                    }
+
+                    if *$addr_mode == AddressingMode::ZeroPageReg || 
+                       *$addr_mode == AddressingMode::AbsoluteReg {
+                        cpu.x = x_values[i];
+                        cpu_copy.x = x_values[i];
+                    }
+
+                    cpu.set_carry(carries[i]);
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode];
+                    cpu_copy.set_zero(value.0 == 0);
+                    cpu_copy.set_negative((value.0 as i8) < 0);
+                    cpu_copy.set_carry(value.1);
+
+                    let res: u8 = memory.read(addresses_final[i as usize]);
+                    assert_cpu(&cpu, &cpu_copy);
+                    if *$addr_mode != AddressingMode::Implied {
+                        assert_eq!(res, value.0);
+                    }
+                }
+            }
+        }
+    }
+
+    test_shifts! {test_asl_accumulator, Instruction::ASL_A, asl_func, &AddressingMode::Implied}
+    test_shifts! {test_asl_zero_page, Instruction::ASL_ZP, asl_func, &AddressingMode::ZeroPage}
+    test_shifts! {test_asl_zero_page_x, Instruction::ASL_ZP_X, asl_func, &AddressingMode::ZeroPageReg}
+    test_shifts! {test_asl_absolute, Instruction::ASL_ABS, asl_func, &AddressingMode::Absolute}
+    test_shifts! {test_asl_absolute_x, Instruction::ASL_ABS_X, asl_func, &AddressingMode::AbsoluteReg}
+
+    test_shifts! {test_lsr_accumulator, Instruction::LSR_A, lsr_func, &AddressingMode::Implied}
+    test_shifts! {test_lsr_zero_page, Instruction::LSR_ZP, lsr_func, &AddressingMode::ZeroPage}
+    test_shifts! {test_lsr_zero_page_x, Instruction::LSR_ZP_X, lsr_func, &AddressingMode::ZeroPageReg}
+    test_shifts! {test_lsr_absolute, Instruction::LSR_ABS, lsr_func, &AddressingMode::Absolute}
+    test_shifts! {test_lsr_absolute_x, Instruction::LSR_ABS_X, lsr_func, &AddressingMode::AbsoluteReg}
+
+    test_shifts! {test_rol_accumulator, Instruction::ROL_A, rol_func, &AddressingMode::Implied}
+    test_shifts! {test_rol_zero_page, Instruction::ROL_ZP, rol_func, &AddressingMode::ZeroPage}
+    test_shifts! {test_rol_zero_page_x, Instruction::ROL_ZP_X, rol_func, &AddressingMode::ZeroPageReg}
+    test_shifts! {test_rol_absolute, Instruction::ROL_ABS, rol_func, &AddressingMode::Absolute}
+    test_shifts! {test_rol_absolute_x, Instruction::ROL_ABS_X, rol_func, &AddressingMode::AbsoluteReg}
+
+    test_shifts! {test_ror_accumulator, Instruction::ROR_A, ror_func, &AddressingMode::Implied}
+    test_shifts! {test_ror_zero_page, Instruction::ROR_ZP, ror_func, &AddressingMode::ZeroPage}
+    test_shifts! {test_ror_zero_page_x, Instruction::ROR_ZP_X, ror_func, &AddressingMode::ZeroPageReg}
+    test_shifts! {test_ror_absolute, Instruction::ROR_ABS, ror_func, &AddressingMode::Absolute}
+    test_shifts! {test_ror_absolute_x, Instruction::ROR_ABS_X, ror_func, &AddressingMode::AbsoluteReg}
+
+    #[test]
+    fn test_rotate_carry() {
+        // (instruction, value, carry in) => (result, carry out, zero, negative)
+        let cases = [
+            (
+                (Instruction::ROL_A, 0x00u8, false),
+                (0x00u8, false, true, false),
+            ),
+            (
+                (Instruction::ROL_A, 0x00, true),
+                (0x01, false, false, false),
+            ),
+            ((Instruction::ROL_A, 0x80, false), (0x00, true, true, false)),
+            ((Instruction::ROL_A, 0xc1, true), (0x83, true, false, true)),
+            (
+                (Instruction::ROR_A, 0x00, false),
+                (0x00, false, true, false),
+            ),
+            ((Instruction::ROR_A, 0x00, true), (0x80, false, false, true)),
+            ((Instruction::ROR_A, 0x01, false), (0x00, true, true, false)),
+            ((Instruction::ROR_A, 0x83, true), (0xc1, true, false, true)),
+        ];
+
+        for ((instruction, value, carry), (res, carry_res, zero, negative)) in cases {
+            for memory_operand in [false, true] {
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                if memory_operand {
+                    let opcode = match instruction {
+                        Instruction::ROL_A => Instruction::ROL_ZP,
+                        _ => Instruction::ROR_ZP,
+                    };
+                    memory.write(0x0000, u8::from(opcode));
+                    memory.write(0x0001, 0x80u8);
+                    memory.write(0x0080, value);
+                } else {
+                    memory.write(0x0000, u8::from(instruction));
+                    cpu.a = value;
+                }
+                cpu.set_carry(carry);
+
+                cpu.step(&mut memory).unwrap();
+
+                let actual: u8 = if memory_operand {
+                    memory.read(0x0080)
+                } else {
+                    cpu.a
+                };
+                let name = format!("{:?} {:02X} {}", instruction, value, memory_operand);
+                assert_eq!(actual, res, "{}", name);
+                assert_eq!(cpu.get_carry(), carry_res, "{}", name);
+                assert_eq!(cpu.get_zero(), zero, "{}", name);
+                assert_eq!(cpu.get_negative(), negative, "{}", name);
+            }
+        }
+    }
+
+    macro_rules! test_jmp {
+        ($func_name: ident, $instr_name: expr, $addr_mode: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                if (*$addr_mode != AddressingMode::Indirect && *$addr_mode != AddressingMode::Absolute) {
+                    panic!("Unsupported addressing mode {:?}", $addr_mode);
+                }
+
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+        
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::Absolute, 3);
+                cycles_increments.insert(AddressingMode::Indirect, 5);
+
+                let addresses = [0x1234, 0x4321, 0xABCD, 0x6942];
+                let addresses_indirect = [0x2233, 0x3454, 0x6547, 0xBE43];
+                let mut addresses_final = [0, 0, 0, 0];
+
+                for i in 0..3 {
+                    memory.write(3*i, u8::from($instr_name));
+                    memory.write(3*i + 1, addresses[i as usize]);
+                    addresses_final[i as usize] = addresses[i as usize];
+                    if *$addr_mode == AddressingMode::Indirect {
+                        memory.write(addresses[i as usize], addresses_indirect[i as usize]);
+                        addresses_final[i as usize] = addresses_indirect[i as usize];
+                    }
+                }
+
+                for i in 0..3 {
+                    cpu.pc = 3*i;
+                    let cycles = cpu.cycles;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    cpu_copy.pc = addresses_final[i as usize];
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode];
+
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_jmp! {test_jmp_absolute, Instruction::JMP_ABS, &AddressingMode::Absolute}
+    test_jmp! {test_jmp_indirect, Instruction::JMP_IN, &AddressingMode::Indirect}
+
+    #[test]
+    fn test_jsr() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        let mut cpu_copy = cpu.clone();
+
+        let addresses = [0x1234, 0x4321, 0xABCD, 0x6942];
+
+        for i in 0..3 {
+            memory.write(i*3, u8::from(Instruction::JSR_ABS));
+            memory.write(i*3 + 1, addresses[i as usize]);
+        }
+
+        for i in 0..3 {
+            cpu.pc = i * 3;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.pc = addresses[i as usize];
+            cpu_copy.cycles = cycles + 6;
+            cpu_copy.sp -= 2;
+
+            assert_cpu(&cpu, &cpu_copy);
+            let stack_addr: u16 = memory.read(0x100u16 + cpu_copy.sp as u16 + 1);
+            assert_eq!(stack_addr, i as u16 * 3 + 3 - 1, "Address on stack should be {}, but found {}", i as u16 * 3 + 3 - 1, stack_addr);
+        }
+    }
+
+    #[test]
+    fn test_rts() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        let mut cpu_copy = cpu.clone();
+
+        let addresses = [0x1234, 0x4321, 0xABCD, 0x6942];
+
+        for i in 0..3 {
+            memory.write(i*3, u8::from(Instruction::JSR_ABS));
+            memory.write(i*3 + 1, addresses[i as usize]);
+            memory.write(addresses[i as usize], u8::from(Instruction::RTS_IM));
+        }
+
+        for i in 0..3 {
+            let cycles = cpu.cycles;
+            let instruction_jsr = cpu.fetch_instruction(&mut memory);
+            
+            cpu.execute(&mut memory, instruction_jsr).unwrap();
+            let instruction_rts = cpu.fetch_instruction(&mut memory);
+            cpu.execute(&mut memory, instruction_rts).unwrap();
+
+            cpu_copy.pc = 3 * i + 3;
+            cpu_copy.cycles = cycles + 12;
+
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_stack_layout() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+
+        // JSR leaves the return address minus one, high byte pulled last
+        memory.write(0x1234, u8::from(Instruction::JSR_ABS));
+        memory.write(0x1235, 0x2000u16);
+        memory.write(0x2000, u8::from(Instruction::PLA));
+        memory.write(0x2001, u8::from(Instruction::TAX));
+        memory.write(0x2002, u8::from(Instruction::PLA));
+        cpu.pc = 0x1234;
+        cpu.run_for_cycles(&mut memory, 6 + 4 + 2 + 4).unwrap();
+
+        assert_eq!(cpu.x, 0x36);
+        assert_eq!(cpu.a, 0x12);
+        assert_eq!(cpu.sp, 0xff);
+
+        // RTS returns to an address pushed by PHA, plus one
+        memory.write(0x2003, u8::from(Instruction::PHA));
+        memory.write(0x2004, u8::from(Instruction::TXA));
+        memory.write(0x2005, u8::from(Instruction::PHA));
+        memory.write(0x2006, u8::from(Instruction::RTS_IM));
+        cpu.run_for_cycles(&mut memory, 3 + 2 + 3 + 6).unwrap();
+
+        assert_eq!(cpu.pc, 0x1237);
+        assert_eq!(cpu.sp, 0xff);
+    }
+
+    #[test]
+    fn test_stack_wrap() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0x00;
+        cpu.a = 0x42;
+        memory.write(0x0000, u8::from(Instruction::PHA));
+        memory.write(0x0001, u8::from(Instruction::JSR_ABS));
+        memory.write(0x0002, 0x3000u16);
+        memory.write(0x3000, u8::from(Instruction::RTS_IM));
+        memory.write(0x0004, u8::from(Instruction::PLA));
+
+        cpu.run_for_cycles(&mut memory, 3 + 6).unwrap();
+
+        assert_eq!(cpu.sp, 0xfd);
+        let stack: [u8; 3] = [memory.ram[0x0100], memory.ram[0x01ff], memory.ram[0x01fe]];
+        assert_eq!(stack, [0x42, 0x00, 0x03]);
+
+        cpu.a = 0;
+        cpu.run_for_cycles(&mut memory, 6 + 4).unwrap();
+
+        assert_eq!(cpu.pc, 0x0005);
+        assert_eq!(cpu.sp, 0x00);
+        assert_eq!(cpu.a, 0x42);
+    }
+
+    #[test]
+    fn test_zero_page_pointer_wrap() {
+        let mut cpu = CPU {
+            pc: 0x0200,
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        // the pointer at $FF takes its high byte from $00, not $0100
+        memory.write(0x00ff, 0x34u8);
+        memory.write(0x0000, 0x12u8);
+        memory.write(0x0100, 0x56u8);
+        memory.write(0x1236, 0x99u8);
+        memory.write(0x0200, u8::from(Instruction::LDA_IN_Y));
+        memory.write(0x0201, 0xffu8);
+        memory.write(0x0202, u8::from(Instruction::ISC_IN_Y));
+        memory.write(0x0203, 0xffu8);
+        cpu.y = 0x02;
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        cpu.execute(&mut memory, instruction).unwrap();
+        assert_eq!(cpu.a, 0x99);
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        cpu.execute(&mut memory, instruction).unwrap();
+        let value: u8 = memory.read(0x1236);
+        assert_eq!(value, 0x9a);
+        assert_eq!(cpu.pc, 0x0204);
+    }
+
+    #[test]
+    fn test_variant_shift_absolute_x_cycles() {
+        let instructions = [
+            Instruction::ASL_ABS_X,
+            Instruction::LSR_ABS_X,
+            Instruction::ROL_ABS_X,
+            Instruction::ROR_ABS_X,
+        ];
+        // (variant, x, cycles), X = $FF crosses a page from $1280
+        let cases = [
+            (CpuVariant::Nmos6502, 0x01u8, 7u64),
+            (CpuVariant::Nmos6502, 0xff, 7),
+            (CpuVariant::Cmos65C02, 0x01, 6),
+            (CpuVariant::Cmos65C02, 0xff, 7),
+        ];
+
+        for cycle_accurate in [false, true] {
+            for instruction in instructions {
+                for (variant, x, cycles) in cases {
+                    let mut cpu = CPU::new(variant);
+                    let mut memory = Memory {
+                        ..Default::default()
+                    };
+                    memory.write(0x0200, u8::from(instruction));
+                    memory.write(0x0201, 0x1280u16);
+                    cpu.pc = 0x0200;
+                    cpu.x = x;
+                    cpu.set_cycle_accurate(cycle_accurate);
+
+                    let info = cpu.step(&mut memory).unwrap();
+
+                    assert_eq!(
+                        info.cycles, cycles,
+                        "{:?} {:?} x={:02X}",
+                        variant, instruction, x
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_inc_dec_wrap() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0000, u8::from(Instruction::INC_ZP));
+        memory.write(0x0001, 0x10u8);
+        memory.write(0x0002, u8::from(Instruction::DEC_ZP));
+        memory.write(0x0003, 0x11u8);
+        memory.write(0x0010, 0xffu8);
+        memory.write(0x0011, 0x00u8);
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        cpu.execute(&mut memory, instruction).unwrap();
+        let value: u8 = memory.read(0x0010);
+        assert_eq!(value, 0x00);
+        assert!(cpu.get_zero());
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        cpu.execute(&mut memory, instruction).unwrap();
+        let value: u8 = memory.read(0x0011);
+        assert_eq!(value, 0xff);
+        assert!(cpu.get_negative());
+        assert_eq!(cpu.cycles, 10);
+    }
+
+    // Steps once in cycle accurate mode and returns the bus activity
+    fn step_bus_cycles(cpu: &mut CPU, memory: &mut Memory) -> (Vec<BusCycle>, u64) {
+        let bus_cycles = Rc::new(RefCell::new(Vec::new()));
+        let sink = bus_cycles.clone();
+        cpu.set_cycle_accurate(true);
+        cpu.set_bus_observer(Some(BusObserver::new(move |cycle| {
+            sink.borrow_mut().push(cycle)
+        })));
+
+        let info = cpu.step(memory).unwrap();
+        cpu.set_bus_observer(None);
+
+        let bus_cycles = bus_cycles.borrow().clone();
+        return (bus_cycles, info.cycles);
+    }
+
+    #[test]
+    fn test_cycle_accurate_access_count() {
+        let variants = [CpuVariant::Nmos6502, CpuVariant::Cmos65C02];
+        // (x, y, status) picked to take branches both ways and to index
+        // across pages or not
+        let setups = [
+            (0x00u8, 0x00u8, 0x00u8),
+            (0xff, 0xff, 0xc3),
+            (0x80, 0x01, 0x00),
+            (0x00, 0x00, 0x08),
+        ];
+
+        for variant in variants {
+            for opcode in 0..=0xffu8 {
+                let instruction = Instruction::decode(opcode, variant);
+                if matches!(instruction, Instruction::INVALID | Instruction::JAM) {
+                    continue;
+                }
+
+                for (seed, (x, y, status)) in setups.iter().enumerate() {
+                    let mut cpu = CPU::new(variant);
+                    let mut memory = Memory {
+                        ..Default::default()
+                    };
+                    memory.fill_random(opcode as u64 * 7 + seed as u64);
+                    memory.write(0x02fe, opcode);
+                    cpu.pc = 0x02fe;
+                    cpu.sp = 0xfd;
+                    cpu.x = *x;
+                    cpu.y = *y;
+                    cpu.set_status(*status);
+
+                    let (bus_cycles, cycles) = step_bus_cycles(&mut cpu, &mut memory);
+
+                    assert_eq!(
+                        bus_cycles.len() as u64,
+                        cycles,
+                        "{:?} opcode ${:02X} ({:?}) x={:02X} y={:02X} p={:02X}: {:?}",
+                        variant,
+                        opcode,
+                        instruction,
+                        x,
+                        y,
+                        status,
+                        bus_cycles
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cycle_accurate_bus_cycles() {
+        let read = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Read,
+        };
+        let write = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Write,
+        };
+
+        // (variant, pc, program, x, memory contents, expected bus cycles)
+        let cases = [
+            // LDA $12F0,X reads the unfixed address $1210 first
+            (
+                CpuVariant::Nmos6502,
+                0x0200u16,
+                vec![0xbdu8, 0xf0, 0x12],
+                0x20u8,
+                vec![(0x1310u16, 0x42u8)],
+                vec![
+                    read(0x0200, 0xbd),
+                    read(0x0201, 0xf0),
+                    read(0x0202, 0x12),
+                    read(0x1210, 0x00),
+                    read(0x1310, 0x42),
+                ],
+            ),
+            // The 65C02 reads the last operand byte again instead
+            (
+                CpuVariant::Cmos65C02,
+                0x0200,
+                vec![0xbd, 0xf0, 0x12],
+                0x20,
+                vec![(0x1310, 0x42)],
+                vec![
+                    read(0x0200, 0xbd),
+                    read(0x0201, 0xf0),
+                    read(0x0202, 0x12),
+                    read(0x0202, 0x12),
+                    read(0x1310, 0x42),
+                ],
+            ),
+            // Indexed stores always spend the fix-up cycle
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0x9d, 0x00, 0x12],
+                0x01,
+                vec![],
+                vec![
+                    read(0x0200, 0x9d),
+                    read(0x0201, 0x00),
+                    read(0x0202, 0x12),
+                    read(0x1201, 0x00),
+                    write(0x1201, 0x00),
+                ],
+            ),
+            // INC $10 writes the old value back before the result
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0xe6, 0x10],
+                0x00,
+                vec![(0x0010, 0x7f)],
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                ],
+            ),
+            // ... while the 65C02 reads it twice
+            (
+                CpuVariant::Cmos65C02,
+                0x0200,
+                vec![0xe6, 0x10],
+                0x00,
+                vec![(0x0010, 0x7f)],
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                ],
+            ),
+            // ASL $1200,X on the 65C02 skips the fix-up read when X stays
+            // in the page
+            (
+                CpuVariant::Cmos65C02,
+                0x0200,
+                vec![0x1e, 0x00, 0x12],
+                0x01,
+                vec![(0x1201, 0x41)],
+                vec![
+                    read(0x0200, 0x1e),
+                    read(0x0201, 0x00),
+                    read(0x0202, 0x12),
+                    read(0x1201, 0x41),
+                    read(0x1201, 0x41),
+                    write(0x1201, 0x82),
+                ],
+            ),
+            // ... and reads the last operand byte again when it crosses
+            (
+                CpuVariant::Cmos65C02,
+                0x0200,
+                vec![0x1e, 0xf0, 0x12],
+                0x20,
+                vec![(0x1310, 0x41)],
+                vec![
+                    read(0x0200, 0x1e),
+                    read(0x0201, 0xf0),
+                    read(0x0202, 0x12),
+                    read(0x0202, 0x12),
+                    read(0x1310, 0x41),
+                    read(0x1310, 0x41),
+                    write(0x1310, 0x82),
+                ],
+            ),
+            // LDA ($10,X) reads the pointer location before adding X
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0xa1, 0x10],
+                0x04,
+                vec![(0x0014, 0x00), (0x0015, 0x30), (0x3000, 0x55)],
+                vec![
+                    read(0x0200, 0xa1),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x00),
+                    read(0x0014, 0x00),
+                    read(0x0015, 0x30),
+                    read(0x3000, 0x55),
+                ],
+            ),
+            // JSR reads the high byte of the target after pushing
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0x20, 0x00, 0x30],
+                0x00,
+                vec![],
+                vec![
+                    read(0x0200, 0x20),
+                    read(0x0201, 0x00),
+                    read(0x01fd, 0x00),
+                    write(0x01fd, 0x02),
+                    write(0x01fc, 0x02),
+                    read(0x0202, 0x30),
+                ],
+            ),
+            // RTS reads the pulled address before incrementing it
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0x60],
+                0x00,
+                vec![(0x01fe, 0x02), (0x01ff, 0x30)],
+                vec![
+                    read(0x0200, 0x60),
+                    read(0x0201, 0x00),
+                    read(0x01fd, 0x00),
+                    read(0x01fe, 0x02),
+                    read(0x01ff, 0x30),
+                    read(0x3002, 0x00),
+                ],
+            ),
+            // PLA reads the current stack slot before pulling
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0x68],
+                0x00,
+                vec![(0x01fe, 0x99)],
+                vec![
+                    read(0x0200, 0x68),
+                    read(0x0201, 0x00),
+                    read(0x01fd, 0x00),
+                    read(0x01fe, 0x99),
+                ],
+            ),
+            // A taken BNE across a page reads the next opcode and the
+            // target with the old high byte
+            (
+                CpuVariant::Nmos6502,
+                0x02fd,
+                vec![0xd0, 0x10],
+                0x00,
+                vec![],
+                vec![
+                    read(0x02fd, 0xd0),
+                    read(0x02fe, 0x10),
+                    read(0x02ff, 0x00),
+                    read(0x020f, 0x00),
+                ],
+            ),
+            // BRK reads its padding byte, pushes and fetches the vector
+            (
+                CpuVariant::Nmos6502,
+                0x0200,
+                vec![0x00, 0xea],
+                0x00,
+                vec![(0xfffe, 0x00), (0xffff, 0x40)],
+                vec![
+                    read(0x0200, 0x00),
+                    read(0x0201, 0xea),
+                    write(0x01fd, 0x02),
+                    write(0x01fc, 0x02),
+                    write(0x01fb, 0x34),
+                    read(0xfffe, 0x00),
+                    read(0xffff, 0x40),
+                ],
+            ),
+        ];
+
+        for (variant, pc, program, x, contents, expected) in cases {
+            let mut cpu = CPU::new(variant);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+            for (i, byte) in program.iter().enumerate() {
+                memory.write(pc + i as u16, *byte);
+            }
+            for (addr, value) in contents {
+                memory.write(addr, value);
+            }
+            cpu.pc = pc;
+            cpu.sp = 0xfd;
+            cpu.x = x;
+            cpu.set_status(0x24);
+
+            let (bus_cycles, cycles) = step_bus_cycles(&mut cpu, &mut memory);
+
+            assert_eq!(bus_cycles, expected, "{:?} {:02X?}", variant, program);
+            assert_eq!(cycles, expected.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_bus_observer() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let bus_cycles = Rc::new(RefCell::new(Vec::new()));
+        let sink = bus_cycles.clone();
+        cpu.set_bus_observer(Some(BusObserver::new(move |cycle: BusCycle| {
+            sink.borrow_mut().push((cycle.addr, cycle.access))
+        })));
+        cpu.set_tracer(Some(Tracer::new(|_| {})));
+
+        // Without cycle accurate mode only the accesses the instruction
+        // needs are made, the tracer's reads are never reported
+        memory.write(0x0000, u8::from(Instruction::STA_ABS_X));
+        memory.write(0x0001, 0x1200u16);
+        cpu.step(&mut memory).unwrap();
+        assert_eq!(
+            *bus_cycles.borrow(),
+            vec![
+                (0x0000, BusAccess::Read),
+                (0x0001, BusAccess::Read),
+                (0x0002, BusAccess::Read),
+                (0x1200, BusAccess::Write)
+            ]
+        );
+        assert_eq!(cpu.cycles, 5);
+
+        // Reset takes seven cycles like an interrupt, with reads in place
+        // of the stack writes
+        bus_cycles.borrow_mut().clear();
+        cpu.set_cycle_accurate(true);
+        cpu.sp = 0x00;
+        cpu.reset(&mut memory);
+        let addresses: Vec<u16> = bus_cycles.borrow().iter().map(|cycle| cycle.0).collect();
+        assert_eq!(
+            addresses,
+            vec![0x0003, 0x0003, 0x0100, 0x01ff, 0x01fe, 0xfffc, 0xfffd]
+        );
+        assert!(bus_cycles
+            .borrow()
+            .iter()
+            .all(|cycle| cycle.1 == BusAccess::Read));
+    }
+
+    #[derive(PartialEq, Eq)]
+    enum FlagState {
+        Set,
+        Clear,
+    }
+
+    macro_rules! test_branches {
+        ($func_name: ident, $instr_name: expr, $flag_state: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                // (address, offset, target, cycles when taken)
+                let branches = [
+                    (0x1000u16, 0xfeu8, 0x1000u16, 3u64),
+                    (0x10f0, 0x10, 0x1102, 4),
+                    (0x2080, 0x7f, 0x2101, 4),
+                    (0x2002, 0x80, 0x1f84, 4),
+                    (0x3040, 0x00, 0x3042, 3),
+                ];
+
+                for i in 0..10 {
+                    let (address, offset, target, taken_cycles) = branches[i / 2];
+                    memory.write(address, u8::from($instr_name));
+                    memory.write(address + 1, offset);
+
+                    cpu.pc = address;
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.set_carry(i % 2 == 1);
+                    cpu.set_negative(i % 2 == 1);
+                    cpu.set_zero(i % 2 == 1);
+                    cpu.set_overflow(i % 2 == 1);
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let flag_set = match $flag_state {
+                        FlagState::Set => 1,
+                        FlagState::Clear => 0,
+                    };
+
+                    cpu_copy.pc = if i % 2 == flag_set {
+                        target
+                    } else {
+                        address + 2
+                    };
+
+                    cpu_copy.cycles += if i % 2 == flag_set {
+                        taken_cycles
+                    } else {
+                        2
+                    };
+
+                    cpu_copy.set_carry(i % 2 == 1);
+                    cpu_copy.set_negative(i % 2 == 1);
+                    cpu_copy.set_zero(i % 2 == 1);
+                    cpu_copy.set_overflow(i % 2 == 1);
+
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+
+            }
+        };
+    }
+
+    test_branches! {test_bcc, Instruction::BCC, FlagState::Clear}
+    test_branches! {test_bcs, Instruction::BCS, FlagState::Set}
+    test_branches! {test_beq, Instruction::BEQ, FlagState::Set}
+    test_branches! {test_bmi, Instruction::BMI, FlagState::Set}
+    test_branches! {test_bne, Instruction::BNE, FlagState::Clear}
+    test_branches! {test_bpl, Instruction::BPL, FlagState::Clear}
+    test_branches! {test_bvc, Instruction::BVC, FlagState::Clear}
+    test_branches! {test_bvs, Instruction::BVS, FlagState::Set}
+
+    enum FlagName {
+        Carry,
+        DecimalMode,
+        InterruptDisable,
+        Overflow,
+    }
+
+    macro_rules! test_status_flag_changes {
+        ($func_name: ident, $instr_name: expr, $flag_name: expr, $flag_state: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU {
+                    ..Default::default()
+                };
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                memory.write(0, u8::from($instr_name));
+                let instruction = cpu.fetch_instruction(&mut memory);
+
+                cpu.execute(&mut memory, instruction).unwrap();
+
+                cpu_copy.pc = 1;
+                cpu_copy.cycles += 2;
+
+                match ($flag_name, $flag_state) {
+                    (FlagName::Carry, FlagState::Clear) => cpu_copy.set_carry(false),
+                    (FlagName::Carry, FlagState::Set) => cpu_copy.set_carry(true),
+                    (FlagName::DecimalMode, FlagState::Clear) => cpu_copy.set_decimal_mode(false),
+                    (FlagName::DecimalMode, FlagState::Set) => cpu_copy.set_decimal_mode(true),
+                    (FlagName::InterruptDisable, FlagState::Clear) => cpu_copy.set_interrupt_disable(false),
+                    (FlagName::InterruptDisable, FlagState::Set) => cpu_copy.set_interrupt_disable(true),
+                    (FlagName::Overflow, FlagState::Clear) => cpu_copy.set_overflow(false),
+                    _ => panic!("No such instruction"),
+                }
+
+                assert_cpu(&cpu, &cpu_copy);
+            }
+        };
+    }
+
+    test_status_flag_changes! {test_clc, Instruction::CLC, FlagName::Carry, FlagState::Clear}
+    test_status_flag_changes! {test_cld, Instruction::CLD, FlagName::DecimalMode, FlagState::Clear}
+    test_status_flag_changes! {test_cli, Instruction::CLI, FlagName::InterruptDisable, FlagState::Clear}
+    test_status_flag_changes! {test_clv, Instruction::CLV, FlagName::Overflow, FlagState::Clear}
+    test_status_flag_changes! {test_sec, Instruction::SEC, FlagName::Carry, FlagState::Set}
+    test_status_flag_changes! {test_sed, Instruction::SED, FlagName::DecimalMode, FlagState::Set}
+    test_status_flag_changes! {test_sei, Instruction::SEI, FlagName::InterruptDisable, FlagState::Set}
+
+    #[test]
+    fn test_nop() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        let mut cpu_copy = cpu.clone();
+
+        memory.write(0, u8::from(Instruction::NOP));
+        let instruction = cpu.fetch_instruction(&mut memory);
+
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        cpu_copy.pc = 1;
+        cpu_copy.cycles += 2;
+
+        assert_cpu(&cpu, &cpu_copy);
+    }
+
+    #[test]
+    fn test_push_processor_status() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        let values = [0u8, 0b0100_0101, 0b1100_0011];
+
+        for i in 0..3 {
+            memory.write_byte(i, Instruction::PHP.into());
+        }
+
+        for i in 0..3 {
+            let instruction = cpu.fetch_instruction(&mut memory);
+            cpu.set_status(values[i as usize]);
+            let mut cpu_copy = cpu.clone();
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            // Unused bit and break flag are always set in the pushed copy
+            let actual_value: u8 = memory.read(0x0100 + (cpu.sp + 1) as u16);
+            cpu_copy.cycles += 3;
+            cpu_copy.sp -= 1;
+            assert_cpu(&cpu, &cpu_copy);
+            assert_eq!(actual_value, values[i as usize] | 0b0011_0000);
+        }
+    }
+
+    #[test]
+    fn test_pull_processor_status() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xfc;
+        memory.write_byte(0x0000, Instruction::PLP.into());
+        memory.write_byte(0x01fd, 0b1101_0011);
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        let mut cpu_copy = cpu.clone();
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        // Break flag is not a register bit, bit 5 always reads as 1
+        cpu_copy.cycles += 4;
+        cpu_copy.sp = 0xfd;
+        cpu_copy.set_status(0b1110_0011);
+        assert_cpu(&cpu, &cpu_copy);
+        assert_eq!(cpu.get_status(), 0b1110_0011);
+        assert_eq!(cpu.get_status_flags(), StatusFlags::from(0b1110_0011));
+    }
+
+    #[test]
+    fn test_brk() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+
+        let processor_status = [false, true, false, true, false, true, false];
+        cpu.set_carry(processor_status[0]);
+        cpu.set_zero(processor_status[1]);
+        cpu.set_interrupt_disable(processor_status[2]);
+        cpu.set_decimal_mode(processor_status[3]);
+        cpu.set_break_command(processor_status[4]);
+        cpu.set_overflow(processor_status[5]);
+        cpu.set_negative(processor_status[6]);
+        let mut cpu_copy = cpu.clone();
+
+        let pc_init = 0x1234;
+        let interrupt_addr = 0x4321u16;
+
+        memory.write(pc_init, u8::from(Instruction::BRK));
+        memory.write(0xFFFE, interrupt_addr);
+
+        cpu.pc = pc_init;
+        let instruction = cpu.fetch_instruction(&mut memory);
+
+        let processor_status = cpu.get_status();
+
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        cpu_copy.pc = interrupt_addr;
+        cpu_copy.cycles += 7;
+        cpu_copy.sp -= 3;
+
+        assert_cpu(&cpu, &cpu_copy);
+        let status: u8 = memory.read(0x100u16 + cpu.sp as u16 + 1);
+        let pc: u16 = memory.read(0x100u16 + cpu.sp as u16 + 2);
+
+        assert_eq!(status, processor_status | 0b0001_0000);
+        assert_eq!(pc, pc_init + 2);
+    }
+
+    #[test]
+    fn test_rti() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        let mut cpu_copy = cpu.clone();
+        let stack_pc: u16 = 0x1234;
+        let stack_processor_status_flags = [false, true, false, true, false, true, false];
+        // Break flag set on the stack is ignored
+        let stack_processor_status = 0b0101_1010u8;
+        let sp: u8 = 0xff - 3;
+
+        memory.write(0, u8::from(Instruction::RTI));
+        memory.write(0x100u16 + sp as u16 + 1, stack_processor_status);
+        memory.write(0x100u16 + sp as u16 + 2, stack_pc);
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+
+        cpu.sp = sp;
+
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        cpu_copy.pc = stack_pc;
+        cpu_copy.cycles += 6;
+        cpu_copy.set_carry(stack_processor_status_flags[0]);
+        cpu_copy.set_zero(stack_processor_status_flags[1]);
+        cpu_copy.set_interrupt_disable(stack_processor_status_flags[2]);
+        cpu_copy.set_decimal_mode(stack_processor_status_flags[3]);
+        cpu_copy.set_break_command(stack_processor_status_flags[4]);
+        cpu_copy.set_overflow(stack_processor_status_flags[5]);
+        cpu_copy.set_negative(stack_processor_status_flags[6]);
+        cpu_copy.sp = 0xff;
+
+        assert_cpu(&cpu, &cpu_copy);
+    }
+
+    #[test]
+    fn test_bit_zero_page() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        let values1 = [0b0000_0000u8, 0b1111_1111, 0b1000_1111];
+        let values2 = [0b1111_1111u8, 0b0101_0101, 0b1011_0011];
+        let zero_flags = [true, false, false];
+        let overflow_flags = [true, true, false];
+        let negative_flags = [true, false, true];
+        let addresses = [0x10, 0xAB, 0xFF];
+
+        for i in 0..3 {
+            memory.write_byte(2 * i, Instruction::BIT_ZP.into());
+            memory.write_byte(2 * i + 1, addresses[i as usize]);
+            memory.write_byte(addresses[i as usize] as u16, values2[i as usize]);
+        }
+
+        let mut cpu_copy = cpu.clone();
+
+        for i in 0..3 {
+            let pc = cpu.pc;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.a = values1[i];
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.cycles = cycles + 3;
+            cpu_copy.pc = pc + 2;
+            cpu_copy.a = values1[i];
+            cpu_copy.set_zero(zero_flags[i]);
+            cpu_copy.set_overflow(overflow_flags[i]);
+            cpu_copy.set_negative(negative_flags[i]);
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_bit_absolute() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        let values1 = [0b0000_0000u8, 0b1111_1111, 0b1000_1111];
+        let values2 = [0b1111_1111u8, 0b0101_0101, 0b1011_0011];
+        let zero_flags = [true, false, false];
+        let overflow_flags = [true, true, false];
+        let negative_flags = [true, false, true];
+        let addresses = [0x1234, 0x4321, 0xABCD];
+
+        for i in 0..3 {
+            memory.write_byte(3 * i, Instruction::BIT_ABS.into());
+            memory.write_word(3 * i + 1, addresses[i as usize]);
+            memory.write_byte(addresses[i as usize] as u16, values2[i as usize]);
+        }
+
+        let mut cpu_copy = cpu.clone();
+
+        for i in 0..3 {
+            let pc = cpu.pc;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.a = values1[i];
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.cycles = cycles + 4;
+            cpu_copy.pc = pc + 3;
+            cpu_copy.a = values1[i];
+            cpu_copy.set_zero(zero_flags[i]);
+            cpu_copy.set_overflow(overflow_flags[i]);
+            cpu_copy.set_negative(negative_flags[i]);
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0xfffc, 0x8000u16);
+        cpu.a = 0x12;
+        cpu.x = 0x34;
+        cpu.y = 0x56;
+        cpu.sp = 0x42;
+        cpu.set_carry(true);
+
+        cpu.reset(&mut memory);
+
+        assert_eq!(cpu.a, 0x12);
+        assert_eq!(cpu.x, 0x34);
+        assert_eq!(cpu.y, 0x56);
+        assert_eq!(cpu.pc, 0x8000);
+        assert_eq!(cpu.sp, 0xfd);
+        assert_eq!(cpu.get_status(), 0b0010_0101);
+        assert_eq!(cpu.cycles, 7);
+    }
+
+    #[test]
+    fn test_power_on() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x1234, 0x42u8);
+        memory.write(0xfffc, 0x8000u16);
+        cpu.a = 0x12;
+        cpu.cycles = 1000;
+        cpu.set_carry(true);
+
+        cpu.power_on(&mut memory, None);
+
+        assert_eq!(cpu.a, 0);
+        assert_eq!(cpu.pc, 0x8000);
+        assert_eq!(cpu.sp, 0xfd);
+        assert_eq!(cpu.get_status(), 0b0010_0100);
+        assert_eq!(cpu.cycles, 7);
+        let value: u8 = memory.read(0x1234);
+        assert_eq!(value, 0x42);
+
+        let mut memory_copy = memory;
+        cpu.power_on(&mut memory, Some(0xC0FFEE));
+        cpu.power_on(&mut memory_copy, Some(0xC0FFEE));
+
+        assert_eq!(memory.ram, memory_copy.ram);
+        assert!(memory.ram.iter().any(|&byte| byte != memory.ram[0]));
+    }
+
+    #[test]
+    fn test_power_on_memory_map() {
+        let mut rom = vec![0xeau8; 0x4000];
+        rom[0x3ffc] = 0x00;
+        rom[0x3ffd] = 0xc0;
+        let new_map = || {
+            MemoryMap::new()
+                .mirrored_ram(0x0000, 0x1fff, 0x0800)
+                .ram(0x6000, 0x7fff)
+                .rom(0xc000, 0xffff, &rom)
+        };
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut map = new_map();
+        let mut map_copy = new_map();
+
+        cpu.power_on(&mut map, Some(0xC0FFEE));
+        assert_eq!(cpu.pc, 0xc000);
+        cpu.power_on(&mut map_copy, Some(0xC0FFEE));
+
+        let read = |map: &mut MemoryMap, range: std::ops::Range<u16>| {
+            return range.map(|addr| map.read_byte(addr)).collect::<Vec<_>>();
+        };
+        let ram = read(&mut map, 0x0000..0x0800);
+        assert_eq!(ram, read(&mut map_copy, 0x0000..0x0800));
+        assert!(ram.iter().any(|&byte| byte != ram[0]));
+        // The second region continues the sequence
+        assert_ne!(read(&mut map, 0x6000..0x6800), ram);
+        assert_eq!(read(&mut map, 0xc000..0xc100), vec![0xea; 0x100]);
+    }
+
+    #[test]
+    fn test_flag_carry() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_carry(false);
+        assert_eq!(cpu.get_carry(), false);
+        cpu.set_carry(true);
+        assert_eq!(cpu.get_carry(), true);
+        cpu.set_carry(false);
+        assert_eq!(cpu.get_carry(), false);
+    }
+
+    #[test]
+    fn test_flag_zero() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_zero(false);
+        assert_eq!(cpu.get_zero(), false);
+        cpu.set_zero(true);
+        assert_eq!(cpu.get_zero(), true);
+        cpu.set_zero(false);
+        assert_eq!(cpu.get_zero(), false);
+    }
+
+    #[test]
+    fn test_flag_interrupt_disable() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_interrupt_disable(false);
+        assert_eq!(cpu.get_interrupt_disable(), false);
+        cpu.set_interrupt_disable(true);
+        assert_eq!(cpu.get_interrupt_disable(), true);
+        cpu.set_interrupt_disable(false);
+        assert_eq!(cpu.get_interrupt_disable(), false);
+    }
+
+    #[test]
+    fn test_flag_decimal_mode() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_decimal_mode(false);
+        assert_eq!(cpu.get_decimal_mode(), false);
+        cpu.set_decimal_mode(true);
+        assert_eq!(cpu.get_decimal_mode(), true);
+        cpu.set_decimal_mode(false);
+        assert_eq!(cpu.get_decimal_mode(), false);
+    }
+
+    #[test]
+    fn test_flag_break_command() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_break_command(false);
+        assert_eq!(cpu.get_break_command(), false);
+        cpu.set_break_command(true);
+        assert_eq!(cpu.get_break_command(), true);
+        cpu.set_break_command(false);
+        assert_eq!(cpu.get_break_command(), false);
+    }
+
+    #[test]
+    fn test_flag_overflow() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_overflow(false);
+        assert_eq!(cpu.get_overflow(), false);
+        cpu.set_overflow(true);
+        assert_eq!(cpu.get_overflow(), true);
+        cpu.set_overflow(false);
+        assert_eq!(cpu.get_overflow(), false);
+    }
+
+    #[test]
+    fn test_flag_negative() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        cpu.set_negative(false);
+        assert_eq!(cpu.get_negative(), false);
+        cpu.set_negative(true);
+        assert_eq!(cpu.get_negative(), true);
+        cpu.set_negative(false);
+        assert_eq!(cpu.get_negative(), false);
+    }
+
+    #[test]
+    fn test_fetch_instruction() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_byte(0x0000, Instruction::LDA_IM.into());
+
+        let cpu_copy = cpu.clone();
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+
+        assert_eq!(cpu.pc, cpu_copy.pc + 1);
+        assert_eq!(
+            <Instruction as Into<u8>>::into(instruction),
+            Instruction::LDA_IM.into()
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_byte(0x0000, Instruction::LDA_IM.into());
+        memory.write_byte(0x0001, 0x42);
+        memory.write_byte(0x0002, Instruction::TAX.into());
+
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.pc, 0x0000);
+        assert_eq!(info.opcode, 0xA9);
+        assert_eq!(info.instruction, Instruction::LDA_IM);
+        assert_eq!(info.cycles, 2);
+        assert_eq!(cpu.get_a(), 0x42);
+        assert_eq!(cpu.get_pc(), 0x0002);
+
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.pc, 0x0002);
+        assert_eq!(info.instruction, Instruction::TAX);
+        assert_eq!(info.cycles, 2);
+        assert_eq!(cpu.get_x(), 0x42);
+        assert_eq!(cpu.get_cycles(), 4);
+    }
+
+    #[test]
+    fn test_run_for_cycles() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        // LDA $10 (3 cycles) followed by NOPs (2 cycles each)
+        memory.write_byte(0x0000, Instruction::LDA_ZP.into());
+        memory.write_byte(0x0001, 0x10);
+        for addr in 0x0002..0x0010 {
+            memory.write_byte(addr, Instruction::NOP.into());
+        }
+
+        let cycles = cpu.run_for_cycles(&mut memory, 7).unwrap();
+
+        assert_eq!(cycles, 7);
+        assert_eq!(cpu.get_pc(), 0x0004);
+
+        // Instructions are never split, so the last one may overshoot
+        let cycles = cpu.run_for_cycles(&mut memory, 1).unwrap();
+
+        assert_eq!(cycles, 2);
+        assert_eq!(cpu.get_pc(), 0x0005);
+        assert_eq!(cpu.get_cycles(), 9);
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_byte(0x0000, Instruction::LDX_IM.into());
+        memory.write_byte(0x0001, 0x00);
+        for addr in 0x0002..0x0010 {
+            memory.write_byte(addr, Instruction::INX.into());
+        }
+
+        let cycles = cpu.run_until(&mut memory, |cpu| cpu.get_x() == 5).unwrap();
+
+        assert_eq!(cycles, 2 + 5 * 2);
+        assert_eq!(cpu.get_x(), 5);
+        assert_eq!(cpu.get_pc(), 0x0007);
+    }
+
+    #[test]
+    fn test_cmos_decimal() {
+        // (instruction, a, operand, carry in) =>
+        // (a, carry out, zero, negative, overflow)
+        let cases = [
+            (
+                (Instruction::ADC_IM, 0x99u8, 0x01u8, false),
+                (0x00u8, true, true, false, false),
+            ),
+            (
+                (Instruction::ADC_IM, 0x58, 0x46, true),
+                (0x05, true, false, false, true),
+            ),
+            (
+                (Instruction::ADC_IM, 0x50, 0x50, false),
+                (0x00, true, true, false, true),
+            ),
+            (
+                (Instruction::ADC_IM, 0x79, 0x00, true),
+                (0x80, false, false, true, true),
+            ),
+            (
+                (Instruction::SBC_IM, 0x42, 0x42, true),
+                (0x00, true, true, false, false),
+            ),
+            (
+                (Instruction::SBC_IM, 0x00, 0x01, true),
+                (0x99, false, false, true, false),
+            ),
+            (
+                (Instruction::SBC_IM, 0x21, 0x34, true),
+                (0x87, false, false, true, false),
+            ),
+            // Invalid BCD, the NMOS chip gives 0x1b
+            (
+                (Instruction::SBC_IM, 0x20, 0x0f, true),
+                (0x0b, true, false, false, false),
+            ),
+        ];
+
+        for ((instruction, a, operand, carry), (res, carry_res, zero, negative, overflow)) in cases
+        {
+            let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            memory.write(0x0000, u8::from(instruction));
+            memory.write(0x0001, operand);
+            cpu.a = a;
+            cpu.set_carry(carry);
+            cpu.set_decimal_mode(true);
+
+            let (bus_cycles, cycles) = step_bus_cycles(&mut cpu, &mut memory);
+
+            let name = format!("{:?} {:02X} {:02X}", instruction, a, operand);
+            assert_eq!(cpu.a, res, "{}", name);
+            assert_eq!(cpu.get_carry(), carry_res, "{}", name);
+            assert_eq!(cpu.get_zero(), zero, "{}", name);
+            assert_eq!(cpu.get_negative(), negative, "{}", name);
+            assert_eq!(cpu.get_overflow(), overflow, "{}", name);
+            assert_eq!(cycles, 3, "{}", name);
+            assert_eq!(bus_cycles.len(), 3, "{}", name);
+            assert_eq!(bus_cycles[2].addr, 0x0002, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_variant_decimal_mode() {
+        let variants = [
+            (CpuVariant::Nmos6502, 0x10u8),
+            (CpuVariant::Cmos65C02, 0x10),
+            (CpuVariant::Ricoh2A03, 0x0a),
+            (CpuVariant::Mos6507, 0x10),
+        ];
+
+        for (variant, result) in variants {
+            let mut cpu = CPU::new(variant);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            memory.write(0x0000, u8::from(Instruction::SED));
+            memory.write(0x0001, u8::from(Instruction::ADC_IM));
+            memory.write(0x0002, 0x01u8);
+            cpu.a = 0x09;
+
+            cpu.step(&mut memory).unwrap();
+            cpu.step(&mut memory).unwrap();
+
+            assert_eq!(cpu.get_variant(), variant);
+            assert_eq!(cpu.a, result, "Wrong decimal result for {:?}", variant);
+        }
+    }
+
+    #[test]
+    fn test_variant_jmp_indirect_page_wrap() {
+        let variants = [
+            (CpuVariant::Nmos6502, 0x1234u16, 5u64),
+            (CpuVariant::Cmos65C02, 0x5634, 6),
+            (CpuVariant::Ricoh2A03, 0x1234, 5),
+            (CpuVariant::Mos6507, 0x1234, 5),
+        ];
+
+        for (variant, target, cycles) in variants {
+            let mut cpu = CPU::new(variant);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            memory.write(0x0000, u8::from(Instruction::JMP_IN));
+            memory.write(0x0001, 0x02ffu16);
+            memory.write(0x02ff, 0x34u8);
+            memory.write(0x0300, 0x56u8);
+            memory.write(0x0200, 0x12u8);
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(cpu.pc, target, "Wrong jump target for {:?}", variant);
+            assert_eq!(info.cycles, cycles, "Wrong cycle count for {:?}", variant);
+        }
+    }
+
+    #[test]
+    fn test_variant_address_bus() {
+        let mut cpu = CPU::new(CpuVariant::Mos6507);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        // Program lives in the first 8 KiB and is visible through every mirror
+        memory.write(0x1ffc, 0xf000u16);
+        memory.write(0x1000, u8::from(Instruction::LDA_ABS));
+        memory.write(0x1001, 0xf080u16);
+        memory.write(0x1080, 0x42u8);
+
+        cpu.reset(&mut memory);
+        cpu.step(&mut memory).unwrap();
+
+        assert_eq!(cpu.pc, 0xf003);
+        assert_eq!(cpu.a, 0x42);
+    }
+
+    #[test]
+    fn test_variant_address_bus_observed() {
+        let mut cpu = CPU::new(CpuVariant::Mos6507);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let addresses = Rc::new(RefCell::new(Vec::new()));
+        let sink = addresses.clone();
+        cpu.set_cycle_accurate(true);
+        cpu.set_bus_observer(Some(BusObserver::new(move |cycle: BusCycle| {
+            sink.borrow_mut().push(cycle.addr)
+        })));
+        memory.write(0x1ffc, 0xf000u16);
+        cpu.pc = 0xf123;
+        cpu.sp = 0xfd;
+
+        cpu.reset(&mut memory);
+
+        // The dummy reads see the 13 address lines like every other access
+        assert_eq!(
+            *addresses.borrow(),
+            vec![0x1123, 0x1123, 0x01fd, 0x01fc, 0x01fb, 0x1ffc, 0x1ffd]
+        );
+        assert_eq!(cpu.pc, 0xf000);
+
+        addresses.borrow_mut().clear();
+        memory.write(0x1000, u8::from(Instruction::LDA_ABS));
+        memory.write(0x1001, 0xf080u16);
+        cpu.step(&mut memory).unwrap();
+
+        assert_eq!(*addresses.borrow(), vec![0x1000, 0x1001, 0x1002, 0x1080]);
+    }
+
+    #[test]
+    fn test_variant_decode_65c02() {
+        let opcodes = [
+            (0x80u8, Instruction::BRA),
+            (0xDA, Instruction::PHX),
+            (0x7A, Instruction::PLY),
+            (0x9C, Instruction::STZ_ABS),
+            (0xB2, Instruction::LDA_IN_ZP),
+            (0x7C, Instruction::JMP_IN_X),
+            (0xCB, Instruction::WAI),
+        ];
+
+        for (opcode, instruction) in opcodes {
+            assert_eq!(
+                Instruction::decode(opcode, CpuVariant::Cmos65C02),
+                instruction
+            );
+            assert_ne!(
+                Instruction::decode(opcode, CpuVariant::Nmos6502),
+                instruction
+            );
+            assert_eq!(u8::from(instruction), opcode);
+        }
+        // Opcodes shared with the NMOS part decode the same way
+        assert_eq!(
+            Instruction::decode(0xA9, CpuVariant::Cmos65C02),
+            Instruction::LDA_IM
+        );
+    }
+
+    macro_rules! test_stz {
+        ($func_name: ident, $instr_name: expr, $addr_mode: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let mut pc_increments: HashMap<AddressingMode, u16> = HashMap::new();
+                pc_increments.insert(AddressingMode::ZeroPage, 2);
+                pc_increments.insert(AddressingMode::ZeroPageReg, 2);
+                pc_increments.insert(AddressingMode::Absolute, 3);
+                pc_increments.insert(AddressingMode::AbsoluteReg, 3);
+
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::ZeroPage, 3);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 4);
+                cycles_increments.insert(AddressingMode::Absolute, 4);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 5);
+
+                let addresses_zp = [0x13u8, 0x69, 0xFF, 0xAB];
+                let x_values = [0x10, 0x00u8, 0x16, 0x4A];
+                let addresses_zp_final_x = [0x23u8, 0x69, 0x15, 0xF5];
+                let addresses_absolute = [0x0013u16, 0xfff4, 0xABCD, 0xffff];
+                let addresses_absolute_final_x = [0x0023u16, 0xfff4, 0xABE3, 0x0049];
+                let mut addresses_final: [u16; 4] = [0, 0, 0, 0];
+
+                for i in 0..4 {
+                    match $addr_mode {
+                        AddressingMode::ZeroPage => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            addresses_final[i as usize] = addresses_zp[i as usize] as u16;
+                        }
+                        AddressingMode::ZeroPageReg => {
+                            memory.write(2 * i, u8::from($instr_name));
+                            memory.write(2 * i + 1, addresses_zp[i as usize]);
+                            addresses_final[i as usize] = addresses_zp_final_x[i as usize] as u16;
+                        }
+                        AddressingMode::Absolute => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute[i as usize];
+                        }
+                        AddressingMode::AbsoluteReg => {
+                            memory.write(3 * i, u8::from($instr_name));
+                            memory.write(3 * i + 1, addresses_absolute[i as usize]);
+                            addresses_final[i as usize] = addresses_absolute_final_x[i as usize];
+                        }
+                        _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                    }
+                }
+
+                for i in 0..4 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let address = addresses_final[i];
+                    memory.write(address, 0xffu8);
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.x = x_values[i];
+                    cpu.a = 0x69;
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let actual_value: u8 = memory.read(address);
+
+                    assert_eq!(actual_value, 0);
+                    cpu_copy.cycles = cycles + cycles_increments[$addr_mode];
+                    cpu_copy.pc = pc + pc_increments[$addr_mode];
+                    cpu_copy.x = x_values[i];
+                    cpu_copy.a = 0x69;
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_stz! {test_stz_zero_page, Instruction::STZ_ZP, &AddressingMode::ZeroPage}
+    test_stz! {test_stz_zero_page_x, Instruction::STZ_ZP_X, &AddressingMode::ZeroPageReg}
+    test_stz! {test_stz_absolute, Instruction::STZ_ABS, &AddressingMode::Absolute}
+    test_stz! {test_stz_absolute_x, Instruction::STZ_ABS_X, &AddressingMode::AbsoluteReg}
+
+    macro_rules! test_trb_tsb {
+        ($func_name: ident, $instr_name: expr, $addr_mode: expr, $op_func: expr) => {
+            #[test]
+            fn $func_name() {
+                let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+                let mut cpu_copy = cpu.clone();
+
+                let a_values = [0b1010_1010u8, 0b0000_1111, 0b1111_1111];
+                let values = [0b0101_0101u8, 0b0011_1100, 0b0000_0000];
+                let zero_flags = [true, false, true];
+                let addresses = [0x0010u16, 0x00AB, 0x1234];
+
+                let (instr_len, cycles_used) = match $addr_mode {
+                    AddressingMode::ZeroPage => (2, 5),
+                    AddressingMode::Absolute => (3, 6),
+                    _ => panic!("Unsupported addressing mode {:?}", $addr_mode),
+                };
+
+                for i in 0..3 {
+                    let address = if instr_len == 2 {
+                        addresses[i as usize] & 0xff
+                    } else {
+                        addresses[i as usize]
+                    };
+                    memory.write(instr_len * i, u8::from($instr_name));
+                    if instr_len == 2 {
+                        memory.write(instr_len * i + 1, address as u8);
+                    } else {
+                        memory.write(instr_len * i + 1, address);
+                    }
+                    memory.write(address, values[i as usize]);
+                }
+
+                for i in 0..3 {
+                    let pc = cpu.pc;
+                    let cycles = cpu.cycles;
+                    let address = if instr_len == 2 {
+                        addresses[i] & 0xff
+                    } else {
+                        addresses[i]
+                    };
+                    let instruction = cpu.fetch_instruction(&mut memory);
+
+                    cpu.a = a_values[i];
+
+                    cpu.execute(&mut memory, instruction).unwrap();
+
+                    let actual_value: u8 = memory.read(address);
+
+                    assert_eq!(actual_value, $op_func(values[i], a_values[i]));
+                    cpu_copy.cycles = cycles + cycles_used;
+                    cpu_copy.pc = pc + instr_len;
+                    cpu_copy.a = a_values[i];
+                    cpu_copy.set_zero(zero_flags[i]);
+                    assert_cpu(&cpu, &cpu_copy);
+                }
+            }
+        };
+    }
+
+    test_trb_tsb! {test_trb_zero_page, Instruction::TRB_ZP, &AddressingMode::ZeroPage, |m: u8, a: u8| m & !a}
+    test_trb_tsb! {test_trb_absolute, Instruction::TRB_ABS, &AddressingMode::Absolute, |m: u8, a: u8| m & !a}
+    test_trb_tsb! {test_tsb_zero_page, Instruction::TSB_ZP, &AddressingMode::ZeroPage, |m: u8, a: u8| m | a}
+    test_trb_tsb! {test_tsb_absolute, Instruction::TSB_ABS, &AddressingMode::Absolute, |m: u8, a: u8| m | a}
+
+    #[test]
+    fn test_bit_immediate() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        let values1 = [0b0000_0000u8, 0b1111_1111, 0b1000_1111];
+        let values2 = [0b1111_1111u8, 0b0101_0101, 0b0111_0000];
+        let zero_flags = [true, false, true];
+
+        for i in 0..3 {
+            memory.write_byte(2 * i, Instruction::BIT_IM.into());
+            memory.write_byte(2 * i + 1, values2[i as usize]);
+        }
+
+        let mut cpu_copy = cpu.clone();
+
+        for i in 0..3 {
+            let pc = cpu.pc;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.a = values1[i];
+            // Immediate mode leaves N and V untouched
+            cpu.set_overflow(i % 2 == 0);
+            cpu.set_negative(i % 2 == 1);
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.cycles = cycles + 2;
+            cpu_copy.pc = pc + 2;
+            cpu_copy.a = values1[i];
+            cpu_copy.set_zero(zero_flags[i]);
+            cpu_copy.set_overflow(i % 2 == 0);
+            cpu_copy.set_negative(i % 2 == 1);
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_bit_zero_page_x() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        let values1 = [0b0000_0000u8, 0b1111_1111, 0b1000_1111];
+        let values2 = [0b1111_1111u8, 0b0101_0101, 0b1011_0011];
+        let zero_flags = [true, false, false];
+        let overflow_flags = [true, true, false];
+        let negative_flags = [true, false, true];
+        let addresses = [0x10, 0xAB, 0xFF];
+        let x_values = [0x05u8, 0x00, 0x21];
+        let addresses_final = [0x15u8, 0xAB, 0x20];
+
+        for i in 0..3 {
+            memory.write_byte(2 * i, Instruction::BIT_ZP_X.into());
+            memory.write_byte(2 * i + 1, addresses[i as usize]);
+            memory.write_byte(addresses_final[i as usize] as u16, values2[i as usize]);
+        }
+
+        let mut cpu_copy = cpu.clone();
+
+        for i in 0..3 {
+            let pc = cpu.pc;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.a = values1[i];
+            cpu.x = x_values[i];
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.cycles = cycles + 4;
+            cpu_copy.pc = pc + 2;
+            cpu_copy.a = values1[i];
+            cpu_copy.x = x_values[i];
+            cpu_copy.set_zero(zero_flags[i]);
+            cpu_copy.set_overflow(overflow_flags[i]);
+            cpu_copy.set_negative(negative_flags[i]);
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_bit_absolute_x() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        let values1 = [0b0000_0000u8, 0b1111_1111, 0b1000_1111];
+        let values2 = [0b1111_1111u8, 0b0101_0101, 0b1011_0011];
+        let zero_flags = [true, false, false];
+        let overflow_flags = [true, true, false];
+        let negative_flags = [true, false, true];
+        let addresses = [0x0010u16, 0x1234, 0xABCD];
+        let x_values = [0x05u8, 0x00, 0x21];
+        let addresses_final = [0x0015u16, 0x1234, 0xABEE];
+
+        for i in 0..3 {
+            memory.write_byte(3 * i, Instruction::BIT_ABS_X.into());
+            memory.write(3 * i + 1, addresses[i as usize]);
+            memory.write_byte(addresses_final[i as usize], values2[i as usize]);
+        }
+
+        let mut cpu_copy = cpu.clone();
+
+        for i in 0..3 {
+            let pc = cpu.pc;
+            let cycles = cpu.cycles;
+            let instruction = cpu.fetch_instruction(&mut memory);
+
+            cpu.a = values1[i];
+            cpu.x = x_values[i];
+
+            cpu.execute(&mut memory, instruction).unwrap();
+
+            cpu_copy.cycles = cycles + 4;
+            cpu_copy.pc = pc + 3;
+            cpu_copy.a = values1[i];
+            cpu_copy.x = x_values[i];
+            cpu_copy.set_zero(zero_flags[i]);
+            cpu_copy.set_overflow(overflow_flags[i]);
+            cpu_copy.set_negative(negative_flags[i]);
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_zero_page_indirect_operations() {
+        // (instruction, a, memory value, carry, expected a)
+        let cases = [
+            (
+                Instruction::ORA_IN_ZP,
+                0b1010_0000u8,
+                0b0000_0101u8,
+                false,
+                0b1010_0101u8,
+            ),
+            (
+                Instruction::AND_IN_ZP,
+                0b1010_1111,
+                0b0110_0101,
+                false,
+                0b0010_0101,
+            ),
+            (
+                Instruction::EOR_IN_ZP,
+                0b1111_0000,
+                0b1010_1010,
+                false,
+                0b0101_1010,
+            ),
+            (Instruction::ADC_IN_ZP, 0x10, 0x22, true, 0x33),
+            (Instruction::SBC_IN_ZP, 0x50, 0x10, true, 0x40),
+            (Instruction::CMP_IN_ZP, 0x42, 0x42, false, 0x42),
+        ];
+
+        for (instruction, a, value, carry, result) in cases {
+            let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, u8::from(instruction));
+            memory.write(0x0001, 0x40u8);
+            memory.write(0x0040, 0x1234u16);
+            memory.write(0x1234, value);
+            cpu.a = a;
+            cpu.set_carry(carry);
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(info.instruction, instruction);
+            assert_eq!(info.cycles, 5, "Wrong cycles for {:?}", instruction);
+            assert_eq!(cpu.pc, 0x0002);
+            assert_eq!(cpu.a, result, "Wrong result for {:?}", instruction);
+            if instruction == Instruction::CMP_IN_ZP {
+                assert!(cpu.get_zero());
+            }
+        }
+    }
+
+    #[test]
+    fn test_jmp_absolute_x_indirect() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        memory.write(0x0000, u8::from(Instruction::JMP_IN_X));
+        memory.write(0x0001, 0x1234u16);
+        memory.write(0x1244, 0xBEEFu16);
+        cpu.x = 0x10;
+
+        let mut cpu_copy = cpu.clone();
+        let instruction = cpu.fetch_instruction(&mut memory);
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        cpu_copy.pc = 0xBEEF;
+        cpu_copy.cycles += 6;
+        assert_cpu(&cpu, &cpu_copy);
+    }
+
+    #[test]
+    fn test_bra() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        memory.write(0x0000, u8::from(Instruction::BRA));
+        memory.write(0x0001, 0x10u8);
+
+        let mut cpu_copy = cpu.clone();
+        let instruction = cpu.fetch_instruction(&mut memory);
+        let pc = cpu.pc;
+        cpu.execute(&mut memory, instruction).unwrap();
+
+        cpu_copy.pc = pc + 1 + 0x10;
+        cpu_copy.cycles += 3;
+        assert_cpu(&cpu, &cpu_copy);
+    }
+
+    #[test]
+    fn test_page_crossing() {
+        // (instruction, operand, pointer, index, cycles)
+        let cases = [
+            (Instruction::LDA_ABS_X, 0x12f0u16, 0u16, 0x0fu8, 4u64),
+            (Instruction::LDA_ABS_X, 0x12f0, 0, 0x10, 5),
+            (Instruction::LDA_ABS_Y, 0x0100, 0, 0xff, 4),
+            (Instruction::LDA_ABS_Y, 0xfff0, 0, 0x20, 5),
+            (Instruction::LDA_IN_Y, 0x0010, 0x4080, 0x7f, 5),
+            (Instruction::LDA_IN_Y, 0x0010, 0x4080, 0x80, 6),
+        ];
+
+        for (instruction, operand, pointer, index, cycles) in cases {
+            let mut cpu = CPU {
+                ..Default::default()
+            };
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+            cpu.pc = 0x0200;
+            cpu.x = index;
+            cpu.y = index;
+            memory.write(0x0200, u8::from(instruction));
+            memory.write(0x0201, operand);
+            memory.write(operand, pointer);
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(
+                info.cycles, cycles,
+                "{:?} index {:#04x}",
+                instruction, index
+            );
+        }
+    }
+
+    #[test]
+    fn test_bbr_bbs() {
+        let bbr = [
+            Instruction::BBR0,
+            Instruction::BBR1,
+            Instruction::BBR2,
+            Instruction::BBR3,
+            Instruction::BBR4,
+            Instruction::BBR5,
+            Instruction::BBR6,
+            Instruction::BBR7,
+        ];
+        let bbs = [
+            Instruction::BBS0,
+            Instruction::BBS1,
+            Instruction::BBS2,
+            Instruction::BBS3,
+            Instruction::BBS4,
+            Instruction::BBS5,
+            Instruction::BBS6,
+            Instruction::BBS7,
+        ];
+
+        for bit in 0..8 {
+            for (instruction, taken) in [(bbr[bit], false), (bbs[bit], true)] {
+                let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+
+                memory.write(0x0000, u8::from(instruction));
+                memory.write(0x0001, 0x40u8);
+                memory.write(0x0002, 0x20u8);
+                memory.write(0x0040, 1u8 << bit);
+
+                let mut cpu_copy = cpu.clone();
+                let instruction = cpu.fetch_instruction(&mut memory);
+                cpu.execute(&mut memory, instruction).unwrap();
+
+                cpu_copy.pc = if taken { 0x0003 + 0x20 } else { 0x0003 };
+                cpu_copy.cycles += if taken { 6 } else { 5 };
+                assert_cpu(&cpu, &cpu_copy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rmb_smb() {
+        let rmb = [
+            Instruction::RMB0,
+            Instruction::RMB1,
+            Instruction::RMB2,
+            Instruction::RMB3,
+            Instruction::RMB4,
+            Instruction::RMB5,
+            Instruction::RMB6,
+            Instruction::RMB7,
+        ];
+        let smb = [
+            Instruction::SMB0,
+            Instruction::SMB1,
+            Instruction::SMB2,
+            Instruction::SMB3,
+            Instruction::SMB4,
+            Instruction::SMB5,
+            Instruction::SMB6,
+            Instruction::SMB7,
+        ];
+
+        for bit in 0..8 {
+            for (instruction, value, result) in [
+                (rmb[bit], 0xffu8, !(1u8 << bit)),
+                (smb[bit], 0x00, 1 << bit),
+            ] {
+                let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+
+                memory.write(0x0000, u8::from(instruction));
+                memory.write(0x0001, 0x40u8);
+                memory.write(0x0040, value);
+
+                let mut cpu_copy = cpu.clone();
+                let instruction = cpu.fetch_instruction(&mut memory);
+                cpu.execute(&mut memory, instruction).unwrap();
+
+                let actual_value: u8 = memory.read(0x0040);
+
+                assert_eq!(actual_value, result);
+                cpu_copy.pc = 0x0002;
+                cpu_copy.cycles += 5;
+                assert_cpu(&cpu, &cpu_copy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wai() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0xfffe, 0x2000u16);
+        memory.write(0x0000, u8::from(Instruction::WAI));
+        memory.write(0x2000, u8::from(Instruction::NOP));
+
+        cpu.reset(&mut memory);
+        cpu.set_interrupt_disable(false);
+
+        let info = cpu.step(&mut memory).unwrap();
+        assert_eq!(info.instruction, Instruction::WAI);
+        assert_eq!(info.cycles, 3);
+        assert!(cpu.is_waiting());
+
+        // Without an interrupt the CPU idles one cycle per step
+        let info = cpu.step(&mut memory).unwrap();
+        assert_eq!(info.cycles, 1);
+        assert_eq!(cpu.pc, 0x0001);
+        assert!(cpu.is_waiting());
+
+        cpu.set_irq_line(true);
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert!(!cpu.is_waiting());
+        assert_eq!(info.interrupt, Some(Interrupt::Irq));
+        assert_eq!(info.instruction, Instruction::NOP);
+        assert_eq!(cpu.pc, 0x2001);
+    }
+
+    #[test]
+    fn test_wai_interrupt_disabled() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0000, u8::from(Instruction::WAI));
+        memory.write(0x0001, u8::from(Instruction::INX));
+
+        cpu.reset(&mut memory);
+        cpu.step(&mut memory).unwrap();
+        cpu.set_irq_line(true);
+
+        // With I set the CPU resumes after WAI without taking the interrupt
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert!(!cpu.is_waiting());
+        assert_eq!(info.interrupt, None);
+        assert_eq!(info.instruction, Instruction::INX);
+        assert_eq!(cpu.x, 1);
+    }
+
+    #[test]
+    fn test_stp() {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0000, u8::from(Instruction::STP));
+
+        cpu.reset(&mut memory);
+        cpu.step(&mut memory).unwrap();
+        assert!(cpu.is_stopped());
+
+        cpu.set_irq_line(true);
+        cpu.trigger_nmi();
+        let cycles = cpu.run_for_cycles(&mut memory, 10).unwrap();
+
+        assert_eq!(cycles, 10);
+        assert_eq!(cpu.pc, 0x0001);
+        assert!(cpu.is_stopped());
+
+        cpu.reset(&mut memory);
+        assert!(!cpu.is_stopped());
+        assert_eq!(cpu.pc, 0x0000);
+    }
+
+    // Writes the operand of the instruction at 0x0000 so that it
+    // addresses 0x0040 with X = Y = 0x10 and no page is crossed.
+    // Returns the effective address and the instruction length
+    fn write_undocumented_operand(memory: &mut Memory, addr_mode: &AddressingMode) -> (u16, u16) {
+        return match addr_mode {
+            AddressingMode::ZeroPage => {
+                memory.write(0x0001, 0x40u8);
+                (0x0040, 2)
+            }
+            AddressingMode::ZeroPageReg => {
+                memory.write(0x0001, 0x30u8);
+                (0x0040, 2)
+            }
+            AddressingMode::Absolute => {
+                memory.write(0x0001, 0x0040u16);
+                (0x0040, 3)
+            }
+            AddressingMode::AbsoluteReg => {
+                memory.write(0x0001, 0x0030u16);
+                (0x0040, 3)
+            }
+            AddressingMode::IndirectX => {
+                memory.write(0x0001, 0x20u8);
+                memory.write(0x0030, 0x0040u16);
+                (0x0040, 2)
+            }
+            AddressingMode::IndirectY => {
+                memory.write(0x0001, 0x50u8);
+                memory.write(0x0050, 0x0030u16);
+                (0x0040, 2)
+            }
+            _ => panic!("Unsupported addressing mode {:?}", addr_mode),
+        };
+    }
+
+    macro_rules! test_undocumented_rmw {
+        // ref_func(a, m, carry) returns (a, m, carry, value N and Z are taken from)
+        ($func_name: ident, $instructions: expr, $ref_func: expr, $check_flags: expr) => {
+            #[test]
+            fn $func_name() {
+                use std::collections::HashMap;
+                let mut cycles_increments: HashMap<AddressingMode, u64> = HashMap::new();
+                cycles_increments.insert(AddressingMode::ZeroPage, 5);
+                cycles_increments.insert(AddressingMode::ZeroPageReg, 6);
+                cycles_increments.insert(AddressingMode::Absolute, 6);
+                cycles_increments.insert(AddressingMode::AbsoluteReg, 7);
+                cycles_increments.insert(AddressingMode::IndirectX, 8);
+                cycles_increments.insert(AddressingMode::IndirectY, 8);
+
+                let a_values = [0x0fu8, 0xf0, 0x00, 0x55];
+                let values = [0x81u8, 0x01, 0x00, 0xff];
+                let carries = [false, true, false, true];
+
+                for (instruction, addr_mode) in $instructions {
+                    for i in 0..4 {
+                        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+                        let mut memory = Memory {
+                            ..Default::default()
+                        };
+
+                        cpu.reset(&mut memory);
+
+                        memory.write(0x0000, u8::from(instruction));
+                        let (address, length) = write_undocumented_operand(&mut memory, addr_mode);
+                        memory.write(address, values[i]);
+
+                        cpu.a = a_values[i];
+                        cpu.x = 0x10;
+                        cpu.y = 0x10;
+                        cpu.set_carry(carries[i]);
+
+                        let info = cpu.step(&mut memory).unwrap();
+
+                        let (a, value, carry, result) =
+                            $ref_func(a_values[i], values[i], carries[i]);
+                        let actual_value: u8 = memory.read(address);
+
+                        assert_eq!(info.instruction, instruction);
+                        assert_eq!(
+                            info.cycles, cycles_increments[addr_mode],
+                            "Wrong cycles for {:?}",
+                            instruction
+                        );
+                        assert_eq!(cpu.pc, length);
+                        assert_eq!(
+                            actual_value, value,
+                            "Wrong memory value for {:?}",
+                            instruction
+                        );
+                        assert_eq!(cpu.a, a, "Wrong accumulator for {:?}", instruction);
+                        if $check_flags {
+                            assert_eq!(cpu.get_carry(), carry, "Wrong carry for {:?}", instruction);
+                            assert_eq!(cpu.get_zero(), result == 0);
+                            assert_eq!(cpu.get_negative(), (result as i8) < 0);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    test_undocumented_rmw! {test_slo, [
+        (Instruction::SLO_ZP, &AddressingMode::ZeroPage),
+        (Instruction::SLO_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::SLO_ABS, &AddressingMode::Absolute),
+        (Instruction::SLO_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::SLO_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::SLO_IN_X, &AddressingMode::IndirectX),
+        (Instruction::SLO_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, _c: bool| {
+        let value = m << 1;
+        (a | value, value, m & 0x80 != 0, a | value)
+    }, true}
+
+    test_undocumented_rmw! {test_rla, [
+        (Instruction::RLA_ZP, &AddressingMode::ZeroPage),
+        (Instruction::RLA_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::RLA_ABS, &AddressingMode::Absolute),
+        (Instruction::RLA_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::RLA_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::RLA_IN_X, &AddressingMode::IndirectX),
+        (Instruction::RLA_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, c: bool| {
+        let value = (m << 1) | c as u8;
+        (a & value, value, m & 0x80 != 0, a & value)
+    }, true}
+
+    test_undocumented_rmw! {test_sre, [
+        (Instruction::SRE_ZP, &AddressingMode::ZeroPage),
+        (Instruction::SRE_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::SRE_ABS, &AddressingMode::Absolute),
+        (Instruction::SRE_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::SRE_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::SRE_IN_X, &AddressingMode::IndirectX),
+        (Instruction::SRE_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, _c: bool| {
+        let value = m >> 1;
+        (a ^ value, value, m & 0x01 != 0, a ^ value)
+    }, true}
+
+    test_undocumented_rmw! {test_rra, [
+        (Instruction::RRA_ZP, &AddressingMode::ZeroPage),
+        (Instruction::RRA_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::RRA_ABS, &AddressingMode::Absolute),
+        (Instruction::RRA_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::RRA_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::RRA_IN_X, &AddressingMode::IndirectX),
+        (Instruction::RRA_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, c: bool| {
+        let value = (m >> 1) | ((c as u8) << 7);
+        let sum = a as u16 + value as u16 + (m & 0x01) as u16;
+        (sum as u8, value, sum > 0xff, sum as u8)
+    }, false}
+
+    test_undocumented_rmw! {test_dcp, [
+        (Instruction::DCP_ZP, &AddressingMode::ZeroPage),
+        (Instruction::DCP_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::DCP_ABS, &AddressingMode::Absolute),
+        (Instruction::DCP_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::DCP_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::DCP_IN_X, &AddressingMode::IndirectX),
+        (Instruction::DCP_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, _c: bool| {
+        let value = m.wrapping_sub(1);
+        (a, value, a >= value, a.wrapping_sub(value))
+    }, true}
+
+    test_undocumented_rmw! {test_isc, [
+        (Instruction::ISC_ZP, &AddressingMode::ZeroPage),
+        (Instruction::ISC_ZP_X, &AddressingMode::ZeroPageReg),
+        (Instruction::ISC_ABS, &AddressingMode::Absolute),
+        (Instruction::ISC_ABS_X, &AddressingMode::AbsoluteReg),
+        (Instruction::ISC_ABS_Y, &AddressingMode::AbsoluteReg),
+        (Instruction::ISC_IN_X, &AddressingMode::IndirectX),
+        (Instruction::ISC_IN_Y, &AddressingMode::IndirectY),
+    ], |a: u8, m: u8, c: bool| {
+        let value = m.wrapping_add(1);
+        let res = a.wrapping_sub(value).wrapping_sub(1 - c as u8);
+        (res, value, a as u16 >= value as u16 + (1 - c as u16), res)
+    }, false}
+
+    #[test]
+    fn test_lax() {
+        let instructions = [
+            (Instruction::LAX_ZP, &AddressingMode::ZeroPage, 3),
+            (Instruction::LAX_ZP_Y, &AddressingMode::ZeroPageReg, 4),
+            (Instruction::LAX_ABS, &AddressingMode::Absolute, 4),
+            (Instruction::LAX_ABS_Y, &AddressingMode::AbsoluteReg, 4),
+            (Instruction::LAX_IN_X, &AddressingMode::IndirectX, 6),
+            (Instruction::LAX_IN_Y, &AddressingMode::IndirectY, 5),
+        ];
+
+        for (instruction, addr_mode, cycles) in instructions {
+            for value in [0x00u8, 0x42, 0x80] {
+                let mut cpu = CPU::new(CpuVariant::Nmos6502);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+
+                memory.write(0x0000, u8::from(instruction));
+                let (address, length) = write_undocumented_operand(&mut memory, addr_mode);
+                memory.write(address, value);
+                cpu.x = 0x10;
+                cpu.y = 0x10;
+
+                let mut cpu_copy = cpu.clone();
+                let info = cpu.step(&mut memory).unwrap();
+
+                assert_eq!(info.instruction, instruction);
+                cpu_copy.a = value;
+                cpu_copy.x = value;
+                cpu_copy.pc = length;
+                cpu_copy.cycles += cycles;
+                cpu_copy.set_zero(value == 0);
+                cpu_copy.set_negative((value as i8) < 0);
+                assert_cpu(&cpu, &cpu_copy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sax() {
+        let instructions = [
+            (Instruction::SAX_ZP, &AddressingMode::ZeroPage, 3),
+            (Instruction::SAX_ZP_Y, &AddressingMode::ZeroPageReg, 4),
+            (Instruction::SAX_ABS, &AddressingMode::Absolute, 4),
+            (Instruction::SAX_IN_X, &AddressingMode::IndirectX, 6),
+        ];
+
+        for (instruction, addr_mode, cycles) in instructions {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, u8::from(instruction));
+            let (address, length) = write_undocumented_operand(&mut memory, addr_mode);
+            memory.write(address, 0xffu8);
+            cpu.a = 0b1111_0011;
+            cpu.x = 0x10;
+            cpu.y = 0x10;
+
+            let mut cpu_copy = cpu.clone();
+            cpu.step(&mut memory).unwrap();
+
+            let actual_value: u8 = memory.read(address);
+
+            // Flags are not affected
+            assert_eq!(actual_value, 0b1111_0011 & 0x10);
+            cpu_copy.pc = length;
+            cpu_copy.cycles += cycles;
+            assert_cpu(&cpu, &cpu_copy);
+        }
+    }
+
+    #[test]
+    fn test_anc() {
+        // (opcode, a, value, expected a, expected carry)
+        let cases = [
+            (0x0Bu8, 0xF0u8, 0x81u8, 0x80u8, true),
+            (0x2B, 0x0F, 0x81, 0x01, false),
+            (0x0B, 0x0F, 0xF0, 0x00, false),
+        ];
+
+        for (opcode, a, value, result, carry) in cases {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, opcode);
+            memory.write(0x0001, value);
+            cpu.a = a;
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(info.instruction, Instruction::ANC_IM);
+            assert_eq!(info.cycles, 2);
+            assert_eq!(cpu.pc, 0x0002);
+            assert_eq!(cpu.a, result);
+            assert_eq!(cpu.get_carry(), carry);
+            assert_eq!(cpu.get_negative(), carry);
+            assert_eq!(cpu.get_zero(), result == 0);
+        }
+    }
+
+    #[test]
+    fn test_alr() {
+        // (a, value, expected a, expected carry)
+        let cases = [
+            (0xFFu8, 0x03u8, 0x01u8, true),
+            (0xF0, 0xFF, 0x78, false),
+            (0x01, 0x01, 0x00, true),
+        ];
+
+        for (a, value, result, carry) in cases {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, u8::from(Instruction::ALR_IM));
+            memory.write(0x0001, value);
+            cpu.a = a;
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(info.cycles, 2);
+            assert_eq!(cpu.a, result);
+            assert_eq!(cpu.get_carry(), carry);
+            assert_eq!(cpu.get_negative(), false);
+            assert_eq!(cpu.get_zero(), result == 0);
+        }
+    }
+
+    #[test]
+    fn test_arr() {
+        // (a, value, carry, decimal, expected a, carry, overflow, negative)
+        let cases = [
+            (0xFFu8, 0x7Fu8, true, false, 0xBFu8, false, true, true),
+            (0xC0, 0xFF, false, false, 0x60, true, false, false),
+            (0x99, 0xFF, false, true, 0xA2, true, true, false),
+        ];
+
+        for (a, value, carry_in, decimal, result, carry, overflow, negative) in cases {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, u8::from(Instruction::ARR_IM));
+            memory.write(0x0001, value);
+            cpu.a = a;
+            cpu.set_carry(carry_in);
+            cpu.set_decimal_mode(decimal);
+            cpu.set_overflow(false);
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(info.cycles, 2);
+            assert_eq!(cpu.a, result);
+            assert_eq!(cpu.get_carry(), carry);
+            assert_eq!(cpu.get_overflow(), overflow);
+            assert_eq!(cpu.get_negative(), negative);
+            assert_eq!(cpu.get_zero(), false);
+        }
+    }
+
+    #[test]
+    fn test_sbx() {
+        // (a, x, value, expected x, expected carry)
+        let cases = [
+            (0xF0u8, 0x3Cu8, 0x10u8, 0x20u8, true),
+            (0xF0, 0x3C, 0x40, 0xF0, false),
+            (0xFF, 0x42, 0x42, 0x00, true),
+        ];
+
+        for (a, x, value, result, carry) in cases {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            cpu.reset(&mut memory);
+
+            memory.write(0x0000, u8::from(Instruction::SBX_IM));
+            memory.write(0x0001, value);
+            cpu.a = a;
+            cpu.x = x;
+            // SBX ignores decimal mode
+            cpu.set_decimal_mode(true);
+
+            let info = cpu.step(&mut memory).unwrap();
+
+            assert_eq!(info.cycles, 2);
+            assert_eq!(cpu.a, a);
+            assert_eq!(cpu.x, result);
+            assert_eq!(cpu.get_carry(), carry);
+            assert_eq!(cpu.get_zero(), result == 0);
+            assert_eq!(cpu.get_negative(), (result as i8) < 0);
+        }
+    }
+
+    #[test]
+    fn test_sbc_undocumented_alias() {
+        assert_eq!(Instruction::from(0xEB), Instruction::SBC_IM);
+    }
+
+    #[test]
+    fn test_undocumented_nop() {
+        // (opcodes, length, cycles)
+        let nops: [(&[u8], u16, u64); 6] = [
+            (&[0x1A, 0x3A, 0x5A, 0x7A, 0xDA, 0xFA], 1, 2),
+            (&[0x80, 0x82, 0x89, 0xC2, 0xE2], 2, 2),
+            (&[0x04, 0x44, 0x64], 2, 3),
+            (&[0x14, 0x34, 0x54, 0x74, 0xD4, 0xF4], 2, 4),
+            (&[0x0C], 3, 4),
+            (&[0x1C, 0x3C, 0x5C, 0x7C, 0xDC, 0xFC], 3, 4),
+        ];
+
+        for (opcodes, length, cycles) in nops {
+            for opcode in opcodes {
+                let mut cpu = CPU::new(CpuVariant::Nmos6502);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+
+                cpu.reset(&mut memory);
+
+                memory.write(0x0000, *opcode);
+                memory.write(0x0001, 0x0030u16);
+                cpu.x = 0x10;
+
+                let mut cpu_copy = cpu.clone();
+                cpu.step(&mut memory).unwrap();
+
+                cpu_copy.pc = length;
+                cpu_copy.cycles += cycles;
+                assert_cpu(&cpu, &cpu_copy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nop_absolute_x_page_crossing() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+
+        memory.write(0x0000, u8::from(Instruction::NOP_ABS_X));
+        memory.write(0x0001, 0x02ffu16);
+        cpu.x = 0x01;
+
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.cycles, 5);
+    }
+
+    #[test]
+    fn test_jam() {
+        let opcodes = [
+            0x02u8, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+        ];
+
+        for opcode in opcodes {
+            let mut cpu = CPU::new(CpuVariant::Nmos6502);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+
+            memory.write(0x0000, opcode);
+            memory.write(0x0001, u8::from(Instruction::INX));
+
+            cpu.reset(&mut memory);
+            let result = cpu.step(&mut memory);
+
+            assert_eq!(result, Err(CpuError::Jammed { address: 0x0000 }));
+            assert!(cpu.is_jammed());
+
+            // Nothing but reset gets the CPU going again
+            cpu.trigger_nmi();
+            let result = cpu.run_for_cycles(&mut memory, 10);
+
+            assert_eq!(result, Err(CpuError::Jammed { address: 0x0000 }));
+            assert!(cpu.is_jammed());
+            assert_eq!(cpu.x, 0);
+
+            cpu.reset(&mut memory);
+            assert!(!cpu.is_jammed());
+        }
+    }
+
+    #[test]
+    fn test_variant_decode_undocumented() {
+        // Undocumented NMOS opcodes do not leak into the 65C02
+        let opcodes = [
+            (0x02u8, Instruction::JAM, Instruction::NOP_IM),
+            (0x03, Instruction::SLO_IN_X, Instruction::NOP_FAST),
+            (0x0B, Instruction::ANC_IM, Instruction::NOP_FAST),
+            (0xA7, Instruction::LAX_ZP, Instruction::SMB2),
+            (0xEB, Instruction::SBC_IM, Instruction::NOP_FAST),
+            (0x44, Instruction::NOP_ZP, Instruction::NOP_ZP),
+            (0x5C, Instruction::NOP_ABS_X, Instruction::NOP_SLOW),
+            (0xDC, Instruction::NOP_ABS_X, Instruction::NOP_ABS),
+        ];
+
+        for (opcode, nmos, cmos) in opcodes {
+            assert_eq!(Instruction::decode(opcode, CpuVariant::Nmos6502), nmos);
+            assert_eq!(Instruction::decode(opcode, CpuVariant::Ricoh2A03), nmos);
+            assert_eq!(Instruction::decode(opcode, CpuVariant::Cmos65C02), cmos);
+        }
+    }
+
+    #[test]
+    fn test_cmos_reserved_nops() {
+        // (opcode, length, cycles), X crosses a page for $DC and $FC
+        let opcodes = [
+            (0x03u8, 1u16, 1u64),
+            (0x0B, 1, 1),
+            (0xEB, 1, 1),
+            (0xFB, 1, 1),
+            (0x02, 2, 2),
+            (0xE2, 2, 2),
+            (0x44, 2, 3),
+            (0x54, 2, 4),
+            (0xF4, 2, 4),
+            (0xDC, 3, 4),
+            (0xFC, 3, 4),
+            (0x5C, 3, 8),
+        ];
+
+        for cycle_accurate in [false, true] {
+            for (opcode, length, cycles) in opcodes {
+                let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+                memory.write(0x0200, opcode);
+                memory.write(0x0201, 0x12f0u16);
+                cpu.pc = 0x0200;
+                cpu.x = 0xff;
+                cpu.set_cycle_accurate(cycle_accurate);
+
+                let info = cpu.step(&mut memory).unwrap();
+
+                assert_eq!(cpu.pc, 0x0200 + length, "opcode ${:02X}", opcode);
+                assert_eq!(info.cycles, cycles, "opcode ${:02X}", opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_illegal_opcode() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0000, u8::from(Instruction::INX));
+        memory.write(0x0001, 0x8Bu8);
+
+        cpu.reset(&mut memory);
+        cpu.step(&mut memory).unwrap();
+        let cycles = cpu.cycles;
+        let result = cpu.step(&mut memory);
+
+        assert_eq!(
+            result,
+            Err(CpuError::IllegalOpcode {
+                address: 0x0001,
+                opcode: 0x8B
+            })
+        );
+        assert_eq!(cpu.pc, 0x0001);
+        assert_eq!(cpu.cycles, cycles);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "illegal opcode $8B at $0001"
+        );
+    }
+
+    #[test]
+    fn test_execute_illegal_opcode() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0010, 0xABu8);
+        cpu.pc = 0x0010;
+
+        let instruction = cpu.fetch_instruction(&mut memory);
+        let result = cpu.execute(&mut memory, instruction);
+
+        assert_eq!(
+            result,
+            Err(CpuError::IllegalOpcode {
+                address: 0x0010,
+                opcode: 0xAB
+            })
+        );
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        for i in 0..4 {
+            memory.write(i, u8::from(Instruction::INX));
+        }
+
+        cpu.reset(&mut memory);
+        cpu.add_breakpoint(0x0002);
+
+        let result = cpu.run_for_cycles(&mut memory, 100);
+
+        assert_eq!(
+            result,
+            Err(CpuError::Breakpoint {
+                address: 0x0002,
+                interrupt: None
+            })
+        );
+        assert_eq!(cpu.pc, 0x0002);
+        assert_eq!(cpu.x, 2);
+
+        // Stepping again executes the instruction under the breakpoint
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.pc, 0x0002);
+        assert_eq!(cpu.x, 3);
+
+        cpu.pc = 0x0002;
+        assert_eq!(
+            cpu.step(&mut memory),
+            Err(CpuError::Breakpoint {
+                address: 0x0002,
+                interrupt: None
+            })
+        );
+
+        cpu.remove_breakpoint(0x0002);
+        cpu.step(&mut memory).unwrap();
+        cpu.pc = 0x0002;
+        cpu.step(&mut memory).unwrap();
+        assert_eq!(cpu.x, 5);
+    }
+
+    #[test]
+    fn test_breakpoint_interrupt() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        for i in 0..4 {
+            memory.write(i, u8::from(Instruction::INX));
+        }
+        memory.write(0x0300, u8::from(Instruction::INY));
+        memory.write(0xfffe, 0x00u8);
+        memory.write(0xffff, 0x03u8);
+
+        cpu.reset(&mut memory);
+        cpu.add_breakpoint(0x0002);
+        cpu.add_breakpoint(0x0300);
+        cpu.run_for_cycles(&mut memory, 100).unwrap_err();
+
+        // The IRQ taken while resuming from the breakpoint at $0002 leads
+        // into the handler, whose breakpoint is reported with the interrupt
+        cpu.set_interrupt_disable(false);
+        cpu.set_irq_line(true);
+        let cycles = cpu.get_cycles();
+        assert_eq!(
+            cpu.step(&mut memory),
+            Err(CpuError::Breakpoint {
+                address: 0x0300,
+                interrupt: Some(Interrupt::Irq)
+            })
+        );
+        assert_eq!(cpu.get_cycles() - cycles, 7);
+        assert_eq!(cpu.x, 2);
+
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.pc, 0x0300);
+        assert_eq!(info.interrupt, None);
+        assert_eq!(cpu.y, 1);
+    }
+
+    // Minimal machine: RAM below $D000, a raster counter at $D012 that
+    // advances on every read and ROM from $E000 that ignores writes
+    struct RasterMachine {
+        memory: Memory,
+        raster_line: Byte,
+    }
+
+    impl Bus for RasterMachine {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            if addr == 0xd012 {
+                self.raster_line = self.raster_line.wrapping_add(1);
+                return self.raster_line;
+            }
+            return self.memory.read_byte(addr);
+        }
+
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            if addr < 0xe000 {
+                self.memory.write_byte(addr, value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_bus() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut machine = RasterMachine {
+            memory: Memory {
+                ..Default::default()
+            },
+            raster_line: 0x30,
+        };
+
+        let program = [
+            u8::from(Instruction::LDA_ABS),
+            0x12,
+            0xd0,
+            u8::from(Instruction::LDX_ABS),
+            0x12,
+            0xd0,
+            u8::from(Instruction::STA_ABS),
+            0x00,
+            0xe0,
+        ];
+        for (i, byte) in program.iter().enumerate() {
+            machine.memory.write_byte(0x0200 + i as Word, *byte);
+        }
+        machine.memory.write_word(0xfffc, 0x0200);
+        machine.memory.write_byte(0xe000, 0x99);
+
+        cpu.reset(&mut machine);
+        cpu.run_for_cycles(&mut machine, 12).unwrap();
+
+        assert_eq!(cpu.a, 0x31);
+        assert_eq!(cpu.x, 0x32);
+        assert_eq!(cpu.pc, 0x0209);
+        let rom: u8 = machine.read(0xe000);
+        assert_eq!(rom, 0x99);
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut rom = [0u8; 0x2000];
+        let program = [
+            u8::from(Instruction::LDA_IM),
+            0x42,
+            u8::from(Instruction::STA_ABS),
+            0x10,
+            0x08,
+            u8::from(Instruction::STA_ABS),
+            0x00,
+            0xe0,
+            u8::from(Instruction::LDX_ZP),
+            0x10,
+        ];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x1ffc] = 0x00;
+        rom[0x1ffd] = 0xe0;
+        let mut memory = MemoryMap::new()
+            .mirrored_ram(0x0000, 0x1fff, 0x0800)
+            .rom(0xe000, 0xffff, &rom);
+
+        cpu.reset(&mut memory);
+        cpu.run_for_cycles(&mut memory, 13).unwrap();
+
+        assert_eq!(cpu.x, 0x42);
+        assert_eq!(cpu.pc, 0xe00a);
+        assert_eq!(memory.read_byte(0xe000), u8::from(Instruction::LDA_IM));
+    }
+
+    #[test]
+    fn test_irq() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        cpu.pc = 0x1234;
+        cpu.set_interrupt_disable(false);
+        cpu.set_carry(true);
+        cpu.set_break_command(true);
+
+        let irq_addr = 0x4321u16;
+        memory.write(0xfffe, irq_addr);
+        memory.write_byte(irq_addr, Instruction::NOP.into());
+
+        let status = cpu.get_status();
+
+        cpu.set_irq_line(true);
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.interrupt, Some(Interrupt::Irq));
+        assert_eq!(info.pc, irq_addr);
+        assert_eq!(info.instruction, Instruction::NOP);
+        assert_eq!(info.cycles, 7 + 2);
+        assert_eq!(cpu.pc, irq_addr + 1);
+        assert_eq!(cpu.sp, 0xff - 3);
+        assert_eq!(cpu.get_interrupt_disable(), true);
+
+        let stack_status: u8 = memory.read(0x100u16 + cpu.sp as u16 + 1);
+        let stack_pc: u16 = memory.read(0x100u16 + cpu.sp as u16 + 2);
+        assert_eq!(stack_status, status & !(1 << 4));
+        assert_eq!(stack_pc, 0x1234);
+
+        // Line is still asserted, but interrupts are now disabled
+        let info = cpu.step(&mut memory).unwrap();
+        assert_eq!(info.interrupt, None);
+    }
+
+    #[test]
+    fn test_irq_disabled() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        cpu.set_interrupt_disable(true);
+        memory.write(0xfffe, 0x4321u16);
+        memory.write_byte(0x0000, Instruction::NOP.into());
+
+        cpu.set_irq_line(true);
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.interrupt, None);
+        assert_eq!(info.cycles, 2);
+        assert_eq!(cpu.pc, 0x0001);
+        assert_eq!(cpu.sp, 0xff);
+    }
+
+    #[test]
+    fn test_nmi() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        cpu.reset(&mut memory);
+        cpu.sp = 0xff;
+        cpu.pc = 0x1234;
+        cpu.set_interrupt_disable(true);
+
+        let nmi_addr = 0x5678u16;
+        memory.write(0xfffa, nmi_addr);
+        memory.write(0xfffe, 0x4321u16);
+        memory.write_byte(nmi_addr, Instruction::NOP.into());
+        memory.write_byte(nmi_addr + 1, Instruction::NOP.into());
+
+        let status = cpu.get_status();
+
+        cpu.trigger_nmi();
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(info.interrupt, Some(Interrupt::Nmi));
+        assert_eq!(info.pc, nmi_addr);
+        assert_eq!(info.cycles, 7 + 2);
+        assert_eq!(cpu.sp, 0xff - 3);
+
+        let stack_status: u8 = memory.read(0x100u16 + cpu.sp as u16 + 1);
+        let stack_pc: u16 = memory.read(0x100u16 + cpu.sp as u16 + 2);
+        assert_eq!(stack_status, status & !(1 << 4));
+        assert_eq!(stack_pc, 0x1234);
+
+        // NMI is edge triggered, so it is not serviced again
+        let info = cpu.step(&mut memory).unwrap();
+        assert_eq!(info.interrupt, None);
+        assert_eq!(cpu.pc, nmi_addr + 2);
+    }
+
+    #[test]
+    fn test_interrupt_decimal_mode() {
+        // (variant, decimal mode left set in the handler)
+        let variants = [(CpuVariant::Nmos6502, true), (CpuVariant::Cmos65C02, false)];
+        // None enters the handler through BRK
+        let interrupts = [None, Some(Interrupt::Irq), Some(Interrupt::Nmi)];
+
+        for (variant, decimal) in variants {
+            for interrupt in interrupts {
+                let mut cpu = CPU::new(variant);
+                let mut memory = Memory {
+                    ..Default::default()
+                };
+                memory.write(0xfffa, 0x4000u16);
+                memory.write(0xfffe, 0x4000u16);
+                memory.write(0x1234, u8::from(Instruction::BRK));
+                memory.write(0x4000, u8::from(Instruction::NOP));
+                cpu.pc = 0x1234;
+                cpu.sp = 0xff;
+                cpu.set_interrupt_disable(false);
+                cpu.set_decimal_mode(true);
+                match interrupt {
+                    Some(Interrupt::Irq) => cpu.set_irq_line(true),
+                    Some(Interrupt::Nmi) => cpu.trigger_nmi(),
+                    None => {}
+                }
+
+                let info = cpu.step(&mut memory).unwrap();
+
+                assert_eq!(info.interrupt, interrupt);
+                assert_eq!(
+                    cpu.get_decimal_mode(),
+                    decimal,
+                    "{:?} {:?}",
+                    variant,
+                    interrupt
+                );
+                // The handler can still see the interrupted code's mode
+                let stack_status: u8 = memory.read(0x01fd);
+                assert_eq!(stack_status & 0b0000_1000, 0b0000_1000);
+            }
+
+            let mut cpu = CPU::new(variant);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+            cpu.set_decimal_mode(true);
+            cpu.reset(&mut memory);
+            assert_eq!(cpu.get_decimal_mode(), decimal, "{:?} reset", variant);
+        }
+    }
+
+    #[test]
+    fn test_rdy_line() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let accesses = Rc::new(RefCell::new(Vec::new()));
+        let sink = accesses.clone();
+        cpu.set_bus_observer(Some(BusObserver::new(move |cycle: BusCycle| {
+            sink.borrow_mut().push(cycle.addr)
+        })));
+        cpu.set_cycle_accurate(true);
+
+        cpu.pc = 0x0200;
+        cpu.sp = 0xff;
+        memory.write(0x0200, u8::from(Instruction::INX));
+        memory.write(0xfffa, 0x5678u16);
+        memory.write(0x5678, u8::from(Instruction::NOP));
+
+        // Every step burns a cycle repeating the opcode fetch, the NMI stays
+        // pending until RDY is high again
+        cpu.set_rdy_line(false);
+        cpu.trigger_nmi();
+        for _ in 0..3 {
+            let info = cpu.step(&mut memory).unwrap();
+            assert!(info.stalled);
+            assert_eq!(info.cycles, 1);
+        }
+        assert!(!cpu.get_rdy_line());
+        assert_eq!(cpu.pc, 0x0200);
+        assert_eq!(cpu.cycles, 3);
+        assert_eq!(*accesses.borrow(), vec![0x0200; 3]);
+
+        cpu.set_rdy_line(true);
+        let info = cpu.step(&mut memory).unwrap();
+        assert!(!info.stalled);
+        assert_eq!(info.interrupt, Some(Interrupt::Nmi));
+        assert_eq!(cpu.pc, 0x5679);
+        assert_eq!(cpu.cycles, 3 + 7 + 2);
+        assert_eq!(accesses.borrow().len(), 3 + 7 + 2);
+    }
+
+    #[test]
+    fn test_rdy_source() {
+        let read = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Read,
+        };
+        let write = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Write,
+        };
+        // (variant, samples with RDY low) => bus cycles of INC $10
+        let cases = [
+            // Reads are repeated on both chips
+            (
+                CpuVariant::Nmos6502,
+                3..=4,
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                ],
+            ),
+            (
+                CpuVariant::Cmos65C02,
+                3..=4,
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                ],
+            ),
+            // NMOS writes finish regardless of RDY, the 65C02 holds them
+            (
+                CpuVariant::Nmos6502,
+                5..=5,
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                ],
+            ),
+            (
+                CpuVariant::Cmos65C02,
+                5..=5,
+                vec![
+                    read(0x0200, 0xe6),
+                    read(0x0201, 0x10),
+                    read(0x0010, 0x7f),
+                    read(0x0010, 0x7f),
+                    write(0x0010, 0x80),
+                    write(0x0010, 0x80),
+                ],
+            ),
+        ];
+
+        for (variant, low, expected) in cases {
+            let mut cpu = CPU::new(variant);
+            let mut memory = Memory {
+                ..Default::default()
+            };
+            cpu.pc = 0x0200;
+            memory.write(0x0200, u8::from(Instruction::INC_ZP));
+            memory.write(0x0201, 0x10u8);
+            memory.write(0x0010, 0x7fu8);
+            let mut samples = 0;
+            cpu.set_rdy_source(Some(RdySource::new(move || {
+                samples += 1;
+                return !low.contains(&samples);
+            })));
+
+            let (bus_cycles, cycles) = step_bus_cycles(&mut cpu, &mut memory);
+
+            assert_eq!(bus_cycles, expected, "{:?}", variant);
+            assert_eq!(cycles, expected.len() as u64, "{:?}", variant);
+            assert_eq!(memory.read_byte(0x0010), 0x80);
+        }
+
+        // Without cycle accurate mode the source is not sampled
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write(0x0000, u8::from(Instruction::INX));
+        cpu.set_rdy_source(Some(RdySource::new(|| false)));
+        assert_eq!(cpu.step(&mut memory).unwrap().cycles, 2);
+    }
+
+    #[test]
+    fn test_so_line() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+
+        // Only the falling edge sets overflow
+        assert!(cpu.get_so_line());
+        cpu.set_so_line(false);
+        assert!(cpu.get_overflow());
+
+        cpu.set_overflow(false);
+        cpu.set_so_line(false);
+        assert!(!cpu.get_overflow());
+        cpu.set_so_line(true);
+        assert!(!cpu.get_overflow());
+
+        cpu.set_so_line(false);
+        assert!(cpu.get_overflow());
+    }
+
+    #[test]
+    fn test_halt() {
+        let mut cpu = CPU {
+            ..Default::default()
+        };
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0000, u8::from(Instruction::INX));
+
+        cpu.halt();
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.run_for_cycles(&mut memory, 4).unwrap(), 4);
+        assert_eq!((cpu.pc, cpu.x), (0x0000, 0x00));
+
+        // RDY going high does not resume a halted CPU
+        cpu.set_rdy_line(false);
+        cpu.set_rdy_line(true);
+        assert!(cpu.step(&mut memory).unwrap().stalled);
+
+        cpu.resume();
+        let info = cpu.step(&mut memory).unwrap();
+        assert!(!info.stalled);
+        assert_eq!((cpu.pc, cpu.x), (0x0001, 0x01));
+        assert_eq!(cpu.cycles, 4 + 1 + 2);
+    }
+}
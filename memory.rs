@@ -1,4 +1,10 @@
+#[allow(clippy::module_inception)]
 pub mod memory {
+    use crate::save_state::save_state::{SaveStateError, Snapshot, RAM};
+    use std::cell::RefCell;
+    use std::fmt;
+    use std::rc::Rc;
+
     pub type Byte = u8;
     pub type Word = u16;
 
@@ -12,7 +18,7 @@ pub mod memory {
         return sum as u16;
     }
 
-    #[derive(PartialEq, Eq, Hash, Debug)]
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
     pub enum AddressingMode {
         Implied,
         Immediate,
@@ -23,267 +29,558 @@ pub mod memory {
         Indirect,
         IndirectX,
         IndirectY,
+        // 65C02 (zp)
+        ZeroPageIndirect,
+        // 65C02 JMP (abs,X)
+        AbsoluteIndirectX,
+        // ASL A and friends, only used when describing instructions
+        Accumulator,
+        // Branches, signed offset from the next instruction
+        Relative,
+        // 65C02 BBR/BBS: zero page address followed by a branch offset
+        ZeroPageRelative,
+    }
+
+    // Anything the CPU can be attached to. Only single byte accesses are
+    // required, reads take &mut self so devices can react to being read
+    // (e.g. clearing a flag or returning the current raster line).
+    // MemoryLike and the addressing helpers below are built on top of it
+    pub trait Bus {
+        fn read_byte(&mut self, addr: Word) -> Byte;
+        fn write_byte(&mut self, addr: Word, value: Byte);
+
+        // Value a read would return without its side effects, for tracers
+        // and debuggers. Observers don't see it and devices don't react to
+        // it. The default reads through read_byte, buses whose reads have
+        // side effects override it
+        fn peek_byte(&mut self, addr: Word) -> Byte {
+            return self.read_byte(addr);
+        }
+
+        // Little endian word, the high byte address wraps at $FFFF
+        fn read_word(&mut self, addr: Word) -> Word {
+            let low = self.read_byte(addr);
+            let high = self.read_byte(addr.wrapping_add(1));
+            return ((high as u16) << 8) | low as u16;
+        }
+
+        fn write_word(&mut self, addr: Word, value: Word) {
+            self.write_byte(addr, (value & 0x00ff) as u8);
+            self.write_byte(addr.wrapping_add(1), ((value & 0xff00) >> 8) as u8)
+        }
+
+        // Fills the RAM behind the bus with the pattern on power on
+        // without going through the address lines. ROM, devices and buses
+        // without RAM of their own keep their contents
+        fn fill_ram(&mut self, _pattern: &mut RamPattern) {}
+
+        // Pointers never leave zero page, one at $FF takes its high byte
+        // from $00
+        fn read_zero_page_word(&mut self, addr: Byte) -> Word {
+            let low = self.read_byte(addr as u16);
+            let high = self.read_byte(addr.wrapping_add(1) as u16);
+            return ((high as u16) << 8) | low as u16;
+        }
+
+        // Resolves the operand at pc to the address the instruction
+        // accesses and advances pc past the operand. `index` is the X or Y
+        // register used by the indexed modes. The flag is set when indexing
+        // or a branch offset moved the address to another page. Modes
+        // without an operand resolve to pc, the byte single byte
+        // instructions read and ignore. Indirect follows the pointer like
+        // the 65C02 does, the NMOS page wrap is left to the CPU
+        fn effective_address(
+            &mut self,
+            pc: &mut Word,
+            mode: AddressingMode,
+            index: Byte,
+        ) -> (Word, bool) {
+            let (base, addr, length) = match mode {
+                AddressingMode::Implied | AddressingMode::Accumulator => (*pc, *pc, 0),
+                AddressingMode::Immediate => (*pc, *pc, 1),
+                AddressingMode::ZeroPage => {
+                    let addr = self.read_byte(*pc) as u16;
+                    (addr, addr, 1)
+                }
+                // Indexing wraps around within zero page
+                AddressingMode::ZeroPageReg => {
+                    let addr = add_mod_256(self.read_byte(*pc), index) as u16;
+                    (addr, addr, 1)
+                }
+                AddressingMode::Absolute => {
+                    let addr = self.read_word(*pc);
+                    (addr, addr, 2)
+                }
+                AddressingMode::AbsoluteReg => {
+                    let base = self.read_word(*pc);
+                    (base, base.wrapping_add(index as u16), 2)
+                }
+                AddressingMode::IndirectX => {
+                    let addr_zp = add_mod_256(self.read_byte(*pc), index);
+                    let addr = self.read_zero_page_word(addr_zp);
+                    (addr, addr, 1)
+                }
+                AddressingMode::IndirectY => {
+                    let addr_zp = self.read_byte(*pc);
+                    let base = self.read_zero_page_word(addr_zp);
+                    (base, base.wrapping_add(index as u16), 1)
+                }
+                AddressingMode::ZeroPageIndirect => {
+                    let addr_zp = self.read_byte(*pc);
+                    let addr = self.read_zero_page_word(addr_zp);
+                    (addr, addr, 1)
+                }
+                AddressingMode::Indirect => {
+                    let pointer = self.read_word(*pc);
+                    let addr = self.read_word(pointer);
+                    (addr, addr, 2)
+                }
+                AddressingMode::AbsoluteIndirectX => {
+                    let pointer = self.read_word(*pc).wrapping_add(index as u16);
+                    let addr = self.read_word(pointer);
+                    (addr, addr, 2)
+                }
+                // Offset from the address of the next instruction
+                AddressingMode::Relative => {
+                    let offset = self.read_byte(*pc) as i8;
+                    let next = pc.wrapping_add(1);
+                    (next, next.wrapping_add(offset as u16), 1)
+                }
+                // Only the zero page address, the branch offset after it
+                // is a separate Relative operand
+                AddressingMode::ZeroPageRelative => {
+                    let addr = self.read_byte(*pc) as u16;
+                    (addr, addr, 1)
+                }
+            };
+            *pc = pc.wrapping_add(length);
+
+            return (addr, (base & 0xff00) != (addr & 0xff00));
+        }
+
+        fn write_zero_page(&mut self, pc: &mut Word, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPage, 0);
+            self.write_byte(addr, value);
+        }
+
+        fn write_zero_page_x(&mut self, pc: &mut Word, x: u8, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPageReg, x);
+            self.write_byte(addr, value);
+        }
+
+        fn write_absolute(&mut self, pc: &mut Word, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::Absolute, 0);
+            self.write_byte(addr, value);
+        }
+
+        fn write_absolute_x(&mut self, pc: &mut Word, x: u8, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::AbsoluteReg, x);
+            self.write_byte(addr, value);
+        }
+
+        fn write_indirect_x(&mut self, pc: &mut Word, x: u8, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::IndirectX, x);
+            self.write_byte(addr, value);
+        }
+
+        fn write_indirect_y(&mut self, pc: &mut Word, y: u8, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::IndirectY, y);
+            self.write_byte(addr, value);
+        }
+
+        fn write_zero_page_indirect(&mut self, pc: &mut Word, value: u8) {
+            let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPageIndirect, 0);
+            self.write_byte(addr, value);
+        }
+
+        fn read_zero_page_indirect(&mut self, pc: &mut Word) -> u8 {
+            let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPageIndirect, 0);
+            return self.read_byte(addr);
+        }
+
+        fn read_immediate(&mut self, pc: &mut Word) -> u8 {
+            let (addr, _) = self.effective_address(pc, AddressingMode::Immediate, 0);
+            return self.read_byte(addr);
+        }
     }
 
     pub trait MemoryLike<T> {
-        fn read(&self, addr: Word) -> T;
+        fn read(&mut self, addr: Word) -> T;
         fn write(&mut self, addr: Word, value: T);
-        fn read_zero_page(&self, pc: &mut Word) -> T;
-        fn read_zero_page_x(&self, pc: &mut Word, x: Byte) -> T;
-        fn read_absolute(&self, pc: &mut Word) -> T;
-        fn read_absolute_x(&self, pc: &mut Word, x: Byte) -> T;
+        fn read_zero_page(&mut self, pc: &mut Word) -> T;
+        fn read_zero_page_x(&mut self, pc: &mut Word, x: Byte) -> T;
+        fn read_absolute(&mut self, pc: &mut Word) -> T;
+        fn read_absolute_x(&mut self, pc: &mut Word, x: Byte) -> T;
         fn read_absolute_x_check_crossing(
-            &self,
+            &mut self,
             pc: &mut Word,
             x: Byte,
             page_crossed: &mut bool,
         ) -> T;
-        fn read_indirect_x(&self, pc: &mut Word, x: Byte) -> T;
-        fn read_indirect_y(&self, pc: &mut Word, y: Byte) -> T;
+        fn read_indirect_x(&mut self, pc: &mut Word, x: Byte) -> T;
+        fn read_indirect_y(&mut self, pc: &mut Word, y: Byte) -> T;
         fn read_indirect_y_check_crossing(
-            &self,
+            &mut self,
             pc: &mut Word,
             y: Byte,
             page_crossed: &mut bool,
         ) -> T;
     }
 
+    // Both widths resolve the operand the same way and only differ in how
+    // much they read at the resulting address
+    macro_rules! memory_like {
+        ($type: ty) => {
+            fn read_zero_page(&mut self, pc: &mut Word) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPage, 0);
+                return self.read(addr);
+            }
+
+            fn read_zero_page_x(&mut self, pc: &mut Word, x: Byte) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::ZeroPageReg, x);
+                return self.read(addr);
+            }
+
+            fn read_absolute(&mut self, pc: &mut Word) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::Absolute, 0);
+                return self.read(addr);
+            }
+
+            fn read_absolute_x(&mut self, pc: &mut Word, x: Byte) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::AbsoluteReg, x);
+                return self.read(addr);
+            }
+
+            fn read_absolute_x_check_crossing(
+                &mut self,
+                pc: &mut Word,
+                x: Byte,
+                page_crossed: &mut bool,
+            ) -> $type {
+                let (addr, crossed) = self.effective_address(pc, AddressingMode::AbsoluteReg, x);
+                *page_crossed = crossed;
+                return self.read(addr);
+            }
+
+            fn read_indirect_x(&mut self, pc: &mut Word, x: Byte) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::IndirectX, x);
+                return self.read(addr);
+            }
+
+            fn read_indirect_y(&mut self, pc: &mut Word, y: Byte) -> $type {
+                let (addr, _) = self.effective_address(pc, AddressingMode::IndirectY, y);
+                return self.read(addr);
+            }
+
+            fn read_indirect_y_check_crossing(
+                &mut self,
+                pc: &mut Word,
+                y: Byte,
+                page_crossed: &mut bool,
+            ) -> $type {
+                let (addr, crossed) = self.effective_address(pc, AddressingMode::IndirectY, y);
+                *page_crossed = crossed;
+                return self.read(addr);
+            }
+        };
+    }
+
+    impl<B: Bus + ?Sized> MemoryLike<u8> for B {
+        fn read(&mut self, addr: Word) -> u8 {
+            return self.read_byte(addr);
+        }
+
+        fn write(&mut self, addr: Word, value: u8) {
+            self.write_byte(addr, value);
+        }
+
+        memory_like! {u8}
+    }
+
+    impl<B: Bus + ?Sized> MemoryLike<u16> for B {
+        fn read(&mut self, addr: Word) -> u16 {
+            return self.read_word(addr);
+        }
+
+        fn write(&mut self, addr: Word, value: u16) {
+            self.write_word(addr, value);
+        }
+
+        memory_like! {u16}
+    }
+
     #[derive(Debug, Clone, Copy)]
     pub struct Memory {
         pub(crate) ram: [Byte; 0x10000],
     }
 
-    impl MemoryLike<u8> for Memory {
-        fn read(&self, addr: Word) -> u8 {
+    impl Bus for Memory {
+        fn read_byte(&mut self, addr: Word) -> Byte {
             return self.ram[addr as usize];
         }
 
-        fn write(&mut self, addr: Word, value: u8) {
+        fn write_byte(&mut self, addr: Word, value: Byte) {
             self.ram[addr as usize] = value;
         }
 
-        fn read_zero_page(&self, pc: &mut Word) -> u8 {
-            let addr_zp: u8 = self.read(*pc);
-            let value = self.read(addr_zp as u16);
-            *pc += 1;
-
-            return value;
+        fn fill_ram(&mut self, pattern: &mut RamPattern) {
+            pattern.fill(&mut self.ram);
         }
+    }
 
-        fn read_zero_page_x(&self, pc: &mut Word, x: Byte) -> u8 {
-            let addr_zp = self.read(*pc);
-            let addr_final = add_mod_256(addr_zp, x);
-            let value = self.read(addr_final as u16);
-            *pc += 1;
+    impl Snapshot for Memory {
+        fn save_state(&self) -> Vec<Byte> {
+            return self.ram.to_vec();
+        }
 
-            return value;
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            if data.len() != self.ram.len() {
+                return Err(SaveStateError::InvalidSection { tag: RAM });
+            }
+            return Ok(());
         }
 
-        fn read_absolute(&self, pc: &mut Word) -> u8 {
-            let addr = self.read(*pc);
-            let value = self.read(addr);
-            *pc += 2;
+        fn load_state(&mut self, data: &[Byte]) {
+            self.ram.copy_from_slice(data);
+        }
+    }
 
-            return value;
+    impl Memory {
+        // Fills ram with pseudo random values, the same seed always
+        // produces the same contents
+        pub fn fill_random(&mut self, seed: u64) {
+            RamPattern::new(seed).fill(&mut self.ram);
         }
+    }
 
-        fn read_absolute_x(&self, pc: &mut Word, x: Byte) -> u8 {
-            let addr = self.read(*pc);
-            let addr_final = add_mod_65536(addr, x as u16);
-            let value = self.read(addr_final);
-            *pc += 2;
+    // Pseudo random power on contents (xorshift64*). Filling several
+    // blocks from one pattern continues the sequence instead of repeating
+    // it in every block
+    pub struct RamPattern {
+        state: u64,
+    }
 
-            return value;
+    impl RamPattern {
+        pub fn new(seed: u64) -> Self {
+            return RamPattern { state: seed | 1 };
         }
 
-        fn read_absolute_x_check_crossing(
-            &self,
-            pc: &mut Word,
-            x: Byte,
-            page_crossed: &mut bool,
-        ) -> u8 {
-            let addr = self.read(*pc);
-            let addr_final = add_mod_65536(addr, x as u16);
-            let value = self.read(addr_final);
-            *pc += 2;
+        pub fn fill(&mut self, data: &mut [Byte]) {
+            for byte in data.iter_mut() {
+                self.state ^= self.state >> 12;
+                self.state ^= self.state << 25;
+                self.state ^= self.state >> 27;
+                *byte = (self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 56) as u8;
+            }
+        }
+    }
 
-            *page_crossed = addr_final > 0xff;
+    // Drops the address lines a chip does not have, e.g. the 6507 only
+    // sees 8 KiB mirrored 8 times over the 64 KiB address space
+    pub struct MaskedBus<'a, B: Bus + ?Sized> {
+        bus: &'a mut B,
+        mask: Word,
+    }
 
-            return value;
+    impl<'a, B: Bus + ?Sized> MaskedBus<'a, B> {
+        pub fn new(bus: &'a mut B, mask: Word) -> Self {
+            return MaskedBus { bus, mask };
         }
+    }
 
-        fn read_indirect_x(&self, pc: &mut Word, x: Byte) -> u8 {
-            let addr = self.read(*pc);
-            let addr_zp = add_mod_256(addr, x);
-            let addr_final = self.read(addr_zp as u16);
-            let value = self.read(addr_final);
-            *pc += 1;
+    impl<B: Bus + ?Sized> Bus for MaskedBus<'_, B> {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            return self.bus.read_byte(addr & self.mask);
+        }
 
-            return value;
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            self.bus.write_byte(addr & self.mask, value);
         }
 
-        fn read_indirect_y(&self, pc: &mut Word, y: Byte) -> u8 {
-            let addr: u8 = self.read(*pc);
-            let addr_on_zp = self.read(addr as u16);
-            let addr_final = add_mod_65536(addr_on_zp, y as u16);
-            let value = self.read(addr_final);
-            *pc += 1;
+        fn peek_byte(&mut self, addr: Word) -> Byte {
+            return self.bus.peek_byte(addr & self.mask);
+        }
 
-            return value;
+        fn fill_ram(&mut self, pattern: &mut RamPattern) {
+            self.bus.fill_ram(pattern);
         }
+    }
 
-        fn read_indirect_y_check_crossing(
-            &self,
-            pc: &mut Word,
-            y: Byte,
-            page_crossed: &mut bool,
-        ) -> u8 {
-            let addr: u8 = self.read(*pc);
-            let addr_on_zp = self.read(addr as u16);
-            let addr_final = add_mod_65536(addr_on_zp, y as u16);
-            let value = self.read(addr_final);
-            *pc += 1;
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub enum BusAccess {
+        Read,
+        Write,
+    }
 
-            *page_crossed = addr_final > 0xff;
+    // A single access as it appears on the address and data pins
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct BusCycle {
+        pub addr: Word,
+        pub value: Byte,
+        pub access: BusAccess,
+    }
 
-            return value;
-        }
+    type Callback = dyn FnMut(BusCycle);
+
+    // Receives every access made through an ObservedBus. Clones share the
+    // same callback
+    #[derive(Clone)]
+    pub struct BusObserver {
+        callback: Rc<RefCell<Callback>>,
     }
 
-    impl MemoryLike<u16> for Memory {
-        fn read(&self, addr: Word) -> u16 {
-            return ((self.ram[(addr + 1) as usize] as u16) << 8) | self.ram[addr as usize] as u16;
+    impl BusObserver {
+        pub fn new<F: FnMut(BusCycle) + 'static>(callback: F) -> Self {
+            return BusObserver {
+                callback: Rc::new(RefCell::new(callback)),
+            };
         }
 
-        fn write(&mut self, addr: Word, value: u16) {
-            self.write(addr + 0, (value & 0x00ff) as u8);
-            self.write(addr + 1, ((value & 0xff00) >> 8) as u8)
+        pub fn observe(&self, cycle: BusCycle) {
+            (self.callback.borrow_mut())(cycle);
         }
+    }
 
-        fn read_zero_page(&self, pc: &mut Word) -> u16 {
-            let addr_zp: u8 = self.read(*pc);
-            let value = self.read(addr_zp as u16);
-            *pc += 1;
-
-            return value;
+    impl fmt::Debug for BusObserver {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return f.write_str("BusObserver");
         }
+    }
 
-        fn read_zero_page_x(&self, pc: &mut Word, x: Byte) -> u16 {
-            let addr_zp = self.read(*pc);
-            let addr_final = add_mod_256(addr_zp, x);
-            let value = self.read(addr_final as u16);
-            *pc += 1;
+    // Passes accesses on to the wrapped bus and reports them to the
+    // observer, if there is one
+    pub struct ObservedBus<'a, B: Bus + ?Sized> {
+        bus: &'a mut B,
+        observer: Option<BusObserver>,
+    }
 
-            return value;
+    impl<'a, B: Bus + ?Sized> ObservedBus<'a, B> {
+        pub fn new(bus: &'a mut B, observer: Option<BusObserver>) -> Self {
+            return ObservedBus { bus, observer };
         }
+    }
 
-        fn read_absolute(&self, pc: &mut Word) -> u16 {
-            let addr = self.read(*pc);
-            let value = self.read(addr);
-            *pc += 2;
+    impl<B: Bus + ?Sized> Bus for ObservedBus<'_, B> {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            let value = self.bus.read_byte(addr);
+            if let Some(observer) = &self.observer {
+                observer.observe(BusCycle {
+                    addr,
+                    value,
+                    access: BusAccess::Read,
+                });
+            }
 
             return value;
         }
 
-        fn read_absolute_x(&self, pc: &mut Word, x: Byte) -> u16 {
-            let addr = self.read(*pc);
-            let addr_final = add_mod_65536(addr, x as u16);
-            let value = self.read(addr_final);
-            *pc += 2;
-
-            return value;
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            if let Some(observer) = &self.observer {
+                observer.observe(BusCycle {
+                    addr,
+                    value,
+                    access: BusAccess::Write,
+                });
+            }
+            self.bus.write_byte(addr, value);
         }
 
-        fn read_absolute_x_check_crossing(
-            &self,
-            _pc: &mut Word,
-            _x: Byte,
-            _page_crossed: &mut bool,
-        ) -> u16 {
-            todo!();
+        fn peek_byte(&mut self, addr: Word) -> Byte {
+            return self.bus.peek_byte(addr);
         }
 
-        fn read_indirect_x(&self, _pc: &mut Word, _x: Byte) -> u16 {
-            todo!();
+        fn fill_ram(&mut self, pattern: &mut RamPattern) {
+            self.bus.fill_ram(pattern);
         }
+    }
 
-        fn read_indirect_y(&self, _pc: &mut Word, _y: Byte) -> u16 {
-            todo!();
-        }
+    type RdyCallback = dyn FnMut() -> bool;
 
-        fn read_indirect_y_check_crossing(
-            &self,
-            _pc: &mut Word,
-            _y: Byte,
-            _page_crossed: &mut bool,
-        ) -> u16 {
-            todo!();
-        }
+    // Level of the RDY line, sampled once per cycle. Returns true while the
+    // line is high. Lets a DMA controller pull RDY low in the middle of an
+    // instruction. Clones share the same callback
+    #[derive(Clone)]
+    pub struct RdySource {
+        callback: Rc<RefCell<RdyCallback>>,
     }
 
-    impl Memory {
-        pub fn write_byte(&mut self, addr: Word, value: Byte) {
-            self.ram[addr as usize] = value;
+    impl RdySource {
+        pub fn new<F: FnMut() -> bool + 'static>(callback: F) -> Self {
+            return RdySource {
+                callback: Rc::new(RefCell::new(callback)),
+            };
         }
 
-        pub fn write_word(&mut self, addr: Word, value: Word) {
-            self.write_byte(addr + 0, (value & 0x00ff) as u8);
-            self.write_byte(addr + 1, ((value & 0xff00) >> 8) as u8)
+        pub fn is_high(&self) -> bool {
+            return (self.callback.borrow_mut())();
         }
+    }
 
-        pub fn write_zero_page(&mut self, pc: &mut Word, value: u8) {
-            let addr: u8 = self.read(*pc);
-            self.write(addr as u16, value);
-
-            *pc += 1;
+    impl fmt::Debug for RdySource {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return f.write_str("RdySource");
         }
+    }
 
-        pub fn write_zero_page_x(&mut self, pc: &mut Word, x: u8, value: u8) {
-            let addr: u8 = self.read(*pc);
-            let addr_final = add_mod_256(addr, x);
-            self.write(addr_final as u16, value);
+    // Samples RDY before every access and repeats the access on the wrapped
+    // bus for as long as the line is low, like the chip does with a stalled
+    // cycle. NMOS chips finish writes whatever the level, stall_writes
+    // makes writes wait as well like on the 65C02
+    pub struct RdyBus<'a, B: Bus + ?Sized> {
+        bus: &'a mut B,
+        rdy: Option<RdySource>,
+        stall_writes: bool,
+        stalls: u64,
+    }
 
-            *pc += 1;
+    impl<'a, B: Bus + ?Sized> RdyBus<'a, B> {
+        pub fn new(bus: &'a mut B, rdy: Option<RdySource>, stall_writes: bool) -> Self {
+            return RdyBus {
+                bus,
+                rdy,
+                stall_writes,
+                stalls: 0,
+            };
         }
 
-        pub fn write_absolute(&mut self, pc: &mut Word, value: u8) {
-            let addr: u16 = self.read(*pc);
-            self.write(addr, value);
-
-            *pc += 2;
+        // Number of repeated accesses so far
+        pub fn get_stalls(&self) -> u64 {
+            return self.stalls;
         }
 
-        pub fn write_absolute_x(&mut self, pc: &mut Word, x: u8, value: u8) {
-            let addr: u16 = self.read(*pc);
-            let addr_final = add_mod_65536(addr, x as u16);
-            self.write(addr_final, value);
-
-            *pc += 2;
+        fn stalled(&self, write: bool) -> bool {
+            return match &self.rdy {
+                Some(rdy) => !rdy.is_high() && (!write || self.stall_writes),
+                None => false,
+            };
         }
+    }
 
-        pub fn write_indirect_x(&mut self, pc: &mut Word, x: u8, value: u8) {
-            let addr: u8 = self.read(*pc);
-            let addr_zp = add_mod_256(addr, x);
-            let addr_final: u16 = self.read(addr_zp as u16);
-            self.write(addr_final, value);
+    impl<B: Bus + ?Sized> Bus for RdyBus<'_, B> {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            while self.stalled(false) {
+                self.bus.read_byte(addr);
+                self.stalls += 1;
+            }
 
-            *pc += 1;
+            return self.bus.read_byte(addr);
         }
 
-        pub fn write_indirect_y(&mut self, pc: &mut Word, y: u8, value: u8) {
-            let addr: u8 = self.read(*pc);
-            let addr_on_zp: u16 = self.read(addr as u16);
-            let addr_final = add_mod_65536(addr_on_zp, y as u16);
-            self.write(addr_final, value);
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            while self.stalled(true) {
+                self.bus.write_byte(addr, value);
+                self.stalls += 1;
+            }
 
-            *pc += 1;
+            self.bus.write_byte(addr, value);
         }
 
-        pub fn read_immediate(&self, pc: &mut Word) -> u8 {
-            let value = self.read(*pc);
-            *pc += 1;
+        fn peek_byte(&mut self, addr: Word) -> Byte {
+            return self.bus.peek_byte(addr);
+        }
 
-            return value;
+        fn fill_ram(&mut self, pattern: &mut RamPattern) {
+            self.bus.fill_ram(pattern);
         }
     }
 
@@ -298,7 +595,12 @@ pub mod memory {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Memory, MemoryLike};
+    use crate::memory::memory::{
+        BusAccess, BusCycle, BusObserver, Memory, ObservedBus, RdyBus, RdySource,
+    };
+    use crate::{AddressingMode, Bus, MemoryLike};
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     #[test]
     fn test_read_u8() {
@@ -500,8 +802,8 @@ mod tests {
             ..Default::default()
         };
 
-        let mut pcs = [0x0000u16, 0xfffd, 0xABCD, 0x5648];
-        let pcs_init = [0x0000u16, 0xfffd, 0xABCD, 0x5648];
+        let mut pcs = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let pcs_init = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
         let addresses = [0x05u8, 0xff, 0x14, 0x54];
         let addresses_on_zp = [0x1015u16, 0xABDC, 0x6342, 0x9999];
         let y_addresses = [0x10u8, 0x05, 0x52, 0x42];
@@ -510,7 +812,12 @@ mod tests {
 
         for i in 0..4 {
             memory.write(pcs[i], addresses[i]);
-            memory.write(addresses[i] as u16, addresses_on_zp[i]);
+            // the pointer at $FF takes its high byte from $00
+            memory.write(addresses[i] as u16, addresses_on_zp[i] as u8);
+            memory.write(
+                addresses[i].wrapping_add(1) as u16,
+                (addresses_on_zp[i] >> 8) as u8,
+            );
         }
 
         for i in 0..4 {
@@ -777,8 +1084,8 @@ mod tests {
             ..Default::default()
         };
 
-        let mut pcs = [0x0000u16, 0xfffd, 0xABCD, 0x5648];
-        let pcs_init = [0x0000u16, 0xfffd, 0xABCD, 0x5648];
+        let mut pcs = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let pcs_init = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
         let addresses = [0x05u8, 0xff, 0x14, 0x54];
         let addresses_on_zp = [0x1015u16, 0xABDC, 0x6342, 0x9999];
         let y_addresses = [0x10u8, 0x05, 0x52, 0x42];
@@ -787,8 +1094,13 @@ mod tests {
 
         for i in 0..4 {
             memory.write(pcs[i], addresses[i]);
-            memory.write(addresses[i] as u16, addresses_on_zp[i]);
-            memory.write(addresses_sum[i] as u16, values[i]);
+            // the pointer at $FF takes its high byte from $00
+            memory.write(addresses[i] as u16, addresses_on_zp[i] as u8);
+            memory.write(
+                addresses[i].wrapping_add(1) as u16,
+                (addresses_on_zp[i] >> 8) as u8,
+            );
+            memory.write(addresses_sum[i], values[i]);
         }
 
         for i in 0..4 {
@@ -798,4 +1110,240 @@ mod tests {
             assert_eq!(pcs[i], pcs_init[i] + 1);
         }
     }
+
+    #[test]
+    fn test_read_indirect_x_u16() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        let mut pcs = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let pcs_init = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let addresses = [0x05u8, 0xfe, 0x14, 0x54];
+        let x_addresses = [0x10u8, 0x01, 0x52, 0x42];
+        let addresses_sum = [0x15u8, 0xff, 0x66, 0x96];
+        let addresses_on_zp = [0x1015u16, 0xABDC, 0x6342, 0x9999];
+        let values = [0xffeeu16, 0x0000, 0xab12, 0x3164];
+
+        for i in 0..4 {
+            memory.write(pcs[i], addresses[i]);
+            memory.write(addresses_sum[i] as u16, addresses_on_zp[i] as u8);
+            memory.write(
+                addresses_sum[i].wrapping_add(1) as u16,
+                (addresses_on_zp[i] >> 8) as u8,
+            );
+            memory.write(addresses_on_zp[i], values[i]);
+        }
+
+        for i in 0..4 {
+            let value: u16 = memory.read_indirect_x(&mut pcs[i], x_addresses[i]);
+
+            assert_eq!(value, values[i]);
+            assert_eq!(pcs[i], pcs_init[i] + 1);
+        }
+    }
+
+    #[test]
+    fn test_read_indirect_y_u16() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        let mut pcs = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let pcs_init = [0x0200u16, 0xfffd, 0xABCD, 0x5648];
+        let addresses = [0x05u8, 0xff, 0x14, 0x54];
+        let addresses_on_zp = [0x1015u16, 0xABDC, 0x6342, 0x9999];
+        let y_addresses = [0x10u8, 0x05, 0xc2, 0x42];
+        let addresses_sum = [0x1025u16, 0xABE1, 0x6404, 0x99DB];
+        let values = [0xffeeu16, 0x0000, 0xab12, 0x3164];
+        let mut page_crossed = false;
+
+        for i in 0..4 {
+            memory.write(pcs[i], addresses[i]);
+            memory.write(addresses[i] as u16, addresses_on_zp[i] as u8);
+            memory.write(
+                addresses[i].wrapping_add(1) as u16,
+                (addresses_on_zp[i] >> 8) as u8,
+            );
+            memory.write(addresses_sum[i], values[i]);
+        }
+
+        for i in 0..4 {
+            let value: u16 = memory.read_indirect_y_check_crossing(
+                &mut pcs[i],
+                y_addresses[i],
+                &mut page_crossed,
+            );
+
+            assert_eq!(value, values[i]);
+            assert_eq!(page_crossed, i == 2);
+            assert_eq!(pcs[i], pcs_init[i] + 1);
+        }
+    }
+
+    #[test]
+    fn test_effective_address() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+
+        memory.write(0x0010u16, 0x0480u16);
+        memory.write(0x00ffu16, 0x34u8);
+        memory.write(0x0000u16, 0x12u8);
+        memory.write(0x0100u16, 0x56u8);
+
+        // (operand, mode, index, address, page crossed, pc increment)
+        let cases = [
+            (
+                0x10u16,
+                AddressingMode::Immediate,
+                0x00u8,
+                0x0200u16,
+                false,
+                1u16,
+            ),
+            (0x10, AddressingMode::ZeroPage, 0x00, 0x0010, false, 1),
+            (0xf0, AddressingMode::ZeroPageReg, 0x20, 0x0010, false, 1),
+            (0x12fe, AddressingMode::Absolute, 0x00, 0x12fe, false, 2),
+            (0x12fe, AddressingMode::AbsoluteReg, 0x01, 0x12ff, false, 2),
+            (0x12fe, AddressingMode::AbsoluteReg, 0x02, 0x1300, true, 2),
+            (0xfffe, AddressingMode::AbsoluteReg, 0x03, 0x0001, true, 2),
+            (0x08, AddressingMode::IndirectX, 0x08, 0x0480, false, 1),
+            (0xef, AddressingMode::IndirectX, 0x10, 0x1234, false, 1),
+            (0x10, AddressingMode::IndirectY, 0x7f, 0x04ff, false, 1),
+            (0x10, AddressingMode::IndirectY, 0x80, 0x0500, true, 1),
+            (0xff, AddressingMode::IndirectY, 0x00, 0x1234, false, 1),
+            (
+                0xff,
+                AddressingMode::ZeroPageIndirect,
+                0x00,
+                0x1234,
+                false,
+                1,
+            ),
+            (0x10, AddressingMode::Implied, 0x00, 0x0200, false, 0),
+            (0x10, AddressingMode::Accumulator, 0x00, 0x0200, false, 0),
+            (0x0010, AddressingMode::Indirect, 0x00, 0x0480, false, 2),
+            (
+                0x0008,
+                AddressingMode::AbsoluteIndirectX,
+                0x08,
+                0x0480,
+                false,
+                2,
+            ),
+            (0x10, AddressingMode::Relative, 0x00, 0x0211, false, 1),
+            (0xf0, AddressingMode::Relative, 0x00, 0x01f1, true, 1),
+            (
+                0x10,
+                AddressingMode::ZeroPageRelative,
+                0x00,
+                0x0010,
+                false,
+                1,
+            ),
+        ];
+
+        for (operand, mode, index, address, crossed, pc_increment) in cases {
+            let mut pc = 0x0200u16;
+            memory.write(pc, operand);
+
+            assert_eq!(
+                memory.effective_address(&mut pc, mode, index),
+                (address, crossed),
+                "{:?} with operand {:#06x} and index {:#04x}",
+                mode,
+                operand,
+                index
+            );
+            assert_eq!(pc, 0x0200 + pc_increment);
+        }
+    }
+
+    #[test]
+    fn test_observed_bus() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let cycles = Rc::new(RefCell::new(Vec::new()));
+        let sink = cycles.clone();
+        let observer = BusObserver::new(move |cycle| sink.borrow_mut().push(cycle));
+
+        memory.write(0x1234u16, 0x56u8);
+        let mut bus = ObservedBus::new(&mut memory, Some(observer));
+        let value: u8 = bus.read(0x1234);
+        bus.write(0x0010u16, 0xbeefu16);
+        assert_eq!(value, 0x56);
+
+        let mut bus = ObservedBus::new(&mut memory, None);
+        let _: u8 = bus.read(0x0010);
+
+        let read = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Read,
+        };
+        let write = |addr, value| BusCycle {
+            addr,
+            value,
+            access: BusAccess::Write,
+        };
+        assert_eq!(
+            *cycles.borrow(),
+            vec![read(0x1234, 0x56), write(0x0010, 0xef), write(0x0011, 0xbe)]
+        );
+        assert_eq!(memory.ram[0x0010..0x0012], [0xef, 0xbe]);
+    }
+
+    #[test]
+    fn test_rdy_bus() {
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let cycles = Rc::new(RefCell::new(Vec::new()));
+        let sink = cycles.clone();
+        let observer =
+            BusObserver::new(move |cycle: BusCycle| sink.borrow_mut().push(cycle.access));
+        // Low for the second and third sample
+        let mut samples = 0;
+        let rdy = RdySource::new(move || {
+            samples += 1;
+            return !(2..=3).contains(&samples);
+        });
+
+        // NMOS: the write sampled low goes through, the read after it waits
+        let mut observed = ObservedBus::new(&mut memory, Some(observer.clone()));
+        let mut bus = RdyBus::new(&mut observed, Some(rdy.clone()), false);
+        let _: u8 = bus.read(0x0010);
+        bus.write(0x0010u16, 0x42u8);
+        let _: u8 = bus.read(0x0011);
+        assert_eq!(bus.get_stalls(), 1);
+        assert_eq!(
+            *cycles.borrow(),
+            vec![
+                BusAccess::Read,
+                BusAccess::Write,
+                BusAccess::Read,
+                BusAccess::Read
+            ]
+        );
+
+        // 65C02: writes wait as well
+        cycles.borrow_mut().clear();
+        let mut samples = 0;
+        let rdy = RdySource::new(move || {
+            samples += 1;
+            return samples != 2;
+        });
+        let mut observed = ObservedBus::new(&mut memory, Some(observer));
+        let mut bus = RdyBus::new(&mut observed, Some(rdy), true);
+        let _: u8 = bus.read(0x0010);
+        bus.write(0x0010u16, 0x43u8);
+        assert_eq!(bus.get_stalls(), 1);
+        assert_eq!(
+            *cycles.borrow(),
+            vec![BusAccess::Read, BusAccess::Write, BusAccess::Write]
+        );
+        assert_eq!(memory.ram[0x0010], 0x43);
+    }
 }
@@ -0,0 +1,385 @@
+#[allow(clippy::module_inception)]
+pub mod memory_map {
+    use crate::memory::memory::{Bus, Byte, RamPattern, Word};
+    use crate::save_state::save_state::{SaveStateError, Snapshot, RAM};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Handler for a memory mapped device. Offsets are relative to the
+    // first address of the region the device is mapped at
+    pub trait Device {
+        fn read(&mut self, offset: Word) -> Byte;
+        fn write(&mut self, offset: Word, value: Byte);
+        // Value a read would return without its side effects, see
+        // Bus::peek_byte. None leaves the open bus value
+        fn peek(&mut self, _offset: Word) -> Option<Byte> {
+            return None;
+        }
+    }
+
+    // Lets the caller keep a handle on a device after mapping it, e.g. to
+    // advance its internal clock between CPU steps
+    impl<D: Device> Device for Rc<RefCell<D>> {
+        fn read(&mut self, offset: Word) -> Byte {
+            return self.borrow_mut().read(offset);
+        }
+
+        fn write(&mut self, offset: Word, value: Byte) {
+            self.borrow_mut().write(offset, value);
+        }
+
+        fn peek(&mut self, offset: Word) -> Option<Byte> {
+            return self.borrow_mut().peek(offset);
+        }
+    }
+
+    enum RegionKind {
+        // Contents repeat every data.len() bytes across the region
+        Ram(Vec<Byte>),
+        Rom(Vec<Byte>),
+        Device(Box<dyn Device>),
+        Unmapped,
+    }
+
+    struct Region {
+        start: Word,
+        end: Word,
+        kind: RegionKind,
+    }
+
+    impl Region {
+        fn contains(&self, addr: Word) -> bool {
+            return self.start <= addr && addr <= self.end;
+        }
+    }
+
+    // Address space assembled from regions. Regions added later take
+    // precedence over earlier ones they overlap, so holes can be punched
+    // with unmapped. Reads from addresses no region claims return the last
+    // value seen on the data bus, like an undriven bus on real hardware
+    pub struct MemoryMap {
+        regions: Vec<Region>,
+        open_bus: Byte,
+    }
+
+    impl MemoryMap {
+        pub fn new() -> Self {
+            return MemoryMap {
+                regions: Vec::new(),
+                open_bus: 0,
+            };
+        }
+
+        pub fn ram(self, start: Word, end: Word) -> Self {
+            return self.mirrored_ram(start, end, end as usize - start as usize + 1);
+        }
+
+        // RAM of `size` bytes repeated over start..=end, e.g. the 2 KiB of
+        // NES work RAM visible four times in $0000-$1FFF
+        pub fn mirrored_ram(self, start: Word, end: Word, size: usize) -> Self {
+            assert!(size > 0, "RAM size must not be zero");
+            return self.add(start, end, RegionKind::Ram(vec![0; size]));
+        }
+
+        // Read-only region, writes are ignored. Contents shorter than the
+        // region are mirrored, e.g. a 16 KiB NROM-128 image in $8000-$FFFF
+        pub fn rom(self, start: Word, end: Word, data: &[Byte]) -> Self {
+            assert!(!data.is_empty(), "ROM contents must not be empty");
+            return self.add(start, end, RegionKind::Rom(data.to_vec()));
+        }
+
+        pub fn device(self, start: Word, end: Word, device: Box<dyn Device>) -> Self {
+            return self.add(start, end, RegionKind::Device(device));
+        }
+
+        pub fn unmapped(self, start: Word, end: Word) -> Self {
+            return self.add(start, end, RegionKind::Unmapped);
+        }
+
+        fn add(mut self, start: Word, end: Word, kind: RegionKind) -> Self {
+            assert!(start <= end, "Region ${:04X}-${:04X} is empty", start, end);
+            self.regions.push(Region { start, end, kind });
+            return self;
+        }
+
+        // Copies data into the RAM regions starting at addr, bytes landing
+        // on ROM, devices or unmapped addresses are dropped. Meant for
+        // loading programs without going through device handlers
+        pub fn load(&mut self, addr: Word, data: &[Byte]) {
+            for (i, byte) in data.iter().enumerate() {
+                let addr = addr.wrapping_add(i as Word);
+                if let Some(region) = self.find(addr) {
+                    if let RegionKind::Ram(ram) = &mut region.kind {
+                        let len = ram.len();
+                        ram[(addr - region.start) as usize % len] = *byte;
+                    }
+                }
+            }
+        }
+
+        pub fn get_open_bus(&self) -> Byte {
+            return self.open_bus;
+        }
+
+        fn find(&mut self, addr: Word) -> Option<&mut Region> {
+            return self.regions.iter_mut().rev().find(|r| r.contains(addr));
+        }
+    }
+
+    // The open bus value followed by the contents of every RAM region in
+    // the order they were added. ROM can't change and devices are saved
+    // on their own
+    impl Snapshot for MemoryMap {
+        fn save_state(&self) -> Vec<Byte> {
+            let mut out = vec![self.open_bus];
+            for region in &self.regions {
+                if let RegionKind::Ram(data) = &region.kind {
+                    out.extend_from_slice(data);
+                }
+            }
+            return out;
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            let len: usize = self
+                .regions
+                .iter()
+                .map(|region| match &region.kind {
+                    RegionKind::Ram(data) => data.len(),
+                    _ => 0,
+                })
+                .sum();
+            if data.len() != 1 + len {
+                return Err(SaveStateError::InvalidSection { tag: RAM });
+            }
+            return Ok(());
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            self.open_bus = data[0];
+            let mut rest = &data[1..];
+            for region in &mut self.regions {
+                if let RegionKind::Ram(ram) = &mut region.kind {
+                    let len = ram.len();
+                    ram.copy_from_slice(&rest[..len]);
+                    rest = &rest[len..];
+                }
+            }
+        }
+    }
+
+    impl Default for MemoryMap {
+        fn default() -> Self {
+            return MemoryMap::new();
+        }
+    }
+
+    impl Bus for MemoryMap {
+        fn read_byte(&mut self, addr: Word) -> Byte {
+            let open_bus = self.open_bus;
+            let value = match self.find(addr) {
+                Some(region) => {
+                    let offset = addr - region.start;
+                    match &mut region.kind {
+                        RegionKind::Ram(data) | RegionKind::Rom(data) => {
+                            data[offset as usize % data.len()]
+                        }
+                        RegionKind::Device(device) => device.read(offset),
+                        RegionKind::Unmapped => open_bus,
+                    }
+                }
+                None => open_bus,
+            };
+            self.open_bus = value;
+
+            return value;
+        }
+
+        fn write_byte(&mut self, addr: Word, value: Byte) {
+            self.open_bus = value;
+            if let Some(region) = self.find(addr) {
+                let offset = addr - region.start;
+                match &mut region.kind {
+                    RegionKind::Ram(data) => {
+                        let len = data.len();
+                        data[offset as usize % len] = value;
+                    }
+                    RegionKind::Device(device) => device.write(offset, value),
+                    RegionKind::Rom(_) | RegionKind::Unmapped => {}
+                }
+            }
+        }
+
+        // Leaves the open bus value alone, devices that can't peek read as
+        // open bus
+        fn peek_byte(&mut self, addr: Word) -> Byte {
+            let open_bus = self.open_bus;
+            return match self.find(addr) {
+                Some(region) => {
+                    let offset = addr - region.start;
+                    match &mut region.kind {
+                        RegionKind::Ram(data) | RegionKind::Rom(data) => {
+                            data[offset as usize % data.len()]
+                        }
+                        RegionKind::Device(device) => device.peek(offset).unwrap_or(open_bus),
+                        RegionKind::Unmapped => open_bus,
+                    }
+                }
+                None => open_bus,
+            };
+        }
+
+        // RAM regions are filled in the order they were added
+        fn fill_ram(&mut self, pattern: &mut RamPattern) {
+            for region in &mut self.regions {
+                if let RegionKind::Ram(data) = &mut region.kind {
+                    pattern.fill(data);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::memory::{Bus, Byte, Word};
+    use crate::memory_map::memory_map::{Device, MemoryMap};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Registers {
+        values: [Byte; 4],
+        reads: u32,
+    }
+
+    impl Device for Registers {
+        fn read(&mut self, offset: Word) -> Byte {
+            self.reads += 1;
+            return self.values[offset as usize];
+        }
+
+        fn write(&mut self, offset: Word, value: Byte) {
+            self.values[offset as usize] = value;
+        }
+    }
+
+    #[test]
+    fn test_ram() {
+        let mut map = MemoryMap::new().ram(0x0000, 0x7fff);
+
+        let addresses = [0x0000u16, 0x00ff, 0x1234, 0x7fff];
+        let values = [0x12u8, 0xff, 0x00, 0xab];
+
+        for i in 0..4 {
+            map.write_byte(addresses[i], values[i]);
+        }
+        for i in 0..4 {
+            assert_eq!(map.read_byte(addresses[i]), values[i]);
+        }
+    }
+
+    #[test]
+    fn test_mirrored_ram() {
+        let mut map = MemoryMap::new().mirrored_ram(0x0000, 0x1fff, 0x0800);
+
+        map.write_byte(0x0012, 0x42);
+        map.write_byte(0x1fff, 0x69);
+
+        for mirror in [0x0000u16, 0x0800, 0x1000, 0x1800] {
+            assert_eq!(map.read_byte(mirror + 0x0012), 0x42);
+            assert_eq!(map.read_byte(mirror + 0x07ff), 0x69);
+        }
+    }
+
+    #[test]
+    fn test_rom() {
+        let rom = [0xeau8, 0x4c, 0x00, 0x80];
+        let mut map = MemoryMap::new()
+            .ram(0x0000, 0x7fff)
+            .rom(0x8000, 0xffff, &rom);
+
+        map.write_byte(0x8000, 0x00);
+
+        assert_eq!(map.read_byte(0x8000), 0xea);
+        assert_eq!(map.read_byte(0x8001), 0x4c);
+        // Mirrored every 4 bytes
+        assert_eq!(map.read_byte(0xfffc), 0xea);
+        assert_eq!(map.read_byte(0xffff), 0x80);
+    }
+
+    #[test]
+    fn test_unmapped_open_bus() {
+        let mut map = MemoryMap::new()
+            .ram(0x0000, 0xffff)
+            .unmapped(0x4000, 0x5fff);
+
+        map.write_byte(0x0010, 0x33);
+        map.write_byte(0x4000, 0x77);
+
+        assert_eq!(map.read_byte(0x0010), 0x33);
+        assert_eq!(map.read_byte(0x4000), 0x33);
+        assert_eq!(map.read_byte(0x3fff), 0x00);
+        assert_eq!(map.read_byte(0x5fff), 0x00);
+        assert_eq!(map.get_open_bus(), 0x00);
+    }
+
+    #[test]
+    fn test_device() {
+        let registers = Rc::new(RefCell::new(Registers {
+            values: [0x01, 0x02, 0x03, 0x04],
+            reads: 0,
+        }));
+        let device = Box::new(registers.clone());
+        let mut map = MemoryMap::new()
+            .ram(0x0000, 0xffff)
+            .device(0xd010, 0xd013, device);
+
+        map.write_byte(0xd011, 0x99);
+
+        assert_eq!(map.read_byte(0xd010), 0x01);
+        assert_eq!(map.read_byte(0xd011), 0x99);
+        assert_eq!(map.read_byte(0xd013), 0x04);
+        assert_eq!(map.read_byte(0xd014), 0x00);
+        assert_eq!(registers.borrow().reads, 3);
+        assert_eq!(registers.borrow().values[1], 0x99);
+    }
+
+    #[test]
+    fn test_peek() {
+        let registers = Rc::new(RefCell::new(Registers {
+            values: [0x01, 0x02, 0x03, 0x04],
+            reads: 0,
+        }));
+        let device = Box::new(registers.clone());
+        let rom = [0xeau8];
+        let mut map = MemoryMap::new()
+            .ram(0x0000, 0x0fff)
+            .device(0xd010, 0xd013, device)
+            .rom(0xe000, 0xffff, &rom);
+
+        map.write_byte(0x0010, 0x42);
+        // Registers doesn't implement peek, so it reads as open bus
+        assert_eq!(map.peek_byte(0xd010), 0x42);
+        assert_eq!(map.peek_byte(0x0010), 0x42);
+        assert_eq!(map.peek_byte(0xe123), 0xea);
+        assert_eq!(map.peek_byte(0x2000), 0x42);
+        assert_eq!(registers.borrow().reads, 0);
+        assert_eq!(map.get_open_bus(), 0x42);
+    }
+
+    #[test]
+    fn test_load() {
+        let rom = [0xffu8];
+        let mut map = MemoryMap::new()
+            .mirrored_ram(0x0000, 0x1fff, 0x0800)
+            .rom(0xe000, 0xffff, &rom);
+
+        map.load(0x0ffe, &[0x01, 0x02, 0x03]);
+        map.load(0xe000, &[0x00]);
+
+        assert_eq!(map.read_byte(0x07fe), 0x01);
+        assert_eq!(map.read_byte(0x07ff), 0x02);
+        assert_eq!(map.read_byte(0x0000), 0x03);
+        assert_eq!(map.read_byte(0xe000), 0xff);
+    }
+}
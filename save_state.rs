@@ -0,0 +1,641 @@
+#[allow(clippy::module_inception)]
+pub mod save_state {
+    use crate::memory::memory::{Byte, Word};
+    use crate::status::status::StatusFlags;
+    use crate::{CpuVariant, CPU};
+    use std::cell::RefCell;
+    use std::fmt;
+    use std::rc::Rc;
+
+    // Layout, all integers little endian:
+    //   header   magic "6502SAVE", version u16, section count u16
+    //   section  tag [u8; 4], length u32, payload, repeated
+    //   trailer  CRC-32 of everything before it
+    // The register block and the memory come first, device sections follow
+    // in the order the devices were passed in. Only machine state is saved:
+    // breakpoints, the tracer, the bus observer, the RDY source and cycle
+    // accurate mode are host settings and stay as they are on load
+    const MAGIC: [Byte; 8] = *b"6502SAVE";
+    pub const VERSION: u16 = 1;
+
+    pub const REGISTERS: [Byte; 4] = *b"REGS";
+    pub const RAM: [Byte; 4] = *b"RAM ";
+    pub const DEVICE: [Byte; 4] = *b"DEV ";
+
+    const HEADER_LEN: usize = MAGIC.len() + 2 + 2;
+    const CHECKSUM_LEN: usize = 4;
+    const REGISTERS_LEN: usize = 8 + 2 + 5 + 1 + 2;
+
+    // Bits of the flag word in the register block
+    const IRQ_LINE: u16 = 1 << 0;
+    const NMI_PENDING: u16 = 1 << 1;
+    const DECIMAL_ENABLED: u16 = 1 << 2;
+    const WAITING: u16 = 1 << 3;
+    const STOPPED: u16 = 1 << 4;
+    const JAMMED: u16 = 1 << 5;
+    const RDY_LINE: u16 = 1 << 6;
+    const SO_LINE: u16 = 1 << 7;
+    const HALTED: u16 = 1 << 8;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SaveStateError {
+        BadMagic,
+        UnsupportedVersion { version: u16 },
+        // Data ends before the header, a section or the checksum does
+        Truncated,
+        ChecksumMismatch { expected: u32, actual: u32 },
+        MissingSection { tag: [Byte; 4] },
+        // Section has the wrong size or holds values the target can't take
+        InvalidSection { tag: [Byte; 4] },
+        // Number of device sections differs from the devices passed in
+        DeviceCount { expected: usize, actual: usize },
+        // Bytes left over after the last section
+        TrailingData { len: usize },
+    }
+
+    impl fmt::Display for SaveStateError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return match self {
+                SaveStateError::BadMagic => write!(f, "not a save state"),
+                SaveStateError::UnsupportedVersion { version } => {
+                    write!(f, "unsupported save state version {}", version)
+                }
+                SaveStateError::Truncated => write!(f, "save state is truncated"),
+                SaveStateError::ChecksumMismatch { expected, actual } => write!(
+                    f,
+                    "checksum mismatch, expected {:08X} but got {:08X}",
+                    expected, actual
+                ),
+                SaveStateError::MissingSection { tag } => {
+                    write!(f, "missing section {}", String::from_utf8_lossy(tag))
+                }
+                SaveStateError::InvalidSection { tag } => {
+                    write!(f, "invalid section {}", String::from_utf8_lossy(tag))
+                }
+                SaveStateError::DeviceCount { expected, actual } => write!(
+                    f,
+                    "expected {} device sections but found {}",
+                    expected, actual
+                ),
+                SaveStateError::TrailingData { len } => {
+                    write!(f, "{} bytes after the last section", len)
+                }
+            };
+        }
+    }
+
+    impl std::error::Error for SaveStateError {}
+
+    // State a memory or device needs to carry across a save state, e.g.
+    // the bank registers of a mapper. The format is up to the implementor,
+    // it only ever gets back what it produced. Loading is split in two so
+    // that nothing changes unless every section is accepted
+    pub trait Snapshot {
+        fn save_state(&self) -> Vec<Byte>;
+        // Fails when data can't be loaded into self, without changing it
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError>;
+        // Only called with data check_state accepted
+        fn load_state(&mut self, data: &[Byte]);
+    }
+
+    // Devices are usually mapped as Rc<RefCell<D>> so a handle to them
+    // stays around for saving
+    impl<D: Snapshot> Snapshot for Rc<RefCell<D>> {
+        fn save_state(&self) -> Vec<Byte> {
+            return self.borrow().save_state();
+        }
+
+        fn check_state(&self, data: &[Byte]) -> Result<(), SaveStateError> {
+            return self.borrow().check_state(data);
+        }
+
+        fn load_state(&mut self, data: &[Byte]) {
+            self.borrow_mut().load_state(data);
+        }
+    }
+
+    // CRC-32 as used by zip and PNG
+    pub fn crc32(data: &[Byte]) -> u32 {
+        let mut crc = 0xffff_ffffu32;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+            }
+        }
+        return !crc;
+    }
+
+    // Tag and payload of one section
+    type Section<'a> = ([Byte; 4], &'a [Byte]);
+
+    fn push_section(out: &mut Vec<Byte>, tag: [Byte; 4], payload: &[Byte]) {
+        out.extend_from_slice(&tag);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+    }
+
+    fn encode_variant(variant: CpuVariant) -> Byte {
+        return match variant {
+            CpuVariant::Nmos6502 => 0,
+            CpuVariant::Cmos65C02 => 1,
+            CpuVariant::Ricoh2A03 => 2,
+            CpuVariant::Mos6507 => 3,
+        };
+    }
+
+    fn decode_variant(value: Byte) -> Option<CpuVariant> {
+        return match value {
+            0 => Some(CpuVariant::Nmos6502),
+            1 => Some(CpuVariant::Cmos65C02),
+            2 => Some(CpuVariant::Ricoh2A03),
+            3 => Some(CpuVariant::Mos6507),
+            _ => None,
+        };
+    }
+
+    fn encode_registers(cpu: &CPU) -> Vec<Byte> {
+        let flags = [
+            (cpu.irq_line, IRQ_LINE),
+            (cpu.nmi_pending, NMI_PENDING),
+            (cpu.decimal_enabled, DECIMAL_ENABLED),
+            (cpu.waiting, WAITING),
+            (cpu.stopped, STOPPED),
+            (cpu.jammed, JAMMED),
+            (cpu.rdy_line, RDY_LINE),
+            (cpu.so_line, SO_LINE),
+            (cpu.halted, HALTED),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0u16, |flags, (_, bit)| flags | bit);
+
+        let mut out = Vec::with_capacity(REGISTERS_LEN);
+        out.extend_from_slice(&cpu.cycles.to_le_bytes());
+        out.extend_from_slice(&cpu.pc.to_le_bytes());
+        out.extend_from_slice(&[
+            cpu.sp,
+            cpu.a,
+            cpu.x,
+            cpu.y,
+            Byte::from(cpu.status),
+            encode_variant(cpu.variant),
+        ]);
+        out.extend_from_slice(&flags.to_le_bytes());
+        return out;
+    }
+
+    // Only touches the CPU once the whole block is known to be valid
+    fn decode_registers(cpu: &mut CPU, data: &[Byte]) -> Result<(), SaveStateError> {
+        let invalid = SaveStateError::InvalidSection { tag: REGISTERS };
+        if data.len() != REGISTERS_LEN {
+            return Err(invalid);
+        }
+        let variant = decode_variant(data[15]).ok_or(invalid)?;
+        let flags = u16::from_le_bytes([data[16], data[17]]);
+
+        cpu.cycles = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        cpu.pc = Word::from_le_bytes([data[8], data[9]]);
+        cpu.sp = data[10];
+        cpu.a = data[11];
+        cpu.x = data[12];
+        cpu.y = data[13];
+        cpu.status = StatusFlags::from_bits(data[14]);
+        cpu.variant = variant;
+        cpu.irq_line = flags & IRQ_LINE != 0;
+        cpu.nmi_pending = flags & NMI_PENDING != 0;
+        cpu.decimal_enabled = flags & DECIMAL_ENABLED != 0;
+        cpu.waiting = flags & WAITING != 0;
+        cpu.stopped = flags & STOPPED != 0;
+        cpu.jammed = flags & JAMMED != 0;
+        cpu.rdy_line = flags & RDY_LINE != 0;
+        cpu.so_line = flags & SO_LINE != 0;
+        cpu.halted = flags & HALTED != 0;
+        // A breakpoint at the restored pc is reported like on a fresh start
+        cpu.breakpoint_hit = None;
+        return Ok(());
+    }
+
+    // Serializes the CPU, the memory, e.g. a Memory or a MemoryMap, and one
+    // section per device. Devices mapped into a MemoryMap are not part of
+    // its state and have to be passed separately
+    pub fn save_state<M: Snapshot + ?Sized>(
+        cpu: &CPU,
+        memory: &M,
+        devices: &[&dyn Snapshot],
+    ) -> Vec<Byte> {
+        let memory = memory.save_state();
+        let mut out = Vec::with_capacity(HEADER_LEN + memory.len() + 64);
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&(2 + devices.len() as u16).to_le_bytes());
+
+        push_section(&mut out, REGISTERS, &encode_registers(cpu));
+        push_section(&mut out, RAM, &memory);
+        for device in devices {
+            push_section(&mut out, DEVICE, &device.save_state());
+        }
+
+        let checksum = crc32(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+        return out;
+    }
+
+    // Splits a save state into its sections after checking the header and
+    // checksum
+    fn parse(data: &[Byte]) -> Result<Vec<Section<'_>>, SaveStateError> {
+        if data.len() < MAGIC.len() || data[..MAGIC.len()] != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+        if data.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(SaveStateError::Truncated);
+        }
+        let version = u16::from_le_bytes([data[8], data[9]]);
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion { version });
+        }
+
+        let (body, trailer) = data.split_at(data.len() - CHECKSUM_LEN);
+        let expected = u32::from_le_bytes(trailer.try_into().unwrap());
+        let actual = crc32(body);
+        if expected != actual {
+            return Err(SaveStateError::ChecksumMismatch { expected, actual });
+        }
+
+        let count = u16::from_le_bytes([data[10], data[11]]);
+        let mut sections = Vec::with_capacity(count as usize);
+        let mut rest = &body[HEADER_LEN..];
+        for _ in 0..count {
+            if rest.len() < 8 {
+                return Err(SaveStateError::Truncated);
+            }
+            let tag: [Byte; 4] = rest[0..4].try_into().unwrap();
+            let len = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+            if rest.len() - 8 < len {
+                return Err(SaveStateError::Truncated);
+            }
+            sections.push((tag, &rest[8..8 + len]));
+            rest = &rest[8 + len..];
+        }
+        if !rest.is_empty() {
+            return Err(SaveStateError::TrailingData { len: rest.len() });
+        }
+
+        return Ok(sections);
+    }
+
+    fn find_section<'a>(
+        sections: &[Section<'a>],
+        tag: [Byte; 4],
+    ) -> Result<&'a [Byte], SaveStateError> {
+        return sections
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, payload)| *payload)
+            .ok_or(SaveStateError::MissingSection { tag });
+    }
+
+    // Restores a state written by save_state. The memory and devices have
+    // to be set up like the saved ones, with the devices in the same
+    // order. Every section is checked before anything is changed, so on
+    // error the CPU, memory and devices are left untouched
+    pub fn load_state<M: Snapshot + ?Sized>(
+        cpu: &mut CPU,
+        memory: &mut M,
+        devices: &mut [&mut dyn Snapshot],
+        data: &[Byte],
+    ) -> Result<(), SaveStateError> {
+        let sections = parse(data)?;
+        let registers = find_section(&sections, REGISTERS)?;
+        let ram = find_section(&sections, RAM)?;
+        let device_sections: Vec<&[Byte]> = sections
+            .iter()
+            .filter(|(tag, _)| *tag == DEVICE)
+            .map(|(_, payload)| *payload)
+            .collect();
+        if device_sections.len() != devices.len() {
+            return Err(SaveStateError::DeviceCount {
+                expected: devices.len(),
+                actual: device_sections.len(),
+            });
+        }
+
+        // Host settings are carried over from the clone
+        let mut restored = cpu.clone();
+        decode_registers(&mut restored, registers)?;
+        memory.check_state(ram)?;
+        for (device, section) in devices.iter().zip(&device_sections) {
+            device.check_state(section)?;
+        }
+
+        for (device, section) in devices.iter_mut().zip(&device_sections) {
+            device.load_state(section);
+        }
+        memory.load_state(ram);
+        *cpu = restored;
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::banking::banking::{BankedMemory, GeoRam, Mapper, Mmc1, UxRom};
+    use crate::memory::memory::{Bus, Memory};
+    use crate::memory_map::memory_map::{Device, MemoryMap};
+    use crate::save_state::save_state::{
+        crc32, load_state, save_state, SaveStateError, Snapshot, DEVICE, RAM, REGISTERS,
+    };
+    use crate::{CpuError, CpuVariant, Instruction, CPU};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Runs a short program so the registers and RAM hold something
+    // other than their defaults
+    fn running_cpu() -> (CPU, Memory) {
+        let mut cpu = CPU::new(CpuVariant::Cmos65C02);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let program = [
+            u8::from(Instruction::LDA_IM),
+            0x42,
+            u8::from(Instruction::STA_ZP),
+            0x10,
+            u8::from(Instruction::LDX_IM),
+            0x7f,
+            u8::from(Instruction::SEC),
+            u8::from(Instruction::PHA),
+        ];
+        for (i, byte) in program.iter().enumerate() {
+            memory.write_byte(0x0200 + i as u16, *byte);
+        }
+        memory.write_byte(0xfffc, 0x00);
+        memory.write_byte(0xfffd, 0x02);
+        cpu.reset(&mut memory);
+        cpu.set_cycle_accurate(true);
+        cpu.set_irq_line(true);
+        for _ in 0..5 {
+            cpu.step(&mut memory).unwrap();
+        }
+        return (cpu, memory);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0x0000_0000);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (cpu, memory) = running_cpu();
+        let data = save_state(&cpu, &memory, &[]);
+
+        // Cycle accurate mode is a host setting, the target enables it
+        // itself so the debug output matches
+        let mut restored = CPU::default();
+        restored.set_cycle_accurate(true);
+        let mut restored_memory = Memory {
+            ..Default::default()
+        };
+        load_state(&mut restored, &mut restored_memory, &mut [], &data).unwrap();
+
+        assert_eq!(format!("{:?}", restored), format!("{:?}", cpu));
+        assert_eq!(restored_memory.read_byte(0x0010), 0x42);
+        assert_eq!(restored_memory.read_byte(0x01fd), 0x42);
+        assert!(restored_memory.ram == memory.ram);
+
+        // Both copies keep running in lockstep
+        let mut cpu = cpu;
+        let mut memory = memory;
+        for _ in 0..3 {
+            assert_eq!(restored.step(&mut restored_memory), cpu.step(&mut memory));
+        }
+        assert_eq!(
+            save_state(&restored, &restored_memory, &[]),
+            save_state(&cpu, &memory, &[])
+        );
+    }
+
+    #[test]
+    fn test_load_keeps_host_settings() {
+        let (cpu, memory) = running_cpu();
+        let data = save_state(&cpu, &memory, &[]);
+
+        let mut restored = CPU::default();
+        restored.add_breakpoint(cpu.get_pc());
+        let mut restored_memory = Memory {
+            ..Default::default()
+        };
+        load_state(&mut restored, &mut restored_memory, &mut [], &data).unwrap();
+
+        assert_eq!(restored.get_pc(), cpu.get_pc());
+        assert!(!restored.get_cycle_accurate());
+        // The breakpoint at the restored pc is reported
+        assert_eq!(
+            restored.step(&mut restored_memory),
+            Err(CpuError::Breakpoint {
+                address: cpu.get_pc(),
+                interrupt: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_devices() {
+        let mmc1 = Rc::new(RefCell::new(BankedMemory::rom(
+            vec![0; 0x20000],
+            Box::new(Mmc1::new(8)),
+        )));
+        let georam = Rc::new(RefCell::new(BankedMemory::ram(
+            0x8000,
+            Box::new(GeoRam::new()),
+        )));
+        for i in 0..5 {
+            mmc1.borrow_mut().write(0x6000, (3 >> i) & 1);
+        }
+        georam.borrow_mut().write(0x1fe, 0x01);
+        georam.borrow_mut().write(0x010, 0x99);
+        let (cpu, memory) = running_cpu();
+        let data = save_state(&cpu, &memory, &[&mmc1, &georam]);
+
+        let mut mmc1_copy = BankedMemory::rom(vec![0; 0x20000], Box::new(Mmc1::new(8)));
+        let mut georam_copy = BankedMemory::ram(0x8000, Box::new(GeoRam::new()));
+        let mut restored = CPU::default();
+        let mut restored_memory = Memory {
+            ..Default::default()
+        };
+        load_state(
+            &mut restored,
+            &mut restored_memory,
+            &mut [&mut mmc1_copy, &mut georam_copy],
+            &data,
+        )
+        .unwrap();
+
+        assert_eq!(mmc1_copy.save_state(), mmc1.borrow().save_state());
+        assert_eq!(georam_copy.read(0x010), 0x99);
+        assert_eq!(georam_copy.get_data()[0x110], 0x99);
+
+        // Loading with the wrong set of devices is refused
+        assert_eq!(
+            load_state(&mut restored, &mut restored_memory, &mut [], &data),
+            Err(SaveStateError::DeviceCount {
+                expected: 0,
+                actual: 2
+            })
+        );
+
+        // A device rejecting its section leaves the ones before it alone
+        let mut mmc1_fresh = BankedMemory::rom(vec![0; 0x20000], Box::new(Mmc1::new(8)));
+        let mut uxrom = BankedMemory::rom(vec![0; 0x8000], Box::new(UxRom::new(2)));
+        let mut fresh = CPU::default();
+        let mut fresh_memory = Memory {
+            ..Default::default()
+        };
+        assert_eq!(
+            load_state(
+                &mut fresh,
+                &mut fresh_memory,
+                &mut [&mut mmc1_fresh, &mut uxrom],
+                &data
+            ),
+            Err(SaveStateError::InvalidSection { tag: DEVICE })
+        );
+        assert_eq!(mmc1_fresh.save_state(), Mmc1::new(8).save_state());
+        assert_eq!(format!("{:?}", fresh), format!("{:?}", CPU::default()));
+        assert!(fresh_memory.ram == [0; 0x10000]);
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let layout = || {
+            MemoryMap::new()
+                .mirrored_ram(0x0000, 0x1fff, 0x0800)
+                .ram(0x6000, 0x7fff)
+                .rom(0x8000, 0xffff, &[0xea])
+        };
+        let mut memory = layout();
+        let mut cpu = CPU::new(CpuVariant::Ricoh2A03);
+        memory.write_byte(0x0812, 0x42);
+        memory.write_byte(0x7fff, 0x69);
+        memory.read_byte(0x8000);
+        cpu.set_a(0x11);
+        let data = save_state(&cpu, &memory, &[]);
+
+        let mut restored = CPU::new(CpuVariant::Ricoh2A03);
+        let mut restored_memory = layout();
+        load_state(&mut restored, &mut restored_memory, &mut [], &data).unwrap();
+
+        assert_eq!(restored.get_a(), 0x11);
+        assert_eq!(restored_memory.get_open_bus(), 0xea);
+        assert_eq!(restored_memory.read_byte(0x0012), 0x42);
+        assert_eq!(restored_memory.read_byte(0x7fff), 0x69);
+
+        // The RAM regions have to match the saved ones
+        let mut other = MemoryMap::new().ram(0x0000, 0x07ff);
+        let mut cpu = CPU::new(CpuVariant::Ricoh2A03);
+        assert_eq!(
+            load_state(&mut cpu, &mut other, &mut [], &data),
+            Err(SaveStateError::InvalidSection { tag: RAM })
+        );
+        assert_eq!(cpu.get_a(), 0x00);
+        assert_eq!(other.read_byte(0x0012), 0x00);
+    }
+
+    #[test]
+    fn test_corruption() {
+        let (cpu, memory) = running_cpu();
+        let data = save_state(&cpu, &memory, &[]);
+        let mut restored = CPU::default();
+        let mut restored_memory = Memory {
+            ..Default::default()
+        };
+
+        let mut flipped = data.clone();
+        flipped[0x1000] ^= 0x01;
+        assert!(matches!(
+            load_state(&mut restored, &mut restored_memory, &mut [], &flipped),
+            Err(SaveStateError::ChecksumMismatch { .. })
+        ));
+
+        let mut magic = data.clone();
+        magic[0] = b'Z';
+        assert_eq!(
+            load_state(&mut restored, &mut restored_memory, &mut [], &magic),
+            Err(SaveStateError::BadMagic)
+        );
+
+        let mut version = data.clone();
+        version[8] = 0x02;
+        assert_eq!(
+            load_state(&mut restored, &mut restored_memory, &mut [], &version),
+            Err(SaveStateError::UnsupportedVersion { version: 2 })
+        );
+
+        for len in [10, 100, data.len() - 1] {
+            assert!(
+                load_state(&mut restored, &mut restored_memory, &mut [], &data[..len]).is_err()
+            );
+        }
+
+        // Failed loads leave everything untouched
+        assert_eq!(format!("{:?}", restored), format!("{:?}", CPU::default()));
+        assert!(restored_memory.ram == [0; 0x10000]);
+    }
+
+    #[test]
+    fn test_section_validation() {
+        let (cpu, memory) = running_cpu();
+        let data = save_state(&cpu, &memory, &[]);
+        let mut restored = CPU::default();
+        let mut restored_memory = Memory {
+            ..Default::default()
+        };
+
+        // Rewrites a byte and fixes up the checksum so only the section
+        // checks can catch it
+        let patch = |offset: usize, value: u8| {
+            let mut patched = data.clone();
+            patched[offset] = value;
+            let len = patched.len();
+            let checksum = crc32(&patched[..len - 4]);
+            patched[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+            return patched;
+        };
+
+        // Variant byte of the register block
+        let registers = 12 + 8;
+        assert_eq!(
+            load_state(
+                &mut restored,
+                &mut restored_memory,
+                &mut [],
+                &patch(registers + 15, 9)
+            ),
+            Err(SaveStateError::InvalidSection { tag: REGISTERS })
+        );
+        // Tag of the RAM section
+        let ram = registers + 18;
+        assert_eq!(
+            load_state(
+                &mut restored,
+                &mut restored_memory,
+                &mut [],
+                &patch(ram, b'X')
+            ),
+            Err(SaveStateError::MissingSection { tag: RAM })
+        );
+
+        // Bytes after the last section, covered by the checksum
+        let mut trailing = data[..data.len() - 4].to_vec();
+        trailing.extend_from_slice(&[0x00, 0x01, 0x02]);
+        let checksum = crc32(&trailing);
+        trailing.extend_from_slice(&checksum.to_le_bytes());
+        assert_eq!(
+            load_state(&mut restored, &mut restored_memory, &mut [], &trailing),
+            Err(SaveStateError::TrailingData { len: 3 })
+        );
+    }
+}
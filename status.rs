@@ -0,0 +1,203 @@
+#[allow(clippy::module_inception)]
+pub mod status {
+    use crate::memory::memory::Byte;
+    use std::fmt;
+    use std::ops::{BitAnd, BitOr, BitOrAssign, Not};
+
+    // Processor status register, laid out like the P byte on hardware:
+    //
+    // 7 6 5 4 3 2 1 0
+    // N V - B D I Z C
+    //
+    // Bit 5 is not backed by a latch and always reads as 1. B only exists
+    // in copies pushed onto the stack: PHP and BRK push it set, IRQ and NMI
+    // push it cleared, and PLP and RTI ignore it
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct StatusFlags(Byte);
+
+    impl StatusFlags {
+        pub const CARRY: StatusFlags = StatusFlags(1 << 0);
+        pub const ZERO: StatusFlags = StatusFlags(1 << 1);
+        pub const INTERRUPT_DISABLE: StatusFlags = StatusFlags(1 << 2);
+        pub const DECIMAL_MODE: StatusFlags = StatusFlags(1 << 3);
+        pub const BREAK: StatusFlags = StatusFlags(1 << 4);
+        pub const UNUSED: StatusFlags = StatusFlags(1 << 5);
+        pub const OVERFLOW: StatusFlags = StatusFlags(1 << 6);
+        pub const NEGATIVE: StatusFlags = StatusFlags(1 << 7);
+
+        pub const fn empty() -> Self {
+            return StatusFlags(0);
+        }
+
+        pub const fn all() -> Self {
+            return StatusFlags(0xff);
+        }
+
+        pub const fn from_bits(bits: Byte) -> Self {
+            return StatusFlags(bits);
+        }
+
+        pub const fn bits(&self) -> Byte {
+            return self.0;
+        }
+
+        pub const fn is_empty(&self) -> bool {
+            return self.0 == 0;
+        }
+
+        pub const fn contains(&self, other: StatusFlags) -> bool {
+            return (self.0 & other.0) == other.0;
+        }
+
+        pub fn insert(&mut self, other: StatusFlags) {
+            self.0 |= other.0;
+        }
+
+        pub fn remove(&mut self, other: StatusFlags) {
+            self.0 &= !other.0;
+        }
+
+        pub fn set(&mut self, other: StatusFlags, value: bool) {
+            if value {
+                self.insert(other);
+            } else {
+                self.remove(other);
+            }
+        }
+
+        // Byte written to the stack. `brk` is true for PHP and BRK and
+        // false for hardware interrupts
+        pub fn to_pushed(self, brk: bool) -> Byte {
+            let mut pushed = self | StatusFlags::UNUSED;
+            pushed.set(StatusFlags::BREAK, brk);
+
+            return pushed.bits();
+        }
+
+        // Register value after PLP or RTI pulled `value` from the stack
+        pub fn from_pulled(value: Byte) -> Self {
+            return (StatusFlags(value) | StatusFlags::UNUSED) & !StatusFlags::BREAK;
+        }
+    }
+
+    impl From<Byte> for StatusFlags {
+        fn from(value: Byte) -> Self {
+            return StatusFlags(value);
+        }
+    }
+
+    impl From<StatusFlags> for Byte {
+        fn from(value: StatusFlags) -> Self {
+            return value.0;
+        }
+    }
+
+    impl BitOr for StatusFlags {
+        type Output = StatusFlags;
+
+        fn bitor(self, rhs: StatusFlags) -> StatusFlags {
+            return StatusFlags(self.0 | rhs.0);
+        }
+    }
+
+    impl BitOrAssign for StatusFlags {
+        fn bitor_assign(&mut self, rhs: StatusFlags) {
+            self.0 |= rhs.0;
+        }
+    }
+
+    impl BitAnd for StatusFlags {
+        type Output = StatusFlags;
+
+        fn bitand(self, rhs: StatusFlags) -> StatusFlags {
+            return StatusFlags(self.0 & rhs.0);
+        }
+    }
+
+    impl Not for StatusFlags {
+        type Output = StatusFlags;
+
+        fn not(self) -> StatusFlags {
+            return StatusFlags(!self.0);
+        }
+    }
+
+    // Shows set flags in upper case, e.g. `Nv-bdIzC`
+    impl fmt::Debug for StatusFlags {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let names = "NV-BDIZC";
+            let text: String = names
+                .chars()
+                .enumerate()
+                .map(|(i, name)| {
+                    if self.0 & (0x80 >> i) != 0 || name == '-' {
+                        name
+                    } else {
+                        name.to_ascii_lowercase()
+                    }
+                })
+                .collect();
+
+            return write!(f, "StatusFlags({})", text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::status::status::StatusFlags;
+
+    #[test]
+    fn test_flag_positions() {
+        let flags = [
+            (StatusFlags::CARRY, 0x01),
+            (StatusFlags::ZERO, 0x02),
+            (StatusFlags::INTERRUPT_DISABLE, 0x04),
+            (StatusFlags::DECIMAL_MODE, 0x08),
+            (StatusFlags::BREAK, 0x10),
+            (StatusFlags::UNUSED, 0x20),
+            (StatusFlags::OVERFLOW, 0x40),
+            (StatusFlags::NEGATIVE, 0x80),
+        ];
+
+        for (flag, bits) in flags {
+            assert_eq!(u8::from(flag), bits);
+            assert_eq!(StatusFlags::from(bits), flag);
+        }
+    }
+
+    #[test]
+    fn test_set_and_contains() {
+        let mut flags = StatusFlags::empty();
+
+        flags.set(StatusFlags::NEGATIVE, true);
+        flags.insert(StatusFlags::CARRY | StatusFlags::ZERO);
+        assert_eq!(flags.bits(), 0x83);
+        assert!(flags.contains(StatusFlags::NEGATIVE | StatusFlags::CARRY));
+        assert!(!flags.contains(StatusFlags::OVERFLOW | StatusFlags::CARRY));
+
+        flags.set(StatusFlags::ZERO, false);
+        flags.remove(StatusFlags::NEGATIVE);
+        assert_eq!(flags, StatusFlags::CARRY);
+        assert!(StatusFlags::empty().is_empty());
+        assert_eq!(StatusFlags::all().bits(), 0xff);
+    }
+
+    #[test]
+    fn test_pushed_and_pulled() {
+        let flags = StatusFlags::NEGATIVE | StatusFlags::CARRY;
+
+        assert_eq!(flags.to_pushed(true), 0xb1);
+        assert_eq!(flags.to_pushed(false), 0xa1);
+        assert_eq!((flags | StatusFlags::BREAK).to_pushed(false), 0xa1);
+        assert_eq!(StatusFlags::from_pulled(0x10).bits(), 0x20);
+        assert_eq!(StatusFlags::from_pulled(0xdf).bits(), 0xef);
+    }
+
+    #[test]
+    fn test_debug() {
+        let flags = StatusFlags::NEGATIVE | StatusFlags::INTERRUPT_DISABLE | StatusFlags::CARRY;
+
+        assert_eq!(format!("{:?}", flags), "StatusFlags(Nv-bdIzC)");
+    }
+}
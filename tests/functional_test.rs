@@ -0,0 +1,89 @@
+// Runs Klaus Dormann's 6502 functional test
+// (https://github.com/Klaus2m5/6502_65C02_functional_tests). The binary is
+// not part of the repository, place the default build of
+// 6502_functional_test.bin in tests/fixtures and run
+// `cargo test -- --ignored` to run the suite.
+#![allow(clippy::needless_return)]
+
+use emulator6502::assembler::assembler::assemble_into;
+use emulator6502::memory::memory::{Bus, Memory};
+use emulator6502::{CpuVariant, CPU};
+use std::path::PathBuf;
+
+// Default build: code starts at $0400 and the `jmp *` reached after the
+// last test is at $3469
+const START_ADDRESS: u16 = 0x0400;
+const SUCCESS_ADDRESS: u16 = 0x3469;
+// Number of the test being run, stored by the test itself
+const TEST_CASE_ADDRESS: u16 = 0x0200;
+// A passing run takes about 96 million cycles
+const CYCLE_LIMIT: u64 = 200_000_000;
+
+fn load_fixture(name: &str) -> Option<Vec<u8>> {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect();
+
+    return std::fs::read(path).ok();
+}
+
+// Steps until the program counter stops moving, which is how the test
+// signals both success and failure. Returns the address of the trap
+fn run_until_trap(cpu: &mut CPU, memory: &mut Memory) -> Result<u16, String> {
+    while cpu.get_cycles() < CYCLE_LIMIT {
+        let info = cpu.step(memory).map_err(|error| error.to_string())?;
+        if info.interrupt.is_none() && cpu.get_pc() == info.pc {
+            return Ok(info.pc);
+        }
+    }
+
+    return Err(format!(
+        "no trap after {} cycles, pc ${:04X}",
+        CYCLE_LIMIT,
+        cpu.get_pc()
+    ));
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/6502_functional_test.bin"]
+fn functional_test() {
+    let image = load_fixture("6502_functional_test.bin")
+        .expect("tests/fixtures/6502_functional_test.bin not found");
+
+    let mut memory = Memory::default();
+    for (addr, byte) in image.iter().take(0x10000).enumerate() {
+        memory.write_byte(addr as u16, *byte);
+    }
+    let mut cpu = CPU::new(CpuVariant::Nmos6502);
+    cpu.reset(&mut memory);
+    cpu.set_pc(START_ADDRESS);
+
+    let result = run_until_trap(&mut cpu, &mut memory);
+    let test_case = memory.read_byte(TEST_CASE_ADDRESS);
+    match result {
+        Ok(SUCCESS_ADDRESS) => {}
+        Ok(trap) => panic!("trapped at ${:04X} in test ${:02X}", trap, test_case),
+        Err(error) => panic!("{} in test ${:02X}", error, test_case),
+    }
+}
+
+#[test]
+fn trap_detection() {
+    let mut memory = Memory::default();
+    let source = "
+        .org $0400
+        lda #$2A
+        sta $0200
+        ldx #$00
+        jmp trap
+trap:   jmp trap
+    ";
+    let assembly = assemble_into(source, &mut memory, CpuVariant::Nmos6502).unwrap();
+    let mut cpu = CPU::new(CpuVariant::Nmos6502);
+    cpu.set_pc(START_ADDRESS);
+
+    let trap = run_until_trap(&mut cpu, &mut memory);
+
+    assert_eq!(trap, Ok(assembly.get_label("trap").unwrap()));
+    assert_eq!(memory.read_byte(TEST_CASE_ADDRESS), 0x2A);
+}
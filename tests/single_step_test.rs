@@ -0,0 +1,538 @@
+// Runs the per-opcode SingleStepTests/ProcessorTests vectors
+// (https://github.com/SingleStepTests/65x02). The vectors are not part of
+// the repository, place the JSON files (e.g. `a9.json`) in
+// tests/fixtures/single_step/6502 or tests/fixtures/single_step/65c02 and
+// run `cargo test -- --ignored` to run them.
+#![allow(clippy::needless_return)]
+
+use emulator6502::memory::memory::{Bus, BusAccess, BusObserver, Memory};
+use emulator6502::{CpuVariant, Instruction, CPU};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Failures printed per file, the rest are only counted
+const REPORTED_FAILURES: usize = 5;
+
+// Just enough JSON for the test vectors
+mod json {
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Value {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Value>),
+        Object(Vec<(String, Value)>),
+    }
+
+    impl Value {
+        pub fn get(&self, key: &str) -> Option<&Value> {
+            return match self {
+                Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+                _ => None,
+            };
+        }
+
+        pub fn as_u64(&self) -> Option<u64> {
+            return match self {
+                Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+                _ => None,
+            };
+        }
+
+        pub fn as_str(&self) -> Option<&str> {
+            return match self {
+                Value::String(s) => Some(s),
+                _ => None,
+            };
+        }
+
+        pub fn as_array(&self) -> Option<&[Value]> {
+            return match self {
+                Value::Array(items) => Some(items),
+                _ => None,
+            };
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Value, String> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.bytes.len() {
+            return Err(format!("trailing characters at {}", parser.pos));
+        }
+
+        return Ok(value);
+    }
+
+    struct Parser<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl Parser<'_> {
+        fn value(&mut self) -> Result<Value, String> {
+            self.skip_whitespace();
+            return match self.bytes.get(self.pos) {
+                Some(b'{') => self.object(),
+                Some(b'[') => self.array(),
+                Some(b'"') => Ok(Value::String(self.string()?)),
+                Some(b't') => self.literal("true", Value::Bool(true)),
+                Some(b'f') => self.literal("false", Value::Bool(false)),
+                Some(b'n') => self.literal("null", Value::Null),
+                Some(_) => self.number(),
+                None => Err("unexpected end of input".to_string()),
+            };
+        }
+
+        fn object(&mut self) -> Result<Value, String> {
+            let mut fields = Vec::new();
+            self.pos += 1;
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) == Some(&b'}') {
+                self.pos += 1;
+                return Ok(Value::Object(fields));
+            }
+            loop {
+                self.skip_whitespace();
+                let key = self.string()?;
+                self.expect(b':')?;
+                fields.push((key, self.value()?));
+                self.skip_whitespace();
+                match self.bytes.get(self.pos) {
+                    Some(b',') => self.pos += 1,
+                    Some(b'}') => {
+                        self.pos += 1;
+                        return Ok(Value::Object(fields));
+                    }
+                    _ => return Err(format!("expected , or }} at {}", self.pos)),
+                }
+            }
+        }
+
+        fn array(&mut self) -> Result<Value, String> {
+            let mut items = Vec::new();
+            self.pos += 1;
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) == Some(&b']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            loop {
+                items.push(self.value()?);
+                self.skip_whitespace();
+                match self.bytes.get(self.pos) {
+                    Some(b',') => self.pos += 1,
+                    Some(b']') => {
+                        self.pos += 1;
+                        return Ok(Value::Array(items));
+                    }
+                    _ => return Err(format!("expected , or ] at {}", self.pos)),
+                }
+            }
+        }
+
+        fn string(&mut self) -> Result<String, String> {
+            self.expect(b'"')?;
+            let mut string = Vec::new();
+            loop {
+                match self.bytes.get(self.pos) {
+                    Some(b'"') => {
+                        self.pos += 1;
+                        return String::from_utf8(string).map_err(|e| e.to_string());
+                    }
+                    Some(b'\\') => {
+                        let escaped = match self.bytes.get(self.pos + 1) {
+                            Some(b'n') => b'\n',
+                            Some(b't') => b'\t',
+                            Some(b'r') => b'\r',
+                            Some(&c) => c,
+                            None => return Err("unterminated escape".to_string()),
+                        };
+                        string.push(escaped);
+                        self.pos += 2;
+                    }
+                    Some(&c) => {
+                        string.push(c);
+                        self.pos += 1;
+                    }
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+        }
+
+        fn number(&mut self) -> Result<Value, String> {
+            let start = self.pos;
+            while let Some(c) = self.bytes.get(self.pos) {
+                if !(c.is_ascii_digit() || b"+-.eE".contains(c)) {
+                    break;
+                }
+                self.pos += 1;
+            }
+            let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default();
+
+            return text
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("bad number at {}", start));
+        }
+
+        fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+            if !self.bytes[self.pos..].starts_with(word.as_bytes()) {
+                return Err(format!("unexpected token at {}", self.pos));
+            }
+            self.pos += word.len();
+
+            return Ok(value);
+        }
+
+        fn expect(&mut self, byte: u8) -> Result<(), String> {
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) != Some(&byte) {
+                return Err(format!("expected {} at {}", byte as char, self.pos));
+            }
+            self.pos += 1;
+
+            return Ok(());
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+        }
+    }
+}
+
+use json::Value;
+
+struct State {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+struct TestCase {
+    name: String,
+    initial: State,
+    expected: State,
+    // Address, value and "read"/"write" of every bus cycle
+    cycles: Vec<(u16, u8, String)>,
+}
+
+fn field(value: &Value, key: &str) -> Result<u64, String> {
+    return value
+        .get(key)
+        .and_then(Value::as_u64)
+        .ok_or(format!("missing {}", key));
+}
+
+fn parse_state(value: &Value) -> Result<State, String> {
+    let mut ram = Vec::new();
+    for entry in value.get("ram").and_then(Value::as_array).unwrap_or(&[]) {
+        let pair = entry.as_array().unwrap_or(&[]);
+        match (
+            pair.first().and_then(Value::as_u64),
+            pair.get(1).and_then(Value::as_u64),
+        ) {
+            (Some(addr), Some(byte)) => ram.push((addr as u16, byte as u8)),
+            _ => return Err("bad ram entry".to_string()),
+        }
+    }
+
+    return Ok(State {
+        pc: field(value, "pc")? as u16,
+        s: field(value, "s")? as u8,
+        a: field(value, "a")? as u8,
+        x: field(value, "x")? as u8,
+        y: field(value, "y")? as u8,
+        p: field(value, "p")? as u8,
+        ram,
+    });
+}
+
+fn parse_test_cases(text: &str) -> Result<Vec<TestCase>, String> {
+    let document = json::parse(text)?;
+    let mut cases = Vec::new();
+    for case in document.as_array().ok_or("expected an array of tests")? {
+        let mut cycles = Vec::new();
+        for cycle in case.get("cycles").and_then(Value::as_array).unwrap_or(&[]) {
+            let cycle = cycle.as_array().unwrap_or(&[]);
+            let addr = cycle.first().and_then(Value::as_u64).ok_or("bad cycle")?;
+            let value = cycle.get(1).and_then(Value::as_u64).ok_or("bad cycle")?;
+            let kind = cycle.get(2).and_then(Value::as_str).ok_or("bad cycle")?;
+            cycles.push((addr as u16, value as u8, kind.to_string()));
+        }
+
+        cases.push(TestCase {
+            name: case
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            initial: parse_state(case.get("initial").ok_or("missing initial")?)?,
+            expected: parse_state(case.get("final").ok_or("missing final")?)?,
+            cycles,
+        });
+    }
+
+    return Ok(cases);
+}
+
+// Runs a single case in cycle accurate mode, describing every mismatch
+fn run_test_case(case: &TestCase, variant: CpuVariant) -> Result<(), String> {
+    let mut cpu = CPU::new(variant);
+    let mut memory = Memory::default();
+    let bus_cycles = Rc::new(RefCell::new(Vec::new()));
+    let sink = bus_cycles.clone();
+    cpu.set_cycle_accurate(true);
+    cpu.set_bus_observer(Some(BusObserver::new(move |cycle| {
+        sink.borrow_mut().push(cycle)
+    })));
+    cpu.set_pc(case.initial.pc);
+    cpu.set_sp(case.initial.s);
+    cpu.set_a(case.initial.a);
+    cpu.set_x(case.initial.x);
+    cpu.set_y(case.initial.y);
+    cpu.set_status(case.initial.p);
+    for (addr, value) in &case.initial.ram {
+        memory.write_byte(*addr, *value);
+    }
+
+    let info = cpu.step(&mut memory).map_err(|error| error.to_string())?;
+
+    let mut mismatches = Vec::new();
+    let registers = [
+        ("pc", cpu.get_pc(), case.expected.pc),
+        ("s", cpu.get_sp() as u16, case.expected.s as u16),
+        ("a", cpu.get_a() as u16, case.expected.a as u16),
+        ("x", cpu.get_x() as u16, case.expected.x as u16),
+        ("y", cpu.get_y() as u16, case.expected.y as u16),
+        ("p", cpu.get_status() as u16, case.expected.p as u16),
+        ("cycles", info.cycles as u16, case.cycles.len() as u16),
+    ];
+    for (name, actual, expected) in registers {
+        if actual != expected {
+            mismatches.push(format!("{} ${:02X} != ${:02X}", name, actual, expected));
+        }
+    }
+    for (addr, expected) in &case.expected.ram {
+        let actual = memory.read_byte(*addr);
+        if actual != *expected {
+            mismatches.push(format!(
+                "${:04X} ${:02X} != ${:02X}",
+                addr, actual, expected
+            ));
+        }
+    }
+    // Only the first differing cycle is reported, the ones after it are
+    // usually off as well
+    let bus_cycles = bus_cycles.borrow();
+    if bus_cycles.len() != case.cycles.len() {
+        mismatches.push(format!(
+            "bus accesses {} != {}",
+            bus_cycles.len(),
+            case.cycles.len()
+        ));
+    }
+    let actual_cycles = bus_cycles.iter().map(|cycle| {
+        let kind = match cycle.access {
+            BusAccess::Read => "read",
+            BusAccess::Write => "write",
+        };
+        (cycle.addr, cycle.value, kind)
+    });
+    let expected_cycles = case
+        .cycles
+        .iter()
+        .map(|(addr, value, kind)| (*addr, *value, kind.as_str()));
+    if let Some((i, (actual, expected))) = actual_cycles
+        .zip(expected_cycles)
+        .enumerate()
+        .find(|(_, (actual, expected))| actual != expected)
+    {
+        mismatches.push(format!(
+            "cycle {} ${:04X} ${:02X} {} != ${:04X} ${:02X} {}",
+            i, actual.0, actual.1, actual.2, expected.0, expected.1, expected.2
+        ));
+    }
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    return Err(mismatches.join(", "));
+}
+
+// Opcodes the CPU refuses to run on variant, their vectors are skipped
+fn is_unsupported(path: &Path, variant: CpuVariant) -> bool {
+    let opcode = path
+        .file_stem()
+        .and_then(|stem| u8::from_str_radix(&stem.to_string_lossy(), 16).ok());
+
+    return match opcode {
+        Some(opcode) => matches!(
+            Instruction::decode(opcode, variant),
+            Instruction::INVALID | Instruction::JAM
+        ),
+        None => true,
+    };
+}
+
+fn run_directory(directory: &str, variant: CpuVariant) {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "fixtures",
+        "single_step",
+    ]
+    .iter()
+    .collect();
+    let entries = std::fs::read_dir(path.join(directory)).unwrap_or_else(|_| {
+        panic!("tests/fixtures/single_step/{} not found", directory);
+    });
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    assert!(
+        !files.is_empty(),
+        "no vectors in tests/fixtures/single_step/{}",
+        directory
+    );
+
+    let mut failed_files = Vec::new();
+    for file in files.iter().filter(|file| !is_unsupported(file, variant)) {
+        let text = std::fs::read_to_string(file).unwrap();
+        let cases = parse_test_cases(&text).unwrap_or_else(|error| {
+            panic!("{}: {}", file.display(), error);
+        });
+
+        let mut failures = 0;
+        for case in &cases {
+            if let Err(error) = run_test_case(case, variant) {
+                if failures < REPORTED_FAILURES {
+                    eprintln!("{}: {}: {}", file.display(), case.name, error);
+                }
+                failures += 1;
+            }
+        }
+        if failures > 0 {
+            failed_files.push(format!("{} ({}/{})", file.display(), failures, cases.len()));
+        }
+    }
+
+    assert!(
+        failed_files.is_empty(),
+        "failing vectors: {}",
+        failed_files.join(", ")
+    );
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/single_step/6502"]
+fn single_step_6502() {
+    run_directory("6502", CpuVariant::Nmos6502);
+}
+
+#[test]
+#[ignore = "needs tests/fixtures/single_step/65c02"]
+fn single_step_65c02() {
+    run_directory("65c02", CpuVariant::Cmos65C02);
+}
+
+#[test]
+fn runner_checks_state_ram_and_cycles() {
+    let vectors = r#"[
+        {
+            "name": "a9 0f",
+            "initial": {"pc": 4096, "s": 253, "a": 0, "x": 1, "y": 2, "p": 36,
+                        "ram": [[4096, 169], [4097, 15]]},
+            "final": {"pc": 4098, "s": 253, "a": 15, "x": 1, "y": 2, "p": 36,
+                      "ram": [[4096, 169], [4097, 15]]},
+            "cycles": [[4096, 169, "read"], [4097, 15, "read"]]
+        },
+        {
+            "name": "85 10",
+            "initial": {"pc": 512, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36,
+                        "ram": [[512, 133], [513, 16], [16, 0]]},
+            "final": {"pc": 514, "s": 253, "a": 66, "x": 0, "y": 0, "p": 36,
+                      "ram": [[512, 133], [513, 16], [16, 67]]},
+            "cycles": [[512, 133, "read"], [513, 16, "read"], [16, 66, "write"]]
+        }
+    ]"#;
+    let cases = parse_test_cases(vectors).unwrap();
+
+    assert_eq!(cases.len(), 2);
+    assert_eq!(cases[1].cycles[2], (0x0010, 0x42, "write".to_string()));
+    assert_eq!(run_test_case(&cases[0], CpuVariant::Nmos6502), Ok(()));
+    assert_eq!(
+        run_test_case(&cases[1], CpuVariant::Nmos6502),
+        Err("$0010 $42 != $43".to_string())
+    );
+}
+
+#[test]
+fn runner_checks_bus_cycles() {
+    let vectors = r#"[
+        {
+            "name": "e6 10",
+            "initial": {"pc": 512, "s": 253, "a": 0, "x": 0, "y": 0, "p": 36,
+                        "ram": [[512, 230], [513, 16], [16, 127]]},
+            "final": {"pc": 514, "s": 253, "a": 0, "x": 0, "y": 0, "p": 164,
+                      "ram": [[16, 128]]},
+            "cycles": [[512, 230, "read"], [513, 16, "read"], [16, 127, "read"],
+                       [16, 127, "write"], [16, 128, "write"]]
+        }
+    ]"#;
+    let mut cases = parse_test_cases(vectors).unwrap();
+
+    assert_eq!(run_test_case(&cases[0], CpuVariant::Nmos6502), Ok(()));
+    // The 65C02 reads the value twice instead of writing it back
+    assert_eq!(
+        run_test_case(&cases[0], CpuVariant::Cmos65C02),
+        Err("cycle 3 $0010 $7F read != $0010 $7F write".to_string())
+    );
+
+    cases[0].cycles[3].1 = 0x7e;
+    assert_eq!(
+        run_test_case(&cases[0], CpuVariant::Nmos6502),
+        Err("cycle 3 $0010 $7F write != $0010 $7E write".to_string())
+    );
+
+    // Accesses past the end of the expected ones are reported as well
+    cases[0].cycles[3].1 = 0x7f;
+    cases[0].cycles.pop();
+    assert_eq!(
+        run_test_case(&cases[0], CpuVariant::Nmos6502),
+        Err("cycles $05 != $04, bus accesses 5 != 4".to_string())
+    );
+}
+
+#[test]
+fn json_parser() {
+    let value = json::parse(r#" {"a": [1, -2.5, true, null], "b": "x\"y", "c": {}} "#).unwrap();
+
+    assert_eq!(
+        value.get("a"),
+        Some(&Value::Array(vec![
+            Value::Number(1.0),
+            Value::Number(-2.5),
+            Value::Bool(true),
+            Value::Null
+        ]))
+    );
+    assert_eq!(value.get("b").and_then(Value::as_str), Some("x\"y"));
+    assert_eq!(value.get("c"), Some(&Value::Object(vec![])));
+    assert!(json::parse("[1, 2").is_err());
+}
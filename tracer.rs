@@ -0,0 +1,380 @@
+#[allow(clippy::module_inception)]
+pub mod tracer {
+    use crate::disassembler::disassembler::{format_instruction, instruction_length};
+    use crate::memory::memory::{AddressingMode, Bus, Byte, Word};
+    use crate::{CpuVariant, Instruction, Register, CPU};
+    use std::cell::RefCell;
+    use std::fmt;
+    use std::rc::Rc;
+
+    type Sink = dyn FnMut(&str);
+
+    // Receives one formatted line per executed instruction. Clones share
+    // the same sink
+    #[derive(Clone)]
+    pub struct Tracer {
+        sink: Rc<RefCell<Sink>>,
+    }
+
+    impl Tracer {
+        pub fn new<F: FnMut(&str) + 'static>(sink: F) -> Self {
+            return Tracer {
+                sink: Rc::new(RefCell::new(sink)),
+            };
+        }
+
+        pub fn trace(&self, line: &str) {
+            (self.sink.borrow_mut())(line);
+        }
+    }
+
+    impl fmt::Debug for Tracer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return f.write_str("Tracer");
+        }
+    }
+
+    // Formats the instruction at the program counter and the register
+    // state before it executes in the nestest.log layout:
+    //
+    // C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12
+    //
+    // Undocumented NMOS opcodes are marked with `*` in front of the
+    // mnemonic, and operands are followed by the addresses and values they
+    // refer to. The PPU column is only written for the Ricoh 2A03, its
+    // position is worked out from the cycle count with rendering off.
+    // Memory is only peeked at, every byte once, so tracing doesn't
+    // disturb devices or bus observers
+    pub fn format_line<B: Bus>(cpu: &CPU, memory: &mut B) -> String {
+        let pc = cpu.get_pc();
+        let variant = cpu.get_variant();
+        let opcode = memory.peek_byte(pc);
+        let mut bytes = vec![opcode];
+        for i in 1..instruction_length(opcode, variant) {
+            bytes.push(memory.peek_byte(pc.wrapping_add(i)));
+        }
+
+        let instruction = Instruction::decode(opcode, variant);
+        let undocumented = variant != CpuVariant::Cmos65C02
+            && instruction != Instruction::INVALID
+            && (instruction.is_undocumented() || u8::from(instruction) != opcode);
+        let marker = if undocumented { '*' } else { ' ' };
+        let text = format!(
+            "{}{}",
+            format_instruction(&bytes, pc, variant),
+            annotate(cpu, memory, instruction, &bytes)
+        );
+        let dump: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        let ppu = if variant == CpuVariant::Ricoh2A03 {
+            let dots = cpu.get_cycles() * 3;
+            format!("PPU:{:3},{:3} ", dots / 341 % 262, dots % 341)
+        } else {
+            String::new()
+        };
+
+        return format!(
+            "{:04X}  {:<8} {}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} {}CYC:{}",
+            pc,
+            dump.join(" "),
+            marker,
+            text,
+            cpu.get_a(),
+            cpu.get_x(),
+            cpu.get_y(),
+            cpu.get_status(),
+            cpu.get_sp(),
+            ppu,
+            cpu.get_cycles()
+        );
+    }
+
+    // What nestest.log writes after the operand: `@ address` where indexing
+    // or a pointer moved it, `= value` for the memory there and `= target`
+    // for pointers
+    fn annotate<B: Bus>(
+        cpu: &CPU,
+        memory: &mut B,
+        instruction: Instruction,
+        bytes: &[Byte],
+    ) -> String {
+        let low = bytes.get(1).copied().unwrap_or(0);
+        let high = bytes.get(2).copied().unwrap_or(0);
+        let word = ((high as Word) << 8) | low as Word;
+        let (mode, register) = instruction.addressing_mode();
+        let index = match register {
+            Register::Y => cpu.get_y(),
+            _ => cpu.get_x(),
+        };
+
+        return match mode {
+            AddressingMode::ZeroPage => format!(" = {:02X}", memory.peek_byte(low as Word)),
+            AddressingMode::ZeroPageReg => {
+                let addr = low.wrapping_add(index);
+                format!(" @ {:02X} = {:02X}", addr, memory.peek_byte(addr as Word))
+            }
+            AddressingMode::Absolute
+                if matches!(instruction, Instruction::JMP_ABS | Instruction::JSR_ABS) =>
+            {
+                String::new()
+            }
+            AddressingMode::Absolute => format!(" = {:02X}", memory.peek_byte(word)),
+            AddressingMode::AbsoluteReg => {
+                let addr = word.wrapping_add(index as Word);
+                format!(" @ {:04X} = {:02X}", addr, memory.peek_byte(addr))
+            }
+            AddressingMode::Indirect => {
+                let high_addr = if cpu.get_variant().has_jmp_indirect_bug() {
+                    (word & 0xff00) | (word.wrapping_add(1) & 0x00ff)
+                } else {
+                    word.wrapping_add(1)
+                };
+                format!(" = {:04X}", peek_word(memory, word, high_addr))
+            }
+            AddressingMode::IndirectX => {
+                let pointer = low.wrapping_add(cpu.get_x());
+                let addr = peek_zero_page_word(memory, pointer);
+                let value = memory.peek_byte(addr);
+                format!(" @ {:02X} = {:04X} = {:02X}", pointer, addr, value)
+            }
+            AddressingMode::IndirectY => {
+                let base = peek_zero_page_word(memory, low);
+                let addr = base.wrapping_add(cpu.get_y() as Word);
+                let value = memory.peek_byte(addr);
+                format!(" = {:04X} @ {:04X} = {:02X}", base, addr, value)
+            }
+            AddressingMode::ZeroPageIndirect => {
+                let addr = peek_zero_page_word(memory, low);
+                format!(" = {:04X} = {:02X}", addr, memory.peek_byte(addr))
+            }
+            AddressingMode::AbsoluteIndirectX => {
+                let pointer = word.wrapping_add(cpu.get_x() as Word);
+                let target = peek_word(memory, pointer, pointer.wrapping_add(1));
+                format!(" @ {:04X} = {:04X}", pointer, target)
+            }
+            AddressingMode::Implied
+            | AddressingMode::Accumulator
+            | AddressingMode::Immediate
+            | AddressingMode::Relative
+            | AddressingMode::ZeroPageRelative => String::new(),
+        };
+    }
+
+    fn peek_word<B: Bus>(memory: &mut B, low: Word, high: Word) -> Word {
+        return ((memory.peek_byte(high) as Word) << 8) | memory.peek_byte(low) as Word;
+    }
+
+    fn peek_zero_page_word<B: Bus>(memory: &mut B, addr: Byte) -> Word {
+        return peek_word(memory, addr as Word, addr.wrapping_add(1) as Word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::assembler::assemble_into;
+    use crate::memory::memory::{Bus, BusObserver, Byte, Memory, Word};
+    use crate::memory_map::memory_map::{Device, MemoryMap};
+    use crate::tracer::tracer::{format_line, Tracer};
+    use crate::{CpuVariant, CPU};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_format_line() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_word(0xfffc, 0xc000);
+        memory.write_byte(0xc000, 0x4c);
+        memory.write_word(0xc001, 0xc5f5);
+        cpu.reset(&mut memory);
+
+        assert_eq!(
+            format_line(&cpu, &mut memory),
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7"
+        );
+    }
+
+    #[test]
+    fn test_format_line_undocumented() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_byte(0x0000, 0x04);
+        memory.write_byte(0x0001, 0xa9);
+        memory.write_byte(0x0002, 0xeb);
+        memory.write_byte(0x0003, 0x01);
+
+        assert_eq!(
+            format_line(&cpu, &mut memory),
+            "0000  04 A9    *NOP $A9 = 00                    A:00 X:00 Y:00 P:20 SP:00 CYC:0"
+        );
+        cpu.step(&mut memory).unwrap();
+        assert!(format_line(&cpu, &mut memory).starts_with("0002  EB 01    *SBC #$01 "));
+    }
+
+    #[test]
+    fn test_tracer() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        let source = "
+            .org $FFFC
+            .word $0600
+            .org $0600
+            ldx #$05
+            inx
+            stx $10
+        ";
+        assemble_into(source, &mut memory, CpuVariant::Nmos6502).unwrap();
+        cpu.reset(&mut memory);
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        cpu.set_tracer(Some(Tracer::new(move |line: &str| {
+            sink.borrow_mut().push(line.to_string());
+        })));
+        cpu.run_for_cycles(&mut memory, 7).unwrap();
+        cpu.set_tracer(None);
+        cpu.step(&mut memory).unwrap();
+
+        assert_eq!(
+            *lines.borrow(),
+            vec![
+                "0600  A2 05     LDX #$05                        A:00 X:00 Y:00 P:24 SP:FD CYC:7",
+                "0602  E8        INX                             A:00 X:05 Y:00 P:24 SP:FD CYC:9",
+                "0603  86 10     STX $10 = 00                    A:00 X:06 Y:00 P:24 SP:FD CYC:11",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nestest_log() {
+        // The first lines of nestest.log, run from $C000 in automation mode
+        let expected = [
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+            "C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10",
+            "C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12",
+            "C5F9  86 10     STX $10 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 45 CYC:15",
+            "C5FB  86 11     STX $11 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 54 CYC:18",
+            "C5FD  20 2D C7  JSR $C72D                       A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 63 CYC:21",
+            "C72D  EA        NOP                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 81 CYC:27",
+            "C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 87 CYC:29",
+            "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 93 CYC:31",
+            "C735  EA        NOP                             A:00 X:00 Y:00 P:27 SP:FB PPU:  0,102 CYC:34",
+        ];
+        let mut cpu = CPU::new(CpuVariant::Ricoh2A03);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        for line in expected {
+            let addr = Word::from_str_radix(&line[0..4], 16).unwrap();
+            for (i, byte) in line[6..14].split_whitespace().enumerate() {
+                let byte = Byte::from_str_radix(byte, 16).unwrap();
+                memory.write_byte(addr + i as Word, byte);
+            }
+        }
+        memory.write_word(0xfffc, 0xc000);
+        cpu.reset(&mut memory);
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = lines.clone();
+        cpu.set_tracer(Some(Tracer::new(move |line: &str| {
+            sink.borrow_mut().push(line.to_string());
+        })));
+        for _ in expected {
+            cpu.step(&mut memory).unwrap();
+        }
+
+        assert_eq!(*lines.borrow(), expected);
+    }
+
+    #[test]
+    fn test_format_line_annotations() {
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        let mut memory = Memory {
+            ..Default::default()
+        };
+        memory.write_word(0x0080, 0x0200);
+        memory.write_word(0x0089, 0x0300);
+        memory.write_byte(0x0078, 0x12);
+        memory.write_byte(0x0200, 0x5a);
+        memory.write_byte(0x0300, 0x89);
+        memory.write_byte(0x06ff, 0x00);
+        memory.write_byte(0x0600, 0x03);
+        memory.write_byte(0x0647, 0x55);
+        cpu.set_status(0x24);
+        cpu.set_sp(0xfb);
+
+        // (bytes, text with its annotations)
+        let cases: [(&[u8], &str); 7] = [
+            (&[0xa1, 0x80], "LDA ($80,X) @ 80 = 0200 = 5A"),
+            (&[0xb1, 0x89], "LDA ($89),Y = 0300 @ 0300 = 89"),
+            (&[0xb5, 0x78], "LDA $78,X @ 78 = 12"),
+            (&[0xbd, 0x00, 0x03], "LDA $0300,X @ 0300 = 89"),
+            (&[0xad, 0x47, 0x06], "LDA $0647 = 55"),
+            (&[0x6c, 0xff, 0x06], "JMP ($06FF) = 0300"),
+            (&[0x4c, 0xf5, 0xc5], "JMP $C5F5"),
+        ];
+
+        for (bytes, text) in cases {
+            for (i, byte) in bytes.iter().enumerate() {
+                memory.write_byte(0xc000 + i as Word, *byte);
+            }
+            cpu.set_pc(0xc000);
+
+            let line = format_line(&cpu, &mut memory);
+
+            let expected = format!("{:<32}A:00 X:00 Y:00 P:24 SP:FB CYC:0", text);
+            assert_eq!(&line[16..], expected, "{}", line);
+        }
+    }
+
+    struct Register {
+        value: Byte,
+        reads: u32,
+    }
+
+    impl Device for Register {
+        fn read(&mut self, _offset: Word) -> Byte {
+            self.reads += 1;
+            return self.value;
+        }
+
+        fn write(&mut self, _offset: Word, value: Byte) {
+            self.value = value;
+        }
+    }
+
+    #[test]
+    fn test_tracer_side_effects() {
+        let register = Rc::new(RefCell::new(Register {
+            value: 0x42,
+            reads: 0,
+        }));
+        let mut memory =
+            MemoryMap::new()
+                .ram(0x0000, 0xffff)
+                .device(0xd000, 0xd000, Box::new(register.clone()));
+        // LDA $D000 followed by the register
+        memory.load(0x0200, &[0xad, 0x00, 0xd0]);
+        let mut cpu = CPU::new(CpuVariant::Nmos6502);
+        cpu.set_pc(0x0200);
+
+        let accesses = Rc::new(RefCell::new(0));
+        let counter = accesses.clone();
+        cpu.set_cycle_accurate(true);
+        cpu.set_bus_observer(Some(BusObserver::new(move |_| {
+            *counter.borrow_mut() += 1;
+        })));
+        cpu.set_tracer(Some(Tracer::new(|_| {})));
+
+        let info = cpu.step(&mut memory).unwrap();
+
+        assert_eq!(register.borrow().reads, 1);
+        assert_eq!(*accesses.borrow(), info.cycles);
+        assert_eq!(cpu.get_a(), 0x42);
+    }
+}